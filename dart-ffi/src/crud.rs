@@ -189,6 +189,14 @@ pub unsafe extern "C" fn isar_clear(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_update_stats(
+    collection: &'static IsarCollection,
+    txn: &mut IsarDartTxn,
+) -> i32 {
+    isar_try_txn!(txn, move |txn| { collection.update_stats(txn) })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_json_import(
     collection: &'static IsarCollection,
@@ -1,6 +1,6 @@
 use crate::from_c_str;
 use isar_core::collection::IsarCollection;
-use isar_core::error::illegal_arg;
+use isar_core::error::{illegal_arg, IsarError};
 use isar_core::object::data_type::DataType;
 use isar_core::object::isar_object::IsarObject;
 use isar_core::query::filter::*;
@@ -54,6 +54,29 @@ pub unsafe extern "C" fn isar_filter_link(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_null(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    not_null: bool,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let query_filter = if not_null {
+                IsNotNullCond::filter(*property)
+            } else {
+                IsNullCond::filter(*property)
+            };
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_filter_null_between(
     collection: &IsarCollection,
@@ -317,4 +340,357 @@ macro_rules! filter_string_ffi {
 filter_string_ffi!(StringStartsWithCond, isar_filter_string_starts_with);
 filter_string_ffi!(StringEndsWithCond, isar_filter_string_ends_with);
 filter_string_ffi!(StringMatchesCond, isar_filter_string_matches);
+filter_string_ffi!(StringContainsCond, isar_filter_string_contains);
 filter_string_ffi!(StringListContainsCond, isar_filter_string_list_contains);
+
+#[macro_export]
+macro_rules! filter_regex_ffi {
+    ($filter_name:ident, $function_name:ident) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $function_name(
+            collection: &IsarCollection,
+            filter: *mut *const Filter,
+            pattern: *const c_char,
+            case_sensitive: bool,
+            property_index: u32,
+        ) -> i32 {
+            let property = collection.get_properties().get(property_index as usize);
+            isar_try! {
+                if let Some((_, property)) = property {
+                    let pattern = from_c_str(pattern)?;
+                    let query_filter = isar_core::query::filter::$filter_name::filter(*property, pattern, case_sensitive)?;
+                    let ptr = Box::into_raw(Box::new(query_filter));
+                    filter.write(ptr);
+                } else {
+                    illegal_arg("Property does not exist.")?;
+                }
+            }
+        }
+    }
+}
+
+filter_regex_ffi!(StringRegexCond, isar_filter_string_regex);
+filter_regex_ffi!(StringListRegexCond, isar_filter_string_list_regex);
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_property_compare(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    property_index: u32,
+    comparison: u8,
+    other_property_index: u32,
+) -> i32 {
+    let properties = collection.get_properties();
+    let property = properties.get(property_index as usize);
+    let other_property = properties.get(other_property_index as usize);
+    isar_try! {
+        if let (Some((_, property)), Some((_, other_property))) = (property, other_property) {
+            let comparison = Comparison::from_ordinal(comparison)
+                .ok_or(IsarError::IllegalArg { message: "Unknown comparison.".to_string() })?;
+            let query_filter = PropertyCompareCond::filter(*property, comparison, *other_property)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_property_offset_between(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    property_index: u32,
+    other_property_index: u32,
+    lower: f64,
+    upper: f64,
+) -> i32 {
+    let properties = collection.get_properties();
+    let property = properties.get(property_index as usize);
+    let other_property = properties.get(other_property_index as usize);
+    isar_try! {
+        if let (Some((_, property)), Some((_, other_property))) = (property, other_property) {
+            let query_filter = PropertyOffsetBetweenCond::filter(*property, *other_property, lower, upper)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_param(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    name: *const c_char,
+    property_index: u32,
+    op: u8,
+    case_sensitive: bool,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let name = from_c_str(name)?;
+            let op = ParamOp::from_ordinal(op)
+                .ok_or(IsarError::IllegalArg { message: "Unknown parameter operation.".to_string() })?;
+            let query_filter = ParamCond::filter(name, *property, op, case_sensitive)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! filter_in_ffi {
+    ($filter_name:ident, $function_name:ident, $type:ty) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $function_name(
+            collection: &IsarCollection,
+            filter: *mut *const Filter,
+            values: *const $type,
+            length: u32,
+            property_index: u32,
+        ) -> i32 {
+            let property = collection.get_properties().get(property_index as usize);
+            isar_try! {
+                if let Some((_, property)) = property {
+                    let values = slice::from_raw_parts(values, length as usize);
+                    let query_filter = $filter_name::filter(*property, values)?;
+                    let ptr = Box::into_raw(Box::new(query_filter));
+                    filter.write(ptr);
+                } else {
+                    illegal_arg("Property does not exist.")?;
+                }
+            }
+        }
+    };
+}
+
+filter_in_ffi!(ByteInCond, isar_filter_byte_in, u8);
+filter_in_ffi!(IntInCond, isar_filter_int_in, i32);
+filter_in_ffi!(LongInCond, isar_filter_long_in, i64);
+filter_in_ffi!(FloatInCond, isar_filter_float_in, f32);
+filter_in_ffi!(DoubleInCond, isar_filter_double_in, f64);
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_string_in(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    values: *const *const c_char,
+    length: u32,
+    case_sensitive: bool,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let mut strings = vec![];
+            for value in slice::from_raw_parts(values, length as usize) {
+                let str = if !value.is_null() {
+                    Some(from_c_str(*value)?)
+                } else {
+                    None
+                };
+                strings.push(str);
+            }
+            let query_filter = StringInCond::filter(*property, &strings, case_sensitive)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_string_fuzzy(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    value: *const c_char,
+    max_distance: u32,
+    case_sensitive: bool,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let str = from_c_str(value)?;
+            let query_filter = StringFuzzyCond::filter(*property, str, max_distance as usize, case_sensitive)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! filter_list_between_ffi {
+    ($filter_name:ident, $function_name:ident, $type:ty) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $function_name(
+            collection: &IsarCollection,
+            filter: *mut *const Filter,
+            lower: $type,
+            upper: $type,
+            all: bool,
+            property_index: u32,
+        ) -> i32 {
+            let property = collection.get_properties().get(property_index as usize);
+            isar_try! {
+                if let Some((_, property)) = property {
+                    let query_filter = $filter_name::filter(*property, lower, upper, all)?;
+                    let ptr = Box::into_raw(Box::new(query_filter));
+                    filter.write(ptr);
+                } else {
+                    illegal_arg("Property does not exist.")?;
+                }
+            }
+        }
+    };
+}
+
+filter_list_between_ffi!(ByteListBetweenCond, isar_filter_byte_list_between, u8);
+filter_list_between_ffi!(IntListBetweenCond, isar_filter_int_list_between, i32);
+filter_list_between_ffi!(LongListBetweenCond, isar_filter_long_list_between, i64);
+filter_list_between_ffi!(FloatListBetweenCond, isar_filter_float_list_between, f32);
+filter_list_between_ffi!(DoubleListBetweenCond, isar_filter_double_list_between, f64);
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_string_list_between(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    lower: *const c_char,
+    upper: *const c_char,
+    case_sensitive: bool,
+    all: bool,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let lower = if !lower.is_null() {
+                Some(from_c_str(lower)?)
+            } else {
+                None
+            };
+            let upper = if !upper.is_null() {
+                Some(from_c_str(upper)?)
+            } else {
+                None
+            };
+            let query_filter = StringListBetweenCond::filter(*property, lower, upper, case_sensitive, all)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! filter_list_contains_values_ffi {
+    ($filter_name:ident, $function_name:ident, $type:ty) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $function_name(
+            collection: &IsarCollection,
+            filter: *mut *const Filter,
+            values: *const $type,
+            length: u32,
+            all: bool,
+            property_index: u32,
+        ) -> i32 {
+            let property = collection.get_properties().get(property_index as usize);
+            isar_try! {
+                if let Some((_, property)) = property {
+                    let values = slice::from_raw_parts(values, length as usize);
+                    let query_filter = $filter_name::filter(*property, values, all)?;
+                    let ptr = Box::into_raw(Box::new(query_filter));
+                    filter.write(ptr);
+                } else {
+                    illegal_arg("Property does not exist.")?;
+                }
+            }
+        }
+    };
+}
+
+filter_list_contains_values_ffi!(
+    ByteListContainsValuesCond,
+    isar_filter_byte_list_contains_values,
+    u8
+);
+filter_list_contains_values_ffi!(
+    IntListContainsValuesCond,
+    isar_filter_int_list_contains_values,
+    i32
+);
+filter_list_contains_values_ffi!(
+    LongListContainsValuesCond,
+    isar_filter_long_list_contains_values,
+    i64
+);
+filter_list_contains_values_ffi!(
+    FloatListContainsValuesCond,
+    isar_filter_float_list_contains_values,
+    f32
+);
+filter_list_contains_values_ffi!(
+    DoubleListContainsValuesCond,
+    isar_filter_double_list_contains_values,
+    f64
+);
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_string_list_contains_values(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    values: *const *const c_char,
+    length: u32,
+    case_sensitive: bool,
+    all: bool,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let mut strings = vec![];
+            for value in slice::from_raw_parts(values, length as usize) {
+                let str = if !value.is_null() {
+                    Some(from_c_str(*value)?)
+                } else {
+                    None
+                };
+                strings.push(str);
+            }
+            let query_filter = StringListContainsValuesCond::filter(*property, &strings, case_sensitive, all)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_filter_list_length(
+    collection: &IsarCollection,
+    filter: *mut *const Filter,
+    lower: u32,
+    upper: u32,
+    property_index: u32,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    isar_try! {
+        if let Some((_, property)) = property {
+            let query_filter = ListLengthCond::filter(*property, lower as usize, upper as usize)?;
+            let ptr = Box::into_raw(Box::new(query_filter));
+            filter.write(ptr);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
@@ -46,6 +46,25 @@ pub extern "C" fn isar_key_add_double(key: &mut IndexKey, value: f64) {
     key.add_double(value);
 }
 
+#[no_mangle]
+pub extern "C" fn isar_key_add_null(key: &mut IndexKey, position: u32) -> i32 {
+    isar_try! {
+        key.add_null(position as usize)?;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_key_add_param(
+    key: &mut IndexKey,
+    position: u32,
+    name: *const c_char,
+) -> i32 {
+    isar_try! {
+        let name = from_c_str(name)?;
+        key.add_param(position as usize, name)?;
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_key_add_string_value(
     key: &mut IndexKey,
@@ -1,12 +1,21 @@
-use super::raw_object_set::{RawObjectSet, RawObjectSetSend};
+use super::raw_object_set::{RawObject, RawObjectSet, RawObjectSetSend};
+use crate::from_c_str;
 use crate::txn::IsarDartTxn;
 use crate::UintSend;
 use isar_core::collection::IsarCollection;
-use isar_core::error::illegal_arg;
+use isar_core::error::{illegal_arg, IsarError, Result};
 use isar_core::index::index_key::IndexKey;
+use isar_core::instance::IsarInstance;
+use isar_core::object::collation::Collation;
+use isar_core::object::property_value::PropertyValue;
 use isar_core::query::filter::Filter;
+use isar_core::query::ngram_where_clause::NGramQuery;
+use isar_core::query::pagination::PageToken;
 use isar_core::query::query_builder::QueryBuilder;
 use isar_core::query::{Query, Sort};
+use serde_json::Value;
+use std::os::raw::c_char;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
 
 #[no_mangle]
 pub extern "C" fn isar_qb_create(collection: &IsarCollection) -> *mut QueryBuilder {
@@ -31,6 +40,25 @@ pub unsafe extern "C" fn isar_qb_add_id_where_clause(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_qb_add_param_id_where_clause(
+    builder: &mut QueryBuilder,
+    lower_param: *const c_char,
+    upper_param: *const c_char,
+    ascending: bool,
+) -> i32 {
+    let sort = if ascending {
+        Sort::Ascending
+    } else {
+        Sort::Descending
+    };
+    isar_try! {
+        let lower_param = from_c_str(lower_param)?;
+        let upper_param = from_c_str(upper_param)?;
+        builder.add_param_id_where_clause(lower_param, upper_param, sort);
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_qb_add_index_where_clause<'a>(
     builder: &'a mut QueryBuilder,
@@ -65,6 +93,28 @@ pub unsafe extern "C" fn isar_qb_add_index_where_clause<'a>(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_qb_add_ngram_where_clause(
+    builder: &mut QueryBuilder,
+    index_index: u32,
+    query_type: u8,
+    value: *const c_char,
+    max_distance: u32,
+) -> i32 {
+    isar_try! {
+        let value = from_c_str(value)?.to_string();
+        let query = match query_type {
+            0 => NGramQuery::Contains(value),
+            1 => NGramQuery::Wildcard(value),
+            _ => NGramQuery::Fuzzy {
+                value,
+                max_distance: max_distance as usize,
+            },
+        };
+        builder.add_ngram_where_clause(index_index as usize, query)?;
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_qb_set_filter(builder: &mut QueryBuilder, filter: *mut Filter) {
     let filter = *Box::from_raw(filter);
@@ -93,6 +143,31 @@ pub unsafe extern "C" fn isar_qb_add_sort_by(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_qb_add_collated_sort_by(
+    collection: &IsarCollection,
+    builder: &mut QueryBuilder,
+    property_index: u32,
+    asc: bool,
+    collation: u8,
+) -> i32 {
+    let property = collection.get_properties().get(property_index as usize);
+    let sort = if asc {
+        Sort::Ascending
+    } else {
+        Sort::Descending
+    };
+    isar_try! {
+        let collation = Collation::from_ordinal(collation)
+            .ok_or(IsarError::IllegalArg { message: "Unknown collation.".to_string() })?;
+        if let Some((_,property)) = property {
+            builder.add_collated_sort(*property, sort, collation);
+        } else {
+            illegal_arg("Property does not exist.")?;
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_qb_add_distinct_by(
     collection: &IsarCollection,
@@ -121,33 +196,150 @@ pub unsafe extern "C" fn isar_qb_set_offset_limit(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn isar_qb_build(builder: *mut QueryBuilder) -> *mut Query {
+pub unsafe extern "C" fn isar_qb_set_sort_memory_budget(builder: &mut QueryBuilder, bytes: u64) {
+    builder.set_sort_memory_budget(bytes as usize);
+}
+
+/// Queries are shared with the txn worker threads. Binding a parameter takes the write lock and
+/// waits until executions of the query which are still in flight have finished.
+pub struct IsarQuery(RwLock<Query>);
+
+impl IsarQuery {
+    fn new(query: Query) -> Self {
+        IsarQuery(RwLock::new(query))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<Query> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn bind(&self, name: &str, value: &PropertyValue) -> Result<()> {
+        let mut query = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        query.bind(name, value)
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_qb_build(builder: *mut QueryBuilder) -> *mut IsarQuery {
     let query = Box::from_raw(builder).build();
-    Box::into_raw(Box::new(query))
+    Box::into_raw(Box::new(IsarQuery::new(query)))
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn isar_q_free(query: *mut Query) {
+pub unsafe extern "C" fn isar_q_free(query: *mut IsarQuery) {
     Box::from_raw(query);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_bind_null(query: &IsarQuery, name: *const c_char) -> i32 {
+    isar_try! {
+        query.bind(from_c_str(name)?, &PropertyValue::Null)?;
+    }
+}
+
+#[macro_export]
+macro_rules! bind_ffi {
+    ($function_name:ident, $variant:ident, $type:ty) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $function_name(
+            query: &IsarQuery,
+            name: *const c_char,
+            value: $type,
+        ) -> i32 {
+            isar_try! {
+                query.bind(from_c_str(name)?, &PropertyValue::$variant(value))?;
+            }
+        }
+    };
+}
+
+bind_ffi!(isar_q_bind_byte, Byte, u8);
+bind_ffi!(isar_q_bind_int, Int, i32);
+bind_ffi!(isar_q_bind_long, Long, i64);
+bind_ffi!(isar_q_bind_float, Float, f32);
+bind_ffi!(isar_q_bind_double, Double, f64);
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_bind_string(
+    query: &IsarQuery,
+    name: *const c_char,
+    value: *const c_char,
+) -> i32 {
+    isar_try! {
+        let value = if !value.is_null() {
+            PropertyValue::String(from_c_str(value)?.to_string())
+        } else {
+            PropertyValue::Null
+        };
+        query.bind(from_c_str(name)?, &value)?;
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_q_find(
-    query: &'static Query,
+    query: &'static IsarQuery,
     txn: &mut IsarDartTxn,
     result: &'static mut RawObjectSet,
     limit: u32,
 ) -> i32 {
     let result = RawObjectSetSend(result);
     isar_try_txn!(txn, move |txn| {
-        result.0.fill_from_query(query, txn, limit as usize)?;
+        result
+            .0
+            .fill_from_query(&query.read(), txn, limit as usize)?;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_find_page(
+    query: &'static IsarQuery,
+    txn: &mut IsarDartTxn,
+    token_bytes: *const u8,
+    token_length: u32,
+    page_size: u32,
+    result: &'static mut RawObjectSet,
+    next_token_bytes: *mut *mut u8,
+    next_token_length: *mut u32,
+) -> i32 {
+    let token = if !token_bytes.is_null() {
+        let bytes = std::slice::from_raw_parts(token_bytes, token_length as usize);
+        Some(PageToken::from_bytes(bytes))
+    } else {
+        None
+    };
+    let result = RawObjectSetSend(result);
+    let next_token = JsonBytes(next_token_bytes);
+    let next_token_length = JsonLen(next_token_length);
+    isar_try_txn!(txn, move |txn| {
+        let (objects, next) = query
+            .read()
+            .find_page(txn, token.as_ref(), page_size as usize)?;
+        let objects = objects
+            .into_iter()
+            .map(|object| {
+                let mut raw_obj = RawObject::new();
+                raw_obj.set_object(Some(object));
+                raw_obj
+            })
+            .collect();
+        result.0.fill_from_vec(objects);
+        if let Some(next) = next {
+            let mut bytes = next.as_bytes().to_vec().into_boxed_slice();
+            next_token_length.0.write(bytes.len() as u32);
+            next_token.0.write(bytes.as_mut_ptr());
+            std::mem::forget(bytes);
+        } else {
+            next_token_length.0.write(0);
+            next_token.0.write(std::ptr::null_mut());
+        }
         Ok(())
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_q_delete(
-    query: &'static Query,
+    query: &'static IsarQuery,
     collection: &'static IsarCollection,
     txn: &mut IsarDartTxn,
     limit: u32,
@@ -156,16 +348,8 @@ pub unsafe extern "C" fn isar_q_delete(
     let limit = limit as usize;
     let count = UintSend(count);
     isar_try_txn!(txn, move |txn| {
-        let mut oids_to_delete = vec![];
-        query.find_while(txn, |object| {
-            let oid = object.read_long(collection.get_oid_property());
-            oids_to_delete.push(oid);
-            oids_to_delete.len() <= limit
-        })?;
-        *count.0 = oids_to_delete.len() as u32;
-        for oid in oids_to_delete {
-            collection.delete(txn, oid)?;
-        }
+        let query = query.read().with_limit(limit);
+        *count.0 = query.delete(txn, collection)? as u32;
         Ok(())
     })
 }
@@ -178,7 +362,7 @@ unsafe impl Send for JsonLen {}
 
 #[no_mangle]
 pub unsafe extern "C" fn isar_q_export_json(
-    query: &'static Query,
+    query: &'static IsarQuery,
     collection: &'static IsarCollection,
     txn: &mut IsarDartTxn,
     primitive_null: bool,
@@ -188,7 +372,9 @@ pub unsafe extern "C" fn isar_q_export_json(
     let json = JsonBytes(json_bytes);
     let json_length = JsonLen(json_length);
     isar_try_txn!(txn, move |txn| {
-        let exported_json = query.export_json(txn, collection, primitive_null, true)?;
+        let exported_json = query
+            .read()
+            .export_json(txn, collection, primitive_null, true)?;
         let bytes = serde_json::to_vec(&exported_json).unwrap();
         let mut bytes = bytes.into_boxed_slice();
         json_length.0.write(bytes.len() as u32);
@@ -198,7 +384,66 @@ pub unsafe extern "C" fn isar_q_export_json(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_explain(
+    query: &'static IsarQuery,
+    txn: &mut IsarDartTxn,
+    json_bytes: *mut *mut u8,
+    json_length: *mut u32,
+) -> i32 {
+    let json = JsonBytes(json_bytes);
+    let json_length = JsonLen(json_length);
+    isar_try_txn!(txn, move |txn| {
+        let explanation = query.read().explain(txn)?;
+        let bytes = serde_json::to_vec(&explanation).unwrap();
+        let mut bytes = bytes.into_boxed_slice();
+        json_length.0.write(bytes.len() as u32);
+        json.0.write(bytes.as_mut_ptr());
+        std::mem::forget(bytes);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_to_json(
+    query: &IsarQuery,
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    json_bytes: *mut *mut u8,
+    json_length: *mut u32,
+) -> i32 {
+    isar_try! {
+        let json = query.read().to_json(isar, collection)?;
+        let bytes = serde_json::to_vec(&json).unwrap();
+        let mut bytes = bytes.into_boxed_slice();
+        json_length.write(bytes.len() as u32);
+        json_bytes.write(bytes.as_mut_ptr());
+        std::mem::forget(bytes);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_from_json(
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    query: *mut *const IsarQuery,
+    json_bytes: *const u8,
+    json_length: u32,
+) -> i32 {
+    isar_try! {
+        let bytes = std::slice::from_raw_parts(json_bytes, json_length as usize);
+        let json: Value = serde_json::from_slice(bytes).map_err(|_| IsarError::InvalidJson {})?;
+        let restored = Query::from_json(isar, collection, &json)?;
+        query.write(Box::into_raw(Box::new(IsarQuery::new(restored))));
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn isar_free_json(json_bytes: *mut u8, json_length: u32) {
     Vec::from_raw_parts(json_bytes, json_length as usize, json_length as usize);
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_free_page_token(token_bytes: *mut u8, token_length: u32) {
+    Vec::from_raw_parts(token_bytes, token_length as usize, token_length as usize);
+}
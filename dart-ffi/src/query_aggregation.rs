@@ -1,160 +1,68 @@
+use crate::query::IsarQuery;
 use crate::txn::IsarDartTxn;
 use enum_ordinalize::Ordinalize;
 use isar_core::collection::IsarCollection;
-use isar_core::error::Result;
-use isar_core::object::data_type::DataType;
-use isar_core::object::isar_object::{IsarObject, Property};
-use isar_core::query::Query;
-use isar_core::txn::IsarTxn;
-use std::cmp::Ordering;
-
-#[derive(Debug)]
-pub enum AggregationResult {
-    Long(i64),
-    Double(f64),
-    Null,
-}
+use isar_core::error::illegal_arg;
+use isar_core::object::isar_object::IsarObject;
+use isar_core::query::aggregation::{AggregationOp, AggregationResult};
+use std::ffi::CString;
+use std::os::raw::c_char;
 
 #[derive(Ordinalize, PartialEq)]
 #[repr(u8)]
-pub enum AggregationOp {
+pub enum DartAggregationOp {
     Min,
     Max,
     Sum,
     Average,
     Count,
-}
-
-fn aggregate(
-    query: &Query,
-    txn: &mut IsarTxn,
-    op: AggregationOp,
-    property: Option<Property>,
-) -> Result<AggregationResult> {
-    let mut count = 0usize;
-
-    let (mut long_value, mut double_value) = if op == AggregationOp::Min {
-        (i64::MAX, f64::INFINITY)
-    } else if op == AggregationOp::Max {
-        (i64::MIN, f64::NEG_INFINITY)
-    } else {
-        (0, 0.0)
-    };
-
-    let min_max_cmp = if op == AggregationOp::Max {
-        Ordering::Greater
-    } else {
-        Ordering::Less
-    };
-
-    query.find_while(txn, |obj| {
-        if op == AggregationOp::Count {
-            count += 1;
-            return true;
-        }
-
-        let property = property.unwrap();
-        if obj.is_null(property) {
-            return true;
-        }
-
-        count += 1;
-        match op {
-            AggregationOp::Min | AggregationOp::Max => match property.data_type {
-                DataType::Int | DataType::Long => {
-                    let value = if property.data_type == DataType::Int {
-                        obj.read_int(property) as i64
-                    } else {
-                        obj.read_long(property)
-                    };
-                    if value.cmp(&long_value) == min_max_cmp {
-                        long_value = value;
-                    }
-                }
-                DataType::Float | DataType::Double => {
-                    let value = if property.data_type == DataType::Float {
-                        obj.read_float(property) as f64
-                    } else {
-                        obj.read_double(property)
-                    };
-                    if value > double_value && min_max_cmp == Ordering::Greater {
-                        double_value = value;
-                    } else if value < double_value && min_max_cmp == Ordering::Less {
-                        double_value = value;
-                    }
-                }
-                _ => unreachable!(),
-            },
-            AggregationOp::Sum | AggregationOp::Average => match property.data_type {
-                DataType::Int => {
-                    long_value = long_value.saturating_add(obj.read_int(property) as i64)
-                }
-                DataType::Long => long_value = long_value.saturating_add(obj.read_long(property)),
-                DataType::Float => double_value += obj.read_float(property) as f64,
-                DataType::Double => double_value += obj.read_double(property),
-                _ => unreachable!(),
-            },
-            _ => unreachable!(),
-        }
-        true
-    })?;
-
-    match op {
-        AggregationOp::Min | AggregationOp::Max | AggregationOp::Average => {
-            if count == 0 {
-                return Ok(AggregationResult::Null);
-            }
-        }
-        _ => {}
-    }
-
-    let result = match op {
-        AggregationOp::Average => {
-            let result = match property.unwrap().data_type {
-                DataType::Int | DataType::Long => (long_value as f64) / (count as f64),
-                DataType::Float | DataType::Double => double_value / (count as f64),
-                _ => unreachable!(),
-            };
-            AggregationResult::Double(result)
-        }
-        AggregationOp::Count => AggregationResult::Long(count as i64),
-        _ => match property.unwrap().data_type {
-            DataType::Int | DataType::Long => AggregationResult::Long(long_value),
-            DataType::Float | DataType::Double => AggregationResult::Double(double_value),
-            _ => unreachable!(),
-        },
-    };
-
-    Ok(result)
+    CountDistinct,
+    Median,
+    Percentile,
+    StandardDeviation,
 }
 
 pub struct AggregationResultSend(*mut *const AggregationResult);
 
 unsafe impl Send for AggregationResultSend {}
 
+/// `Count` counts the non-null values of the property or all results if `property_index` does not
+/// refer to a property.
 #[no_mangle]
 pub unsafe extern "C" fn isar_q_aggregate(
     collection: &IsarCollection,
-    query: &'static Query,
+    query: &'static IsarQuery,
     txn: &mut IsarDartTxn,
     operation: u8,
     property_index: u32,
+    percentile: f64,
     result: *mut *const AggregationResult,
 ) -> i32 {
-    let op = AggregationOp::from_ordinal(operation).unwrap();
-    let property = if op != AggregationOp::Count {
-        let (_, p) = collection
-            .get_properties()
-            .get(property_index as usize)
-            .unwrap();
-        Some(*p)
-    } else {
-        None
+    let op = match DartAggregationOp::from_ordinal(operation) {
+        Some(DartAggregationOp::Min) => Some(AggregationOp::Min),
+        Some(DartAggregationOp::Max) => Some(AggregationOp::Max),
+        Some(DartAggregationOp::Sum) => Some(AggregationOp::Sum),
+        Some(DartAggregationOp::Average) => Some(AggregationOp::Average),
+        Some(DartAggregationOp::Count) => Some(AggregationOp::Count),
+        Some(DartAggregationOp::CountDistinct) => Some(AggregationOp::CountDistinct),
+        Some(DartAggregationOp::Median) => Some(AggregationOp::Median),
+        Some(DartAggregationOp::Percentile) => Some(AggregationOp::Percentile(percentile)),
+        Some(DartAggregationOp::StandardDeviation) => Some(AggregationOp::StandardDeviation),
+        None => None,
     };
+    let property = collection
+        .get_properties()
+        .get(property_index as usize)
+        .map(|(_, p)| *p);
 
     let result = AggregationResultSend(result);
     isar_try_txn!(txn, move |txn| {
-        let aggregate_result = aggregate(query, txn, op, property)?;
+        let op = if let Some(op) = op {
+            op
+        } else {
+            return illegal_arg("Unknown aggregation.");
+        };
+        let aggregate_result = query.read().aggregate(txn, op, property)?;
         result.0.write(Box::into_raw(Box::new(aggregate_result)));
         Ok(())
     })
@@ -165,7 +73,7 @@ pub unsafe extern "C" fn isar_q_aggregate_long_result(result: &AggregationResult
     match result {
         AggregationResult::Long(long) => *long,
         AggregationResult::Double(double) => *double as i64,
-        AggregationResult::Null => IsarObject::NULL_LONG,
+        _ => IsarObject::NULL_LONG,
     }
 }
 
@@ -174,6 +82,21 @@ pub unsafe extern "C" fn isar_q_aggregate_double_result(result: &AggregationResu
     match result {
         AggregationResult::Long(long) => *long as f64,
         AggregationResult::Double(double) => *double,
-        AggregationResult::Null => IsarObject::NULL_DOUBLE,
+        _ => IsarObject::NULL_DOUBLE,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_q_aggregate_string_result(result: &AggregationResult) -> *mut c_char {
+    match result {
+        AggregationResult::String(string) => CString::new(string.as_str()).unwrap().into_raw(),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn isar_free_aggregate_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
     }
 }
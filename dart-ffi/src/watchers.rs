@@ -1,7 +1,7 @@
 use crate::dart::{dart_post_int, DartPort};
+use crate::query::IsarQuery;
 use isar_core::collection::IsarCollection;
 use isar_core::instance::IsarInstance;
-use isar_core::query::Query;
 use isar_core::watch::WatchHandle;
 
 #[no_mangle]
@@ -40,12 +40,12 @@ pub unsafe extern "C" fn isar_watch_object(
 pub extern "C" fn isar_watch_query(
     isar: &IsarInstance,
     collection: &IsarCollection,
-    query: &Query,
+    query: &IsarQuery,
     port: DartPort,
 ) -> *mut WatchHandle {
     let handle = isar.watch_query(
         collection,
-        query.clone(),
+        query.read().clone(),
         Box::new(move || {
             dart_post_int(port, 1);
         }),
@@ -1,11 +1,14 @@
 use crate::error::{illegal_arg, IsarError, Result};
 use crate::index::index_key::IndexKey;
+use crate::index::index_stats::{update_object_count, write_object_count, IndexStats};
 use crate::link::Link;
 use crate::lmdb::{verify_id, IntKey, MAX_ID, MIN_ID};
 use crate::object::isar_object::{IsarObject, Property};
 use crate::object::json_encode_decode::JsonEncodeDecode;
 use crate::object::object_builder::ObjectBuilder;
 use crate::object::object_info::ObjectInfo;
+use crate::object::property_value::PropertyValue;
+use crate::query::full_text_query::{FullTextExpr, FullTextQuery};
 use crate::query::id_where_clause::IdWhereClause;
 use crate::query::query_builder::QueryBuilder;
 use crate::query::Sort;
@@ -66,6 +69,10 @@ impl IsarCollection {
         }
     }
 
+    pub(crate) fn get_links(&self) -> &[(String, Link)] {
+        &self.links
+    }
+
     fn get_links_and_backlinks(&self) -> impl Iterator<Item = &Link> {
         self.links
             .iter()
@@ -93,6 +100,21 @@ impl IsarCollection {
         QueryBuilder::new(self)
     }
 
+    pub fn new_full_text_query(
+        &self,
+        index_index: usize,
+        expr: FullTextExpr,
+    ) -> Result<FullTextQuery> {
+        let index = self
+            .indexes
+            .get(index_index)
+            .filter(|i| i.is_full_text())
+            .ok_or(IsarError::IllegalArg {
+                message: "Full-text index does not exist".to_string(),
+            })?;
+        Ok(FullTextQuery::new(index.clone(), expr))
+    }
+
     pub fn new_index_key(&self, index_index: usize) -> Option<IndexKey> {
         self.indexes.get(index_index).map(|i| IndexKey::new(i))
     }
@@ -101,6 +123,9 @@ impl IsarCollection {
         if key.index.get_col_id() != self.id {
             return illegal_arg("Invalid IndexKey for this collection");
         }
+        if !key.params.is_empty() {
+            return illegal_arg("IndexKey parameters are only supported by where clauses.");
+        }
         Ok(())
     }
 
@@ -178,10 +203,45 @@ impl IsarCollection {
         cursors
             .data
             .put(IntKey::new(self.id, oid), object.as_bytes())?;
+        update_object_count(&mut cursors.info, self.id, 1)?;
         self.register_object_change(change_set, oid, object);
         Ok(())
     }
 
+    /// Assigns `values` to the objects with the given oids. Objects that do not exist are
+    /// skipped. Returns the number of updated objects.
+    pub(crate) fn update_internal(
+        &self,
+        txn: &mut IsarTxn,
+        oids: &[i64],
+        values: &[(Property, PropertyValue)],
+    ) -> Result<usize> {
+        txn.write(|cursors, mut change_set| {
+            let mut buffer = None;
+            let mut count = 0;
+            for oid in oids {
+                let object =
+                    if let Some((_, object)) = cursors.data.move_to(IntKey::new(self.id, *oid))? {
+                        IsarObject::from_bytes(object)
+                    } else {
+                        continue;
+                    };
+                let mut builder = self.new_object_builder(buffer.take());
+                for (_, property) in self.get_properties() {
+                    if let Some((_, value)) = values.iter().find(|(p, _)| p == property) {
+                        builder.write_value(value);
+                    } else {
+                        builder.write_value(&PropertyValue::read(object, *property));
+                    }
+                }
+                self.put_internal(cursors, change_set.as_deref_mut(), builder.finish())?;
+                buffer = Some(builder.recycle());
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
     pub fn delete(&self, txn: &mut IsarTxn, oid: i64) -> Result<bool> {
         txn.write(|cursors, change_set| self.delete_internal(cursors, true, change_set, oid))
     }
@@ -218,6 +278,7 @@ impl IsarCollection {
             }
             self.register_object_change(change_set, oid, object);
             cursors.data.delete_current()?;
+            update_object_count(&mut cursors.info, self.id, -1)?;
             Ok(true)
         } else {
             Ok(false)
@@ -315,10 +376,38 @@ impl IsarCollection {
                     Ok(true)
                 },
             )?;
+            write_object_count(&mut cursors.info, self.id, 0)?;
             Ok(counter)
         })
     }
 
+    /// Recounts all objects and rebuilds the statistics of every index.
+    pub fn update_stats(&self, txn: &mut IsarTxn) -> Result<()> {
+        txn.write(|cursors, _| {
+            let mut count = 0;
+            IdWhereClause::new(self, MIN_ID, MAX_ID, Sort::Ascending).iter(
+                &mut cursors.data,
+                None,
+                |_, _, _| {
+                    count += 1;
+                    Ok(true)
+                },
+            )?;
+            write_object_count(&mut cursors.info, self.id, count)?;
+            for index in &self.indexes {
+                index.update_stats(cursors)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_index_stats(&self, txn: &mut IsarTxn, index_index: usize) -> Result<IndexStats> {
+        let index = self.indexes.get(index_index).ok_or(IsarError::IllegalArg {
+            message: "Index does not exist".to_string(),
+        })?;
+        txn.read(|cursors| index.get_stats(cursors))
+    }
+
     pub fn import_json(&self, txn: &mut IsarTxn, json: Value) -> Result<()> {
         txn.write(|cursors, mut change_set| {
             let array = json.as_array().ok_or(IsarError::InvalidJson {})?;
@@ -35,6 +35,9 @@ pub enum IsarError {
     #[error("IllegalArg: {message:?}.")]
     IllegalArg { message: String },
 
+    #[error("QueryParseError at {position:?}: {message:?}")]
+    QueryParseError { message: String, position: usize },
+
     #[error("Invalid JSON.")]
     InvalidJson {},
 
@@ -49,6 +52,9 @@ pub enum IsarError {
     )]
     CryptoError {},
 
+    #[error("IoError: {message:?}")]
+    IoError { message: String },
+
     #[error("LmdbError ({code:?}): {message:?}")]
     LmdbError { code: i32, message: String },
 }
@@ -69,6 +75,14 @@ impl From<LmdbError> for IsarError {
     }
 }
 
+impl From<std::io::Error> for IsarError {
+    fn from(e: std::io::Error) -> Self {
+        IsarError::IoError {
+            message: e.to_string(),
+        }
+    }
+}
+
 pub fn illegal_arg<T>(msg: &str) -> Result<T> {
     Err(IsarError::IllegalArg {
         message: msg.to_string(),
@@ -0,0 +1,193 @@
+use crate::schema::collection_schema::{AnalyzerSchema, StemmerLanguage};
+use hashbrown::HashSet;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::sync::Arc;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+pub trait TokenFilter: Send + Sync {
+    /// Returns the transformed token or `None` if the token should be dropped.
+    fn apply(&self, token: String) -> Option<String>;
+
+    /// Used for prefix queries where the token is only the beginning of a word.
+    fn apply_to_prefix(&self, token: String) -> Option<String> {
+        self.apply(token)
+    }
+}
+
+pub struct LowercaseFilter;
+
+impl TokenFilter for LowercaseFilter {
+    fn apply(&self, token: String) -> Option<String> {
+        Some(token.to_lowercase())
+    }
+}
+
+pub struct NormalizationFilter {
+    fold_diacritics: bool,
+}
+
+impl NormalizationFilter {
+    pub fn new(fold_diacritics: bool) -> Self {
+        NormalizationFilter { fold_diacritics }
+    }
+}
+
+impl TokenFilter for NormalizationFilter {
+    fn apply(&self, token: String) -> Option<String> {
+        if self.fold_diacritics {
+            let folded = token
+                .nfkd()
+                .filter(|c| !is_combining_mark(*c))
+                .nfc()
+                .collect();
+            Some(folded)
+        } else {
+            Some(token.nfkc().collect())
+        }
+    }
+}
+
+pub struct StopWordFilter {
+    stop_words: HashSet<String>,
+}
+
+impl StopWordFilter {
+    pub fn new(stop_words: &[String]) -> Self {
+        StopWordFilter {
+            stop_words: stop_words.iter().cloned().collect(),
+        }
+    }
+}
+
+impl TokenFilter for StopWordFilter {
+    fn apply(&self, token: String) -> Option<String> {
+        if self.stop_words.contains(&token) {
+            None
+        } else {
+            Some(token)
+        }
+    }
+
+    fn apply_to_prefix(&self, token: String) -> Option<String> {
+        Some(token)
+    }
+}
+
+pub struct StemmingFilter {
+    stemmer: Stemmer,
+}
+
+impl StemmingFilter {
+    pub fn new(language: StemmerLanguage) -> Self {
+        let algorithm = match language {
+            StemmerLanguage::Danish => Algorithm::Danish,
+            StemmerLanguage::Dutch => Algorithm::Dutch,
+            StemmerLanguage::English => Algorithm::English,
+            StemmerLanguage::Finnish => Algorithm::Finnish,
+            StemmerLanguage::French => Algorithm::French,
+            StemmerLanguage::German => Algorithm::German,
+            StemmerLanguage::Italian => Algorithm::Italian,
+            StemmerLanguage::Norwegian => Algorithm::Norwegian,
+            StemmerLanguage::Portuguese => Algorithm::Portuguese,
+            StemmerLanguage::Russian => Algorithm::Russian,
+            StemmerLanguage::Spanish => Algorithm::Spanish,
+            StemmerLanguage::Swedish => Algorithm::Swedish,
+        };
+        StemmingFilter {
+            stemmer: Stemmer::create(algorithm),
+        }
+    }
+}
+
+impl TokenFilter for StemmingFilter {
+    fn apply(&self, token: String) -> Option<String> {
+        Some(self.stemmer.stem(&token).into_owned())
+    }
+
+    fn apply_to_prefix(&self, token: String) -> Option<String> {
+        Some(token)
+    }
+}
+
+/// Splits text into words and runs every word through a chain of [TokenFilter]s.
+#[derive(Clone)]
+pub struct Analyzer {
+    filters: Vec<Arc<dyn TokenFilter>>,
+}
+
+impl Analyzer {
+    pub fn new(filters: Vec<Arc<dyn TokenFilter>>) -> Self {
+        Analyzer { filters }
+    }
+
+    pub(crate) fn from_schema(schema: &AnalyzerSchema, case_sensitive: bool) -> Self {
+        let mut filters: Vec<Arc<dyn TokenFilter>> = vec![];
+        if schema.normalize || schema.fold_diacritics {
+            filters.push(Arc::new(NormalizationFilter::new(schema.fold_diacritics)));
+        }
+        if !case_sensitive {
+            filters.push(Arc::new(LowercaseFilter));
+        }
+        if !schema.stop_words.is_empty() {
+            filters.push(Arc::new(StopWordFilter::new(&schema.stop_words)));
+        }
+        if let Some(language) = schema.stemmer {
+            filters.push(Arc::new(StemmingFilter::new(language)));
+        }
+        Analyzer::new(filters)
+    }
+
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        text.unicode_words()
+            .filter_map(|word| self.analyze_token(word))
+            .collect()
+    }
+
+    pub fn analyze_token(&self, token: &str) -> Option<String> {
+        let mut token = token.to_string();
+        for filter in &self.filters {
+            token = filter.apply(token)?;
+        }
+        Self::check_token(token)
+    }
+
+    pub fn analyze_prefix(&self, prefix: &str) -> Option<String> {
+        let mut prefix = prefix.to_string();
+        for filter in &self.filters {
+            prefix = filter.apply_to_prefix(prefix)?;
+        }
+        Self::check_token(prefix)
+    }
+
+    fn check_token(token: String) -> Option<String> {
+        if token.is_empty() || token.contains('\0') {
+            None
+        } else {
+            Some(token)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let analyzer = Analyzer::from_schema(&AnalyzerSchema::english(), false);
+        let tokens = analyzer.analyze("The Runners were running to the Café.");
+        assert_eq!(tokens, vec!["runner", "were", "run", "cafe"]);
+
+        let analyzer = Analyzer::from_schema(&AnalyzerSchema::default(), true);
+        let tokens = analyzer.analyze("The Café");
+        assert_eq!(tokens, vec!["The", "Café"]);
+    }
+}
@@ -1,12 +1,30 @@
-use crate::index::Index;
+use crate::error::{illegal_arg, Result};
 use crate::index::MAX_STRING_INDEX_SIZE;
+use crate::index::{Index, IndexProperty};
+use crate::object::data_type::DataType;
+use crate::object::isar_object::IsarObject;
+use crate::object::property_value::PropertyValue;
+use crate::schema::collection_schema::IndexType;
 use std::hash::Hasher;
 use wyhash::{wyhash, WyHash};
 
+/// Maximum key size of LMDB.
+const MAX_KEY_SIZE: usize = 511;
+
 #[derive(Clone)]
 pub struct IndexKey<'a> {
     pub(crate) index: &'a Index,
     pub(crate) bytes: Vec<u8>,
+    pub(crate) params: Vec<KeyParam>,
+}
+
+/// Placeholder for the value of the index property at `position` which is inserted at `offset`
+/// once the parameter `name` is bound.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct KeyParam {
+    pub(crate) offset: usize,
+    pub(crate) position: usize,
+    pub(crate) name: String,
 }
 
 impl<'a> IndexKey<'a> {
@@ -14,6 +32,7 @@ impl<'a> IndexKey<'a> {
         IndexKey {
             index: &index,
             bytes: index.get_prefix(),
+            params: vec![],
         }
     }
 
@@ -23,9 +42,44 @@ impl<'a> IndexKey<'a> {
         IndexKey {
             index: &index,
             bytes: buffer,
+            params: vec![],
         }
     }
 
+    /// Builds the key of `template` with the bound `values` of its parameters.
+    pub(crate) fn from_template(
+        index: &'a Index,
+        template: &[u8],
+        params: &[KeyParam],
+        values: &[(String, Option<PropertyValue>)],
+        mut buffer: Vec<u8>,
+    ) -> Result<Self> {
+        buffer.clear();
+        let mut key = IndexKey {
+            index,
+            bytes: buffer,
+            params: vec![],
+        };
+        let mut last = 0;
+        for param in params {
+            key.bytes.extend_from_slice(&template[last..param.offset]);
+            let value = values
+                .iter()
+                .find(|(name, _)| *name == param.name)
+                .and_then(|(_, value)| value.as_ref());
+            if let Some(value) = value {
+                key.add_value(&index.properties[param.position], value)?;
+            } else {
+                return illegal_arg("Parameter is not bound.");
+            }
+            last = param.offset;
+        }
+        key.bytes.extend_from_slice(&template[last..]);
+        // templates padded by add_max_suffix() grow beyond the maximum key size
+        key.bytes.truncate(MAX_KEY_SIZE);
+        Ok(key)
+    }
+
     pub fn add_byte(&mut self, value: u8) {
         self.bytes.push(value);
     }
@@ -90,12 +144,14 @@ impl<'a> IndexKey<'a> {
 
     pub fn add_string_value(&mut self, value: Option<&str>, case_sensitive: bool) {
         if let Some(value) = value {
-            let value = if case_sensitive {
-                value.to_string()
+            let value = if let Some(collation) = self.index.get_collation() {
+                collation.create_key(value, case_sensitive)
+            } else if case_sensitive {
+                value.as_bytes().to_vec()
             } else {
-                value.to_lowercase()
+                value.to_lowercase().into_bytes()
             };
-            let bytes = value.as_bytes();
+            let bytes = value.as_slice();
             self.bytes.push(1);
             if bytes.len() >= MAX_STRING_INDEX_SIZE {
                 self.bytes
@@ -112,6 +168,90 @@ impl<'a> IndexKey<'a> {
         }
     }
 
+    /// Adds the key of a null value of the property at `position` of the index.
+    pub fn add_null(&mut self, position: usize) -> Result<()> {
+        let index = self.index;
+        if let Some(ip) = index.properties.get(position) {
+            if self.add_null_for(ip) {
+                return Ok(());
+            }
+        }
+        illegal_arg("Index property cannot contain null values.")
+    }
+
+    /// Returns `false` if the index property does not store null values as a key.
+    pub(crate) fn add_null_for(&mut self, ip: &IndexProperty) -> bool {
+        match (ip.property.data_type, ip.index_type) {
+            (DataType::Byte, _) => self.add_byte(IsarObject::NULL_BYTE),
+            (DataType::Int, _) => self.add_int(IsarObject::NULL_INT),
+            (DataType::Long, _) => self.add_long(IsarObject::NULL_LONG),
+            (DataType::Float, _) => self.add_float(IsarObject::NULL_FLOAT),
+            (DataType::Double, _) => self.add_double(IsarObject::NULL_DOUBLE),
+            (DataType::String, IndexType::Value) => self.add_string_value(None, false),
+            (DataType::String, IndexType::Hash) => self.add_string_hash(None, false),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Adds a placeholder for the value of the property at `position` of the index. The value is
+    /// inserted when the parameter `name` is bound to the query.
+    pub fn add_param(&mut self, position: usize, name: &str) -> Result<()> {
+        let index = self.index;
+        match index.properties.get(position) {
+            Some(ip)
+                if !ip.property.data_type.is_list()
+                    && matches!(ip.index_type, IndexType::Value | IndexType::Hash) =>
+            {
+                self.params.push(KeyParam {
+                    offset: self.bytes.len(),
+                    position,
+                    name: name.to_string(),
+                });
+                Ok(())
+            }
+            _ => illegal_arg("Index property does not support parameters."),
+        }
+    }
+
+    /// Adds the key of `value` for the index property `ip`.
+    pub(crate) fn add_value(&mut self, ip: &IndexProperty, value: &PropertyValue) -> Result<()> {
+        let case_sensitive = ip.case_sensitive.unwrap_or(true);
+        match (value, ip.property.data_type, ip.index_type) {
+            (PropertyValue::Null, _, _) => {
+                if !self.add_null_for(ip) {
+                    return illegal_arg("Index property cannot contain null values.");
+                }
+            }
+            (PropertyValue::Byte(value), DataType::Byte, _) => self.add_byte(*value),
+            (PropertyValue::Int(value), DataType::Int, _) => self.add_int(*value),
+            (PropertyValue::Long(value), DataType::Long, _) => self.add_long(*value),
+            (PropertyValue::Float(value), DataType::Float, _) => self.add_float(*value),
+            (PropertyValue::Double(value), DataType::Double, _) => self.add_double(*value),
+            (PropertyValue::String(value), DataType::String, IndexType::Value) => {
+                self.add_string_value(Some(value), case_sensitive)
+            }
+            (PropertyValue::String(value), DataType::String, IndexType::Hash) => {
+                self.add_string_hash(Some(value), case_sensitive)
+            }
+            _ => return illegal_arg("Value does not match the index property."),
+        }
+        Ok(())
+    }
+
+    /// Makes this key greater than every key it is a prefix of.
+    pub(crate) fn add_max_suffix(&mut self) {
+        if self.bytes.len() < MAX_KEY_SIZE {
+            self.bytes.resize(MAX_KEY_SIZE, u8::MAX);
+        }
+    }
+
+    pub fn add_full_text_term(&mut self, term: &str, frequency: u32) {
+        self.bytes.extend_from_slice(term.as_bytes());
+        self.bytes.push(0);
+        self.bytes.extend_from_slice(&frequency.to_be_bytes());
+    }
+
     pub fn add_string_word(&mut self, value: &str, case_sensitive: bool) {
         if case_sensitive {
             self.bytes.extend_from_slice(value.as_bytes());
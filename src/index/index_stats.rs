@@ -0,0 +1,246 @@
+use crate::error::{IsarError, Result};
+use crate::lmdb::cursor::Cursor;
+use crate::lmdb::ByteKey;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+/*
+Statistics live in the info db under "stats" + id (u16 BE) + stat type. Entry and distinct key
+counts are updated with every write. Histograms are equi-depth: every bucket holds the same number
+of entries and is described by its greatest key. They are only rebuilt on request because that
+requires a scan of the whole index.
+ */
+
+const STATS_PREFIX: &[u8] = b"stats";
+const STAT_ENTRIES: u8 = 0;
+const STAT_DISTINCT_KEYS: u8 = 1;
+const STAT_HISTOGRAM: u8 = 2;
+const STAT_OBJECTS: u8 = 3;
+const STAT_FULL_TEXT_DOCS: u8 = 4;
+const STAT_FULL_TEXT_TOKENS: u8 = 5;
+
+pub(crate) const HISTOGRAM_BUCKETS: usize = 32;
+
+fn stat_key(id: u16, stat: u8) -> Vec<u8> {
+    let mut key = STATS_PREFIX.to_vec();
+    key.extend_from_slice(&id.to_be_bytes());
+    key.push(stat);
+    key
+}
+
+fn read_count(info: &mut Cursor, id: u16, stat: u8) -> Result<u64> {
+    let key = stat_key(id, stat);
+    let count = info
+        .move_to(ByteKey::new(&key))?
+        .map(|(_, v)| u64::from_le_bytes(v.try_into().unwrap()))
+        .unwrap_or(0);
+    Ok(count)
+}
+
+fn write_count(info: &mut Cursor, id: u16, stat: u8, count: u64) -> Result<()> {
+    let key = stat_key(id, stat);
+    info.put(ByteKey::new(&key), &count.to_le_bytes())
+}
+
+fn update_count(info: &mut Cursor, id: u16, stat: u8, delta: i64) -> Result<()> {
+    if delta == 0 {
+        return Ok(());
+    }
+    let count = read_count(info, id, stat)?;
+    let count = if delta > 0 {
+        count.saturating_add(delta as u64)
+    } else {
+        count.saturating_sub(delta.unsigned_abs())
+    };
+    write_count(info, id, stat, count)
+}
+
+pub(crate) fn read_object_count(info: &mut Cursor, col_id: u16) -> Result<u64> {
+    read_count(info, col_id, STAT_OBJECTS)
+}
+
+pub(crate) fn update_object_count(info: &mut Cursor, col_id: u16, delta: i64) -> Result<()> {
+    update_count(info, col_id, STAT_OBJECTS, delta)
+}
+
+pub(crate) fn write_object_count(info: &mut Cursor, col_id: u16, count: u64) -> Result<()> {
+    write_count(info, col_id, STAT_OBJECTS, count)
+}
+
+/// Returns the number of documents and tokens of a full-text index.
+pub(crate) fn read_full_text_counts(info: &mut Cursor, index_id: u16) -> Result<(u64, u64)> {
+    let docs = read_count(info, index_id, STAT_FULL_TEXT_DOCS)?;
+    let tokens = read_count(info, index_id, STAT_FULL_TEXT_TOKENS)?;
+    Ok((docs, tokens))
+}
+
+pub(crate) fn update_full_text_counts(
+    info: &mut Cursor,
+    index_id: u16,
+    docs: i64,
+    tokens: i64,
+) -> Result<()> {
+    update_count(info, index_id, STAT_FULL_TEXT_DOCS, docs)?;
+    update_count(info, index_id, STAT_FULL_TEXT_TOKENS, tokens)
+}
+
+pub(crate) fn write_full_text_counts(
+    info: &mut Cursor,
+    index_id: u16,
+    docs: u64,
+    tokens: u64,
+) -> Result<()> {
+    write_count(info, index_id, STAT_FULL_TEXT_DOCS, docs)?;
+    write_count(info, index_id, STAT_FULL_TEXT_TOKENS, tokens)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Histogram {
+    entries: u64,
+    lower_key: Vec<u8>,
+    upper_keys: Vec<Vec<u8>>,
+}
+
+impl Histogram {
+    /// Builds a histogram from the sorted keys of an index containing `entries` entries.
+    pub(crate) fn build<'a>(entries: u64, keys: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let bucket_size = ((entries as f64 / HISTOGRAM_BUCKETS as f64).ceil() as u64).max(1);
+        let mut lower_key = None;
+        let mut upper_keys = vec![];
+        let mut last_key: &[u8] = &[];
+        let mut count = 0;
+        for key in keys {
+            if lower_key.is_none() {
+                lower_key = Some(key.to_vec());
+            }
+            count += 1;
+            if count % bucket_size == 0 {
+                upper_keys.push(key.to_vec());
+            }
+            last_key = key;
+        }
+        if count % bucket_size != 0 {
+            upper_keys.push(last_key.to_vec());
+        }
+        Histogram {
+            entries: count,
+            lower_key: lower_key.unwrap_or_default(),
+            upper_keys,
+        }
+    }
+
+    /// Estimated fraction of entries between `lower` and `upper` (both inclusive).
+    pub fn estimate_fraction(&self, lower: &[u8], upper: &[u8]) -> f64 {
+        if self.upper_keys.is_empty() || lower > upper {
+            return 0.0;
+        }
+        let mut buckets = 0.0;
+        let mut bucket_lower = self.lower_key.as_slice();
+        for (i, bucket_upper) in self.upper_keys.iter().enumerate() {
+            let bucket_upper = bucket_upper.as_slice();
+            let starts_after = if i == 0 {
+                bucket_lower > upper
+            } else {
+                bucket_lower >= upper
+            };
+            if bucket_upper < lower || starts_after {
+                // bucket does not overlap
+            } else if lower <= bucket_lower && bucket_upper <= upper {
+                buckets += 1.0;
+            } else {
+                buckets += 0.5;
+            }
+            bucket_lower = bucket_upper;
+        }
+        buckets / self.upper_keys.len() as f64
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexStats {
+    pub entries: u64,
+    pub distinct_keys: u64,
+    pub histogram: Option<Histogram>,
+}
+
+impl IndexStats {
+    pub(crate) fn read(info: &mut Cursor, index_id: u16) -> Result<Self> {
+        let entries = read_count(info, index_id, STAT_ENTRIES)?;
+        let distinct_keys = read_count(info, index_id, STAT_DISTINCT_KEYS)?;
+        let key = stat_key(index_id, STAT_HISTOGRAM);
+        let histogram = if let Some((_, bytes)) = info.move_to(ByteKey::new(&key))? {
+            let histogram = serde_json::from_slice(bytes).map_err(|_| IsarError::DbCorrupted {
+                message: "Could not deserialize index histogram.".to_string(),
+            })?;
+            Some(histogram)
+        } else {
+            None
+        };
+        Ok(IndexStats {
+            entries,
+            distinct_keys,
+            histogram,
+        })
+    }
+
+    pub(crate) fn update_counts(
+        info: &mut Cursor,
+        index_id: u16,
+        entries: i64,
+        distinct_keys: i64,
+    ) -> Result<()> {
+        update_count(info, index_id, STAT_ENTRIES, entries)?;
+        update_count(info, index_id, STAT_DISTINCT_KEYS, distinct_keys)
+    }
+
+    pub(crate) fn write(&self, info: &mut Cursor, index_id: u16) -> Result<()> {
+        write_count(info, index_id, STAT_ENTRIES, self.entries)?;
+        write_count(info, index_id, STAT_DISTINCT_KEYS, self.distinct_keys)?;
+        let key = stat_key(index_id, STAT_HISTOGRAM);
+        if let Some(histogram) = &self.histogram {
+            let bytes = serde_json::to_vec(histogram).unwrap();
+            info.put(ByteKey::new(&key), &bytes)
+        } else {
+            if info.move_to(ByteKey::new(&key))?.is_some() {
+                info.delete_current()?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Estimated number of entries with exactly one key.
+    pub fn estimate_equal(&self) -> f64 {
+        self.entries as f64 / self.distinct_keys.max(1) as f64
+    }
+
+    /// Estimated number of entries between `lower` and `upper` (both inclusive).
+    pub fn estimate_between(&self, lower: &[u8], upper: &[u8]) -> f64 {
+        if lower == upper {
+            self.estimate_equal()
+        } else if let Some(histogram) = &self.histogram {
+            histogram.estimate_fraction(lower, upper) * self.entries as f64
+        } else {
+            self.entries as f64 / 3.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let keys = (0u8..100).map(|i| vec![i]).collect::<Vec<_>>();
+        let histogram = Histogram::build(100, keys.iter().map(|k| k.as_slice()));
+        assert_eq!(histogram.upper_keys.len(), 25);
+
+        assert_eq!(histogram.estimate_fraction(&[0], &[99]), 1.0);
+        assert_eq!(histogram.estimate_fraction(&[100], &[200]), 0.0);
+        let fraction = histogram.estimate_fraction(&[10], &[29]);
+        assert!(fraction > 0.15 && fraction < 0.25);
+
+        let empty = Histogram::build(0, vec![]);
+        assert_eq!(empty.estimate_fraction(&[0], &[99]), 0.0);
+    }
+}
@@ -1,22 +1,35 @@
 use crate::error::{IsarError, Result};
+use crate::index::analyzer::Analyzer;
 use crate::index::index_key::IndexKey;
+use crate::index::index_stats::{
+    read_full_text_counts, update_full_text_counts, write_full_text_counts, Histogram, IndexStats,
+};
 use crate::lmdb::{ByteKey, IntKey, Key};
+use crate::object::collation::Collation;
 use crate::object::data_type::DataType;
 use crate::object::isar_object::{IsarObject, Property};
+use crate::object::property_value::PropertyValue;
+use crate::query::filter::{Condition, Filter};
 use crate::query::index_where_clause::IndexWhereClause;
 use crate::query::Sort;
 use crate::schema::collection_schema::IndexType;
 use crate::txn::Cursors;
+use hashbrown::HashMap;
 use itertools::Itertools;
+use std::convert::TryInto;
 use unicode_segmentation::UnicodeSegmentation;
 
 #[cfg(test)]
 use {crate::txn::IsarTxn, crate::utils::debug::dump_db, hashbrown::HashSet};
 
+pub mod analyzer;
 pub mod index_key;
+pub mod index_stats;
 
 pub const MAX_STRING_INDEX_SIZE: usize = 1024;
 
+pub const NGRAM_SIZE: usize = 3;
+
 /*
 
 Null values are always considered the "smallest" element.
@@ -28,6 +41,7 @@ pub struct IndexProperty {
     pub property: Property,
     pub index_type: IndexType,
     pub case_sensitive: Option<bool>,
+    pub collation: Option<Collation>,
 }
 
 impl IndexProperty {
@@ -35,11 +49,13 @@ impl IndexProperty {
         property: Property,
         index_type: IndexType,
         case_sensitive: Option<bool>,
+        collation: Option<Collation>,
     ) -> Self {
         IndexProperty {
             property,
             index_type,
             case_sensitive,
+            collation,
         }
     }
 
@@ -54,15 +70,25 @@ impl IndexProperty {
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone)]
 pub(crate) struct Index {
     pub id: u16,
     col_id: u16,
     pub properties: Vec<IndexProperty>,
     pub unique: bool,
     pub replace: bool,
+    analyzer: Option<Analyzer>,
+    filter: Option<Filter>,
+}
+
+impl PartialEq for Index {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.col_id == other.col_id
+    }
 }
 
+impl Eq for Index {}
+
 impl Index {
     pub fn new(
         id: u16,
@@ -70,6 +96,8 @@ impl Index {
         properties: Vec<IndexProperty>,
         unique: bool,
         replace: bool,
+        analyzer: Option<Analyzer>,
+        filter: Option<Filter>,
     ) -> Self {
         Index {
             id,
@@ -77,6 +105,8 @@ impl Index {
             properties,
             unique,
             replace,
+            analyzer,
+            filter,
         }
     }
 
@@ -89,7 +119,64 @@ impl Index {
     }
 
     pub fn multiple(&self) -> bool {
-        self.properties.first().unwrap().index_type == IndexType::Words
+        let ip = self.properties.first().unwrap();
+        ip.index_type == IndexType::Words
+            || ip.index_type == IndexType::FullText
+            || ip.index_type == IndexType::NGram
+            || ip.property.data_type.is_list()
+    }
+
+    /// String value indexes may contain at most one string value property which is always the last.
+    pub fn get_collation(&self) -> Option<Collation> {
+        self.properties
+            .iter()
+            .find(|ip| ip.index_type == IndexType::Value && ip.collation.is_some())
+            .and_then(|ip| ip.collation)
+    }
+
+    pub fn is_full_text(&self) -> bool {
+        self.analyzer.is_some()
+    }
+
+    pub(crate) fn get_analyzer(&self) -> Option<&Analyzer> {
+        self.analyzer.as_ref()
+    }
+
+    /// Partial indexes only contain objects matching their filter.
+    pub(crate) fn get_filter(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    fn is_indexed(&self, object: IsarObject) -> Result<bool> {
+        if let Some(filter) = &self.filter {
+            filter.evaluate(object, None)
+        } else {
+            Ok(true)
+        }
+    }
+
+    pub(crate) fn get_full_text_tokens(&self, object: IsarObject) -> Vec<String> {
+        let ip = self.properties.first().unwrap();
+        object
+            .read_string(ip.property)
+            .map(|str| self.analyzer.as_ref().unwrap().analyze(str))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of indexed documents and the total number of indexed tokens.
+    pub(crate) fn get_full_text_stats(&self, cursors: &mut Cursors) -> Result<(u64, u64)> {
+        read_full_text_counts(&mut cursors.info, self.id)
+    }
+
+    fn update_full_text_stats(
+        &self,
+        cursors: &mut Cursors,
+        object: IsarObject,
+        added: bool,
+    ) -> Result<()> {
+        let token_count = self.get_full_text_tokens(object).len() as i64;
+        let sign = if added { 1 } else { -1 };
+        update_full_text_counts(&mut cursors.info, self.id, sign, sign * token_count)
     }
 
     pub fn create_for_object<F>(
@@ -102,11 +189,28 @@ impl Index {
     where
         F: FnMut(&mut Cursors, i64) -> Result<()>,
     {
+        if !self.is_indexed(object)? {
+            return Ok(());
+        }
         let id_key = IntKey::new(self.col_id, oid);
+        let mut entries = 0;
+        let mut distinct_keys = 0;
         self.create_keys(object, |key| {
-            self.create_for_object_key(cursors, id_key, ByteKey::new(key), &mut delete_existing)?;
+            let (added_entries, added_keys) = self.create_for_object_key(
+                cursors,
+                id_key,
+                ByteKey::new(key),
+                &mut delete_existing,
+            )?;
+            entries += added_entries;
+            distinct_keys += added_keys;
             Ok(true)
-        })
+        })?;
+        IndexStats::update_counts(&mut cursors.info, self.id, entries, distinct_keys)?;
+        if self.is_full_text() {
+            self.update_full_text_stats(cursors, object, true)?;
+        }
+        Ok(())
     }
 
     fn create_for_object_key<F>(
@@ -115,23 +219,25 @@ impl Index {
         id_key: IntKey,
         key: ByteKey,
         mut delete_existing: F,
-    ) -> Result<()>
+    ) -> Result<(i64, i64)>
     where
         F: FnMut(&mut Cursors, i64) -> Result<()>,
     {
         if self.unique {
             let success = cursors.index.put_no_override(key, id_key.as_bytes())?;
-            if !success {
-                if self.replace {
-                    delete_existing(cursors, id_key.get_id())?;
-                } else {
-                    return Err(IsarError::UniqueViolated {});
-                }
+            if success {
+                return Ok((1, 1));
+            } else if self.replace {
+                delete_existing(cursors, id_key.get_id())?;
+            } else {
+                return Err(IsarError::UniqueViolated {});
             }
+            Ok((0, 0))
         } else {
+            let new_key = cursors.index.move_to(key)?.is_none();
             cursors.index.put(key, id_key.as_bytes())?;
+            Ok((1, if new_key { 1 } else { 0 }))
         }
-        Ok(())
     }
 
     pub fn delete_for_object(
@@ -140,31 +246,107 @@ impl Index {
         oid: i64,
         object: IsarObject,
     ) -> Result<()> {
+        if !self.is_indexed(object)? {
+            return Ok(());
+        }
         let key = IntKey::new(self.col_id, oid);
         let oid_bytes = key.as_bytes();
+        let mut entries = 0;
+        let mut distinct_keys = 0;
         self.create_keys(object, |key| {
             let entry = cursors
                 .index
                 .move_to_key_val(ByteKey::new(key), &oid_bytes)?;
             if entry.is_some() {
                 cursors.index.delete_current()?;
+                entries -= 1;
+                if cursors.index.move_to(ByteKey::new(key))?.is_none() {
+                    distinct_keys -= 1;
+                }
             }
             Ok(true)
-        })
+        })?;
+        IndexStats::update_counts(&mut cursors.info, self.id, entries, distinct_keys)?;
+        if self.is_full_text() {
+            self.update_full_text_stats(cursors, object, false)?;
+        }
+        Ok(())
+    }
+
+    /// Words indexes of databases before version 2 used the words without index prefix as keys.
+    pub(crate) fn delete_legacy_word_keys(
+        &self,
+        cursors: &mut Cursors,
+        oid: i64,
+        object: IsarObject,
+    ) -> Result<()> {
+        let key = IntKey::new(self.col_id, oid);
+        let oid_bytes = key.as_bytes();
+        let value = self
+            .properties
+            .first()
+            .unwrap()
+            .get_string_with_case(object);
+        let mut result = Ok(());
+        Self::create_word_keys(value.as_deref(), |word| {
+            result = match cursors.index.move_to_key_val(ByteKey::new(word), oid_bytes) {
+                Ok(Some(_)) => cursors.index.delete_current(),
+                Ok(None) => Ok(()),
+                Err(err) => Err(err),
+            };
+            result.is_ok()
+        });
+        result
+    }
+
+    /// Where clause containing every entry of this index.
+    fn get_full_where_clause(&self) -> Result<IndexWhereClause> {
+        let mut upper = IndexKey::new(self);
+        upper.add_max_suffix();
+        IndexWhereClause::new(IndexKey::new(self), upper, false, Sort::Ascending)
     }
 
     pub fn clear(&self, cursors: &mut Cursors) -> Result<()> {
-        IndexWhereClause::new(
-            IndexKey::new(self),
-            IndexKey::new(self),
-            false,
-            Sort::Ascending,
-        )?
-        .iter_ids(&mut cursors.index, |cursor, _| {
-            cursor.delete_current()?;
-            Ok(true)
-        })?;
-        Ok(())
+        self.get_full_where_clause()?
+            .iter_ids(&mut cursors.index, |cursor, _| {
+                cursor.delete_current()?;
+                Ok(true)
+            })?;
+        write_full_text_counts(&mut cursors.info, self.id, 0, 0)?;
+        IndexStats::default().write(&mut cursors.info, self.id)
+    }
+
+    pub(crate) fn get_stats(&self, cursors: &mut Cursors) -> Result<IndexStats> {
+        IndexStats::read(&mut cursors.info, self.id)
+    }
+
+    /// Recounts all entries and rebuilds the histogram of this index.
+    pub(crate) fn update_stats(&self, cursors: &mut Cursors) -> Result<IndexStats> {
+        let mut entries = 0;
+        let mut distinct_keys = 0;
+        let mut keys = vec![];
+        self.get_full_where_clause()?
+            .iter_keys(&mut cursors.index, |key, new_key| {
+                entries += 1;
+                if new_key {
+                    distinct_keys += 1;
+                }
+                keys.push(key);
+                Ok(true)
+            })?;
+
+        let histogram = if self.is_full_text() || self.multiple() {
+            None
+        } else {
+            Some(Histogram::build(entries, keys))
+        };
+        let stats = IndexStats {
+            entries,
+            distinct_keys,
+            histogram,
+        };
+        stats.write(&mut cursors.info, self.id)?;
+        Ok(stats)
     }
 
     pub fn create_keys(
@@ -219,22 +401,167 @@ impl Index {
         key.bytes
     }
 
+    /// Whether the value of `property` can be restored from the keys of this index. Only the last
+    /// property of an index may be a string because strings have no fixed size.
+    pub(crate) fn covers_property(&self, property: Property) -> bool {
+        if self.multiple() {
+            return false;
+        }
+        let last = self.properties.len() - 1;
+        self.properties.iter().enumerate().any(|(i, ip)| {
+            ip.property == property
+                && (ip.property.data_type != DataType::String
+                    || (i == last
+                        && ip.index_type == IndexType::Value
+                        && ip.case_sensitive == Some(true)
+                        && ip.collation.is_none()))
+        })
+    }
+
+    /// Restores the values of all properties from a key created by [Index::create_single_key].
+    /// Values which cannot be restored, for example truncated strings, are `None`.
+    pub(crate) fn read_key_values(&self, key: &[u8]) -> Vec<Option<PropertyValue>> {
+        let mut values = vec![];
+        let mut bytes = &key[self.get_prefix().len()..];
+        for (i, ip) in self.properties.iter().enumerate() {
+            let value = match ip.property.data_type {
+                DataType::Byte => Some(PropertyValue::Byte(bytes[0])),
+                DataType::Int => {
+                    let unsigned = u32::from_be_bytes(bytes[..4].try_into().unwrap());
+                    Some(PropertyValue::int((unsigned ^ 1 << 31) as i32))
+                }
+                DataType::Long => {
+                    let unsigned = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+                    Some(PropertyValue::long((unsigned ^ 1 << 63) as i64))
+                }
+                DataType::Float => {
+                    let bits = u32::from_be_bytes(bytes[..4].try_into().unwrap());
+                    let value = if bits == 0 {
+                        f32::NAN
+                    } else if bits >= 2u32.pow(31) {
+                        f32::from_bits(bits - 2u32.pow(31))
+                    } else {
+                        -f32::from_bits(!(bits + 2u32.pow(31)))
+                    };
+                    Some(PropertyValue::float(value))
+                }
+                DataType::Double => {
+                    let bits = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+                    let value = if bits == 0 {
+                        f64::NAN
+                    } else if bits >= 2u64.pow(63) {
+                        f64::from_bits(bits - 2u64.pow(63))
+                    } else {
+                        -f64::from_bits(!(bits + 2u64.pow(63)))
+                    };
+                    Some(PropertyValue::double(value))
+                }
+                DataType::String if ip.index_type == IndexType::Hash => None,
+                DataType::String if i + 1 == self.properties.len() => {
+                    if !self.covers_property(ip.property) {
+                        None
+                    } else if bytes[0] == 0 {
+                        Some(PropertyValue::Null)
+                    } else if bytes.len() - 2 < MAX_STRING_INDEX_SIZE {
+                        let value = &bytes[1..bytes.len() - 1];
+                        std::str::from_utf8(value)
+                            .ok()
+                            .map(|s| PropertyValue::String(s.to_string()))
+                    } else {
+                        None
+                    }
+                }
+                _ => {
+                    values.resize(self.properties.len(), None);
+                    return values;
+                }
+            };
+            values.push(value);
+            let size = match ip.property.data_type {
+                DataType::String => 8,
+                data_type => data_type.get_static_size(),
+            };
+            bytes = &bytes[size.min(bytes.len())..];
+        }
+        values
+    }
+
     fn create_multiple_keys(
         &self,
         object: IsarObject,
         mut callback: impl FnMut(&[u8]) -> Result<bool>,
     ) -> Result<()> {
         let ip = self.properties.first().unwrap();
-        let value = ip.get_string_with_case(object);
-        let mut result = Ok(());
-        Self::create_word_keys(value.as_deref(), |word_key| match callback(word_key) {
-            Ok(cont) => cont,
-            Err(err) => {
-                result = Err(err);
-                false
+        let mut keys = vec![];
+        let mut add_key = |add: &mut dyn FnMut(&mut IndexKey)| {
+            let mut key = IndexKey::new(self);
+            add(&mut key);
+            keys.push(key.bytes);
+        };
+        match ip.property.data_type {
+            DataType::String if ip.index_type == IndexType::FullText => {
+                let mut term_frequencies = HashMap::new();
+                for token in self.get_full_text_tokens(object) {
+                    *term_frequencies.entry(token).or_insert(0u32) += 1;
+                }
+                for (term, frequency) in term_frequencies {
+                    add_key(&mut |key| key.add_full_text_term(&term, frequency));
+                }
             }
-        });
-        result
+            DataType::String if ip.index_type == IndexType::NGram => {
+                let value = ip.get_string_with_case(object);
+                for gram in Self::create_ngrams(value.as_deref().unwrap_or_default()) {
+                    add_key(&mut |key| key.bytes.extend_from_slice(gram.as_bytes()));
+                }
+            }
+            DataType::String => {
+                let value = ip.get_string_with_case(object);
+                Self::create_word_keys(value.as_deref(), |word| {
+                    add_key(&mut |key| key.bytes.extend_from_slice(word));
+                    true
+                });
+            }
+            DataType::IntList => {
+                for value in object.read_int_list(ip.property).unwrap_or_default() {
+                    add_key(&mut |key| key.add_int(value));
+                }
+            }
+            DataType::FloatList => {
+                for value in object.read_float_list(ip.property).unwrap_or_default() {
+                    add_key(&mut |key| key.add_float(value));
+                }
+            }
+            DataType::LongList => {
+                for value in object.read_long_list(ip.property).unwrap_or_default() {
+                    add_key(&mut |key| key.add_long(value));
+                }
+            }
+            DataType::DoubleList => {
+                for value in object.read_double_list(ip.property).unwrap_or_default() {
+                    add_key(&mut |key| key.add_double(value));
+                }
+            }
+            DataType::StringList => {
+                let case_sensitive = ip.case_sensitive.unwrap();
+                for value in object.read_string_list(ip.property).unwrap_or_default() {
+                    add_key(&mut |key| match ip.index_type {
+                        IndexType::Hash => key.add_string_hash(value, case_sensitive),
+                        _ => key.add_string_value(value, case_sensitive),
+                    });
+                }
+            }
+            _ => unimplemented!(),
+        }
+
+        // every element produces at most one entry per object
+        keys.sort_unstable();
+        keys.dedup();
+        for key in keys {
+            if !callback(&key)? {
+                break;
+            }
+        }
+        Ok(())
     }
 
     pub fn create_word_keys(value: Option<&str>, mut callback: impl FnMut(&[u8]) -> bool) {
@@ -247,6 +574,16 @@ impl Index {
         }
     }
 
+    /// Returns the distinct n-grams of `value`. Strings shorter than [NGRAM_SIZE] have none.
+    pub fn create_ngrams(value: &str) -> Vec<String> {
+        let chars = value.chars().collect_vec();
+        chars
+            .windows(NGRAM_SIZE)
+            .map(|gram| gram.iter().collect::<String>())
+            .unique()
+            .collect()
+    }
+
     #[cfg(test)]
     pub fn debug_dump(&self, txn: &mut IsarTxn) -> HashSet<(Vec<u8>, Vec<u8>)> {
         txn.read(|cursors| {
@@ -362,6 +699,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_for_object_list() {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::IntList; ind!(field)));
+        let mut builder = col.new_object_builder(None);
+        builder.write_long(1);
+        builder.write_int_list(Some(&[3, 1, 3, 2]));
+        let object = builder.finish();
+        assert_eq!(col.debug_get_index(0).debug_create_keys(object).len(), 3);
+        check_index(&isar, col, object);
+        isar.close();
+    }
+
+    #[test]
+    fn test_create_for_object_string_list() {
+        fn test(str_type: IndexType, str_lc: bool) {
+            isar!(isar, col => col!(oid => DataType::Long, field => DataType::StringList; ind!(str field, str_type, Some(str_lc))));
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(1);
+            builder.write_string_list(Some(&[Some("Hello"), None, Some("hello"), Some("TEST")]));
+            check_index(&isar, col, builder.finish());
+            isar.close();
+        }
+
+        for str_type in &[IndexType::Value, IndexType::Hash] {
+            test(*str_type, false);
+            test(*str_type, true);
+        }
+    }
+
+    #[test]
+    fn test_collated_string_index() {
+        use crate::object::collation::Collation;
+        use crate::query::Sort;
+        use crate::schema::collection_schema::*;
+
+        let index = IndexSchema::new(
+            vec![IndexPropertySchema::collated(
+                "field",
+                true,
+                Collation::Swedish,
+            )],
+            false,
+            false,
+        );
+        let properties = vec![
+            PropertySchema::new("oid", DataType::Long),
+            PropertySchema::new("field", DataType::String),
+        ];
+        let schema = CollectionSchema::new("col", "oid", properties, vec![index], vec![]);
+        isar!(isar, col => schema.clone());
+
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for (oid, value) in ["öl", "zoo", "Apa", "åka", "apa"].iter().enumerate() {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid as i64 + 1);
+            builder.write_string(Some(*value));
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let field = col.get_properties().get(1).unwrap().1;
+        let mut qb = col.new_query_builder();
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_string_value(Some("a"), true);
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_string_value(Some("åz"), true);
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Ascending)
+            .unwrap();
+        let index_order = qb
+            .build()
+            .find_all_vec(&mut txn)
+            .unwrap()
+            .iter()
+            .map(|o| o.read_string(field).unwrap().to_string())
+            .collect_vec();
+        assert_eq!(index_order, vec!["apa", "Apa", "zoo", "åka"]);
+
+        let mut qb = col.new_query_builder();
+        qb.add_collated_sort(field, Sort::Ascending, Collation::Swedish);
+        let sort_order = qb
+            .build()
+            .find_all_vec(&mut txn)
+            .unwrap()
+            .iter()
+            .map(|o| o.read_string(field).unwrap().to_string())
+            .collect_vec();
+        assert_eq!(sort_order, vec!["apa", "Apa", "zoo", "åka", "öl"]);
+
+        txn.abort();
+        isar.close();
+    }
+
+    #[test]
+    fn test_partial_index() {
+        use crate::query::filter::IntBetweenCond;
+        use crate::query::Sort;
+        use crate::schema::collection_schema::*;
+
+        let filter = IndexFilterSchema::IntBetween {
+            property: "status".to_string(),
+            lower: 1,
+            upper: 1,
+        };
+        let index = IndexSchema::partial(
+            vec![IndexPropertySchema::new("field", IndexType::Value, None)],
+            false,
+            false,
+            filter,
+        );
+        let properties = vec![
+            PropertySchema::new("oid", DataType::Long),
+            PropertySchema::new("field", DataType::Long),
+            PropertySchema::new("status", DataType::Int),
+        ];
+        let schema = CollectionSchema::new("col", "oid", properties, vec![index], vec![]);
+        isar!(isar, col => schema.clone());
+
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for (oid, status) in [1, 0, 1, 2].iter().enumerate() {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid as i64 + 1);
+            builder.write_long(oid as i64 * 10);
+            builder.write_int(*status);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        assert_eq!(col.debug_get_index(0).debug_dump(&mut txn).len(), 2);
+
+        let status = col.get_properties().get(2).unwrap().1;
+        let mut find = |filter: Option<i32>| {
+            let mut qb = col.new_query_builder();
+            let mut lower = col.new_index_key(0).unwrap();
+            lower.add_long(0);
+            let mut upper = col.new_index_key(0).unwrap();
+            upper.add_long(20);
+            qb.add_index_where_clause(lower, true, upper, true, false, Sort::Ascending)
+                .unwrap();
+            if let Some(value) = filter {
+                qb.set_filter(IntBetweenCond::filter(status, value, value).unwrap());
+            }
+            qb.build()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+        assert_eq!(find(Some(1)), vec![1, 3]);
+        assert_eq!(find(Some(0)), vec![2]);
+        assert_eq!(find(None), vec![1, 2, 3]);
+
+        txn.abort();
+        isar.close();
+    }
+
     #[test]
     fn test_create_for_object_unique() {}
 
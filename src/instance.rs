@@ -137,6 +137,10 @@ impl IsarInstance {
         self.collections.get(collection_index)
     }
 
+    pub(crate) fn get_collections(&self) -> &[IsarCollection] {
+        &self.collections
+    }
+
     pub fn get_collection_by_name(&self, collection_name: &str) -> Option<&IsarCollection> {
         self.collections
             .iter()
@@ -234,6 +238,7 @@ impl DataDbs {
             data2: self.data.cursor(&txn)?,
             index: self.index.cursor(&txn)?,
             links: self.links.cursor(&txn)?,
+            info: self.info.cursor(&txn)?,
         })
     }
 
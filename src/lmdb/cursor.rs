@@ -60,6 +60,22 @@ impl<'txn> Cursor<'txn> {
         self.op_get(ffi::MDB_GET_BOTH, Some(key.as_bytes()), Some(val))
     }
 
+    /// Moves to `key` and its first value greater than or equal to `val`. Only for dupsort
+    /// databases.
+    pub fn move_to_key_val_gte(
+        &mut self,
+        key: impl Key,
+        val: &[u8],
+    ) -> Result<Option<KeyVal<'txn>>> {
+        let result = self.op_get(ffi::MDB_GET_BOTH_RANGE, Some(key.as_bytes()), Some(val))?;
+        // MDB_GET_BOTH_RANGE returns the passed key instead of the stored one
+        if result.is_some() {
+            self.op_get(ffi::MDB_GET_CURRENT, None, None)
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn move_to_gte(&mut self, key: impl Key) -> Result<Option<KeyVal<'txn>>> {
         self.op_get(ffi::MDB_SET_RANGE, Some(key.as_bytes()), None)
     }
@@ -76,6 +92,15 @@ impl<'txn> Cursor<'txn> {
         self.op_get(ffi::MDB_PREV_NODUP, None, None)
     }
 
+    /// Moves to the last value of the current key.
+    pub fn move_to_last_dup(&mut self) -> Result<Option<KeyVal<'txn>>> {
+        if self.op_get(ffi::MDB_LAST_DUP, None, None)?.is_some() {
+            self.op_get(ffi::MDB_GET_CURRENT, None, None)
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn move_to_last(&mut self) -> Result<Option<KeyVal<'txn>>> {
         self.op_get(ffi::MDB_LAST, None, None)
     }
@@ -199,6 +224,39 @@ impl<'txn> Cursor<'txn> {
         }
     }
 
+    /// Moves to the first entry between `lower_key` and `upper_key` if `first` is true and to the
+    /// next entry otherwise. Allows to resume an iteration as long as the cursor is not moved.
+    pub fn iter_between_step<K: Key>(
+        &mut self,
+        lower_key: K,
+        upper_key: K,
+        skip_duplicates: bool,
+        ascending: bool,
+        first: bool,
+    ) -> Result<Option<KeyVal<'txn>>> {
+        if first {
+            return self.iter_between_first(lower_key, upper_key, ascending);
+        }
+
+        let next = match (ascending, skip_duplicates) {
+            (true, true) => ffi::MDB_NEXT_NODUP,
+            (true, false) => ffi::MDB_NEXT,
+            (false, true) => ffi::MDB_PREV_NODUP,
+            (false, false) => ffi::MDB_PREV,
+        };
+        if let Some((key, val)) = self.op_get(next, None, None)? {
+            if (ascending && upper_key.cmp_bytes(key) == Ordering::Less)
+                || (!ascending && lower_key.cmp_bytes(key) == Ordering::Greater)
+            {
+                Ok(None)
+            } else {
+                Ok(Some((key, val)))
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn iter_dups<'a>(
         &mut self,
         key: impl Key,
@@ -0,0 +1,187 @@
+use enum_ordinalize::Ordinalize;
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/*
+
+Collation keys consist of three levels separated by LEVEL_SEPARATOR:
+
+1. Primary: base letters. Every weight is four bytes, the first byte is the character class.
+2. Secondary: diacritics. One byte per character or per combining mark.
+3. Tertiary: case. One byte per character, only present if the comparison is case sensitive.
+
+All class bytes are greater than LEVEL_SEPARATOR so shorter strings sort before longer ones.
+
+ */
+
+const LEVEL_SEPARATOR: u8 = 1;
+
+const CLASS_SYMBOL: u8 = 2;
+const CLASS_DIGIT: u8 = 3;
+const CLASS_LETTER: u8 = 4;
+
+const NO_MARK: u8 = 5;
+const LOWER_CASE: u8 = 5;
+const UPPER_CASE: u8 = 6;
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize_repr, Deserialize_repr, Debug, Ordinalize)]
+#[repr(u8)]
+pub enum Collation {
+    Root = 0,
+    Danish = 1,
+    Finnish = 2,
+    German = 3,
+    Norwegian = 4,
+    Spanish = 5,
+    Swedish = 6,
+}
+
+impl Collation {
+    /// Letters which the locale sorts as separate letters after `base`.
+    fn get_tailoring(&self) -> &'static [(char, char)] {
+        match self {
+            Collation::Root | Collation::German => &[],
+            Collation::Danish | Collation::Norwegian => &[('æ', 'z'), ('ø', 'z'), ('å', 'z')],
+            Collation::Finnish | Collation::Swedish => &[('å', 'z'), ('ä', 'z'), ('ö', 'z')],
+            Collation::Spanish => &[('ñ', 'n')],
+        }
+    }
+
+    fn get_expansion(&self, c: char) -> Option<&'static str> {
+        match c {
+            'ß' => Some("ss"),
+            'æ' if self.get_tailoring().is_empty() => Some("ae"),
+            'œ' => Some("oe"),
+            _ => None,
+        }
+    }
+
+    fn letter_weight(base: char, offset: u32) -> [u8; 4] {
+        let value = ((base as u32) << 3) | offset;
+        let bytes = value.to_be_bytes();
+        [CLASS_LETTER, bytes[1], bytes[2], bytes[3]]
+    }
+
+    fn primary_weight(c: char) -> [u8; 4] {
+        if let Some(digit) = c.to_digit(10) {
+            [CLASS_DIGIT, 0, 0, digit as u8]
+        } else if c.is_alphabetic() {
+            Self::letter_weight(c, 0)
+        } else {
+            let bytes = (c as u32).to_be_bytes();
+            [CLASS_SYMBOL, bytes[1], bytes[2], bytes[3]]
+        }
+    }
+
+    pub fn create_key(&self, value: &str, case_sensitive: bool) -> Vec<u8> {
+        let tailoring = self.get_tailoring();
+        let mut primary = vec![];
+        let mut secondary = vec![];
+        let mut tertiary = vec![];
+
+        for c in value.nfc() {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let case = if lower != c { UPPER_CASE } else { LOWER_CASE };
+
+            let tailored = tailoring.iter().position(|(letter, _)| *letter == lower);
+            if let Some(position) = tailored {
+                let (_, base) = tailoring[position];
+                primary.extend_from_slice(&Self::letter_weight(base, position as u32 + 1));
+                secondary.push(NO_MARK);
+                tertiary.push(case);
+            } else if let Some(expansion) = self.get_expansion(lower) {
+                for e in expansion.chars() {
+                    primary.extend_from_slice(&Self::primary_weight(e));
+                    secondary.push(NO_MARK);
+                    tertiary.push(case);
+                }
+            } else {
+                let mut marks = false;
+                for d in lower.to_string().nfd() {
+                    if is_combining_mark(d) {
+                        let mark = (d as u32).saturating_sub(0x300).min(0xef) as u8;
+                        secondary.push(0x10 + mark);
+                        marks = true;
+                    } else {
+                        primary.extend_from_slice(&Self::primary_weight(d));
+                    }
+                }
+                if !marks {
+                    secondary.push(NO_MARK);
+                }
+                tertiary.push(case);
+            }
+        }
+
+        let mut key = primary;
+        key.push(LEVEL_SEPARATOR);
+        key.extend_from_slice(&secondary);
+        if case_sensitive {
+            key.push(LEVEL_SEPARATOR);
+            key.extend_from_slice(&tertiary);
+        }
+        key
+    }
+
+    pub fn compare(&self, s1: &str, s2: &str, case_sensitive: bool) -> Ordering {
+        let key1 = self.create_key(s1, case_sensitive);
+        let key2 = self.create_key(s2, case_sensitive);
+        key1.cmp(&key2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collation: Collation, values: &[&str]) -> Vec<String> {
+        let mut values = values.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        values.sort_by(|s1, s2| collation.compare(s1, s2, true));
+        values
+    }
+
+    #[test]
+    fn test_root_collation() {
+        let values = [
+            "Zebra", "äpfel", "apple", "Apple", "Éclair", "eclair", "b", "10", "ab",
+        ];
+        assert_eq!(
+            sorted(Collation::Root, &values),
+            vec!["10", "ab", "äpfel", "apple", "Apple", "b", "eclair", "Éclair", "Zebra"]
+        );
+    }
+
+    #[test]
+    fn test_tailored_collation() {
+        let values = ["öl", "zoo", "apa", "åka"];
+        assert_eq!(
+            sorted(Collation::Swedish, &values),
+            vec!["apa", "zoo", "åka", "öl"]
+        );
+        assert_eq!(
+            sorted(Collation::German, &values),
+            vec!["åka", "apa", "öl", "zoo"]
+        );
+
+        let values = ["nube", "ñu", "oso"];
+        assert_eq!(
+            sorted(Collation::Spanish, &values),
+            vec!["nube", "ñu", "oso"]
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_key() {
+        let collation = Collation::Root;
+        assert_eq!(
+            collation.create_key("Straße", false),
+            collation.create_key("STRASSE", false)
+        );
+        assert_ne!(
+            collation.create_key("Straße", true),
+            collation.create_key("strasse", true)
+        );
+    }
+}
@@ -30,6 +30,22 @@ impl DataType {
         !self.is_static()
     }
 
+    pub fn is_list(&self) -> bool {
+        self.is_dynamic() && *self != DataType::String
+    }
+
+    pub fn get_element_type(&self) -> Option<DataType> {
+        match *self {
+            DataType::ByteList => Some(DataType::Byte),
+            DataType::IntList => Some(DataType::Int),
+            DataType::FloatList => Some(DataType::Float),
+            DataType::LongList => Some(DataType::Long),
+            DataType::DoubleList => Some(DataType::Double),
+            DataType::StringList => Some(DataType::String),
+            _ => None,
+        }
+    }
+
     pub fn get_static_size(&self) -> usize {
         match *self {
             DataType::Byte => 1,
@@ -1,3 +1,4 @@
+use crate::object::collation::Collation;
 use crate::object::data_type::DataType;
 use byteorder::{ByteOrder, LittleEndian};
 use std::cmp::Ordering;
@@ -129,6 +130,12 @@ impl<'a> IsarObject<'a> {
         self.read_string_at(property.offset, false)
     }
 
+    pub fn read_list_length(&self, property: Property) -> Option<usize> {
+        assert!(property.data_type.is_list());
+        let (_, length) = self.get_offset_length(property.offset, false)?;
+        Some(length)
+    }
+
     pub fn read_byte_list(&self, property: Property) -> Option<&'a [u8]> {
         assert_eq!(property.data_type, DataType::ByteList);
         let (offset, length) = self.get_offset_length(property.offset, false)?;
@@ -217,20 +224,47 @@ impl<'a> IsarObject<'a> {
         }
     }
 
+    pub fn compare_property_with_collation(
+        &self,
+        other: &IsarObject,
+        property: Property,
+        collation: Option<Collation>,
+    ) -> Ordering {
+        if let (Some(collation), DataType::String) = (collation, property.data_type) {
+            match (self.read_string(property), other.read_string(property)) {
+                (Some(s1), Some(s2)) => collation.compare(s1, s2, true),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            }
+        } else {
+            self.compare_property(other, property)
+        }
+    }
+
     pub fn compare_property(&self, other: &IsarObject, property: Property) -> Ordering {
+        self.compare_property_to(property, other, property)
+    }
+
+    /// Compares `property` of this object with `other_property` of `other`. Both properties have
+    /// to be of the same type.
+    pub fn compare_property_to(
+        &self,
+        property: Property,
+        other: &IsarObject,
+        other_property: Property,
+    ) -> Ordering {
         match property.data_type {
-            DataType::Byte => self.read_byte(property).cmp(&other.read_byte(property)),
-            DataType::Int => self.read_int(property).cmp(&other.read_int(property)),
+            DataType::Byte => self
+                .read_byte(property)
+                .cmp(&other.read_byte(other_property)),
+            DataType::Int => self.read_int(property).cmp(&other.read_int(other_property)),
             DataType::Float => {
                 let f1 = self.read_float(property);
-                let f2 = other.read_float(property);
+                let f2 = other.read_float(other_property);
                 if !f1.is_nan() {
                     if !f2.is_nan() {
-                        if f1 > f2 {
-                            Ordering::Greater
-                        } else {
-                            Ordering::Less
-                        }
+                        f1.partial_cmp(&f2).unwrap()
                     } else {
                         Ordering::Greater
                     }
@@ -240,17 +274,15 @@ impl<'a> IsarObject<'a> {
                     Ordering::Equal
                 }
             }
-            DataType::Long => self.read_long(property).cmp(&other.read_long(property)),
+            DataType::Long => self
+                .read_long(property)
+                .cmp(&other.read_long(other_property)),
             DataType::Double => {
                 let f1 = self.read_double(property);
-                let f2 = other.read_double(property);
+                let f2 = other.read_double(other_property);
                 if !f1.is_nan() {
                     if !f2.is_nan() {
-                        if f1 > f2 {
-                            Ordering::Greater
-                        } else {
-                            Ordering::Less
-                        }
+                        f1.partial_cmp(&f2).unwrap()
                     } else {
                         Ordering::Greater
                     }
@@ -262,7 +294,7 @@ impl<'a> IsarObject<'a> {
             }
             DataType::String => {
                 let s1 = self.read_string(property);
-                let s2 = other.read_string(property);
+                let s2 = other.read_string(other_property);
                 if let Some(s1) = s1 {
                     if let Some(s2) = s2 {
                         s1.cmp(s2)
@@ -1,5 +1,7 @@
+pub mod collation;
 pub mod data_type;
 pub mod isar_object;
 pub mod json_encode_decode;
 pub mod object_builder;
 pub mod object_info;
+pub mod property_value;
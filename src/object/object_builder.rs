@@ -2,6 +2,7 @@ use crate::object::data_type::DataType;
 use crate::object::isar_object::IsarObject;
 use crate::object::isar_object::Property;
 use crate::object::object_info::ObjectInfo;
+use crate::object::property_value::PropertyValue;
 use std::slice::from_raw_parts;
 
 pub struct ObjectBuilder<'a> {
@@ -158,6 +159,27 @@ impl<'a> ObjectBuilder<'a> {
         }
     }
 
+    pub fn write_value(&mut self, value: &PropertyValue) {
+        match value {
+            PropertyValue::Null => self.write_null(),
+            PropertyValue::Byte(value) => self.write_byte(*value),
+            PropertyValue::Int(value) => self.write_int(*value),
+            PropertyValue::Long(value) => self.write_long(*value),
+            PropertyValue::Float(value) => self.write_float(*value),
+            PropertyValue::Double(value) => self.write_double(*value),
+            PropertyValue::String(value) => self.write_string(Some(value.as_str())),
+            PropertyValue::ByteList(value) => self.write_byte_list(Some(value.as_slice())),
+            PropertyValue::IntList(value) => self.write_int_list(Some(value.as_slice())),
+            PropertyValue::LongList(value) => self.write_long_list(Some(value.as_slice())),
+            PropertyValue::FloatList(value) => self.write_float_list(Some(value.as_slice())),
+            PropertyValue::DoubleList(value) => self.write_double_list(Some(value.as_slice())),
+            PropertyValue::StringList(value) => {
+                let value: Vec<Option<&str>> = value.iter().map(|s| s.as_deref()).collect();
+                self.write_string_list(Some(value.as_slice()))
+            }
+        }
+    }
+
     fn write_list<T>(&mut self, offset: usize, list: Option<&[T]>) {
         if let Some(list) = list {
             self.write_at(offset, &(self.dynamic_offset as u32).to_le_bytes());
@@ -0,0 +1,178 @@
+use crate::object::collation::Collation;
+use crate::object::data_type::DataType;
+use crate::object::isar_object::{IsarObject, Property};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::hash::Hasher;
+
+/// An owned property value. Null values of every type are represented by `Null`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Null,
+    Byte(u8),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    ByteList(Vec<u8>),
+    IntList(Vec<i32>),
+    LongList(Vec<i64>),
+    FloatList(Vec<f32>),
+    DoubleList(Vec<f64>),
+    StringList(Vec<Option<String>>),
+}
+
+impl PropertyValue {
+    pub fn read(object: IsarObject, property: Property) -> Self {
+        if object.is_null(property) && property.data_type != DataType::Byte {
+            return PropertyValue::Null;
+        }
+        match property.data_type {
+            DataType::Byte => PropertyValue::Byte(object.read_byte(property)),
+            DataType::Int => PropertyValue::Int(object.read_int(property)),
+            DataType::Long => PropertyValue::Long(object.read_long(property)),
+            DataType::Float => PropertyValue::Float(object.read_float(property)),
+            DataType::Double => PropertyValue::Double(object.read_double(property)),
+            DataType::String => PropertyValue::String(object.read_string(property).unwrap().into()),
+            DataType::ByteList => {
+                PropertyValue::ByteList(object.read_byte_list(property).unwrap().to_vec())
+            }
+            DataType::IntList => PropertyValue::IntList(object.read_int_list(property).unwrap()),
+            DataType::LongList => PropertyValue::LongList(object.read_long_list(property).unwrap()),
+            DataType::FloatList => {
+                PropertyValue::FloatList(object.read_float_list(property).unwrap())
+            }
+            DataType::DoubleList => {
+                PropertyValue::DoubleList(object.read_double_list(property).unwrap())
+            }
+            DataType::StringList => {
+                let list = object.read_string_list(property).unwrap();
+                PropertyValue::StringList(list.iter().map(|s| s.map(|s| s.to_string())).collect())
+            }
+        }
+    }
+
+    pub(crate) fn int(value: i32) -> Self {
+        if value == IsarObject::NULL_INT {
+            PropertyValue::Null
+        } else {
+            PropertyValue::Int(value)
+        }
+    }
+
+    pub(crate) fn long(value: i64) -> Self {
+        if value == IsarObject::NULL_LONG {
+            PropertyValue::Null
+        } else {
+            PropertyValue::Long(value)
+        }
+    }
+
+    pub(crate) fn float(value: f32) -> Self {
+        if value.is_nan() {
+            PropertyValue::Null
+        } else {
+            PropertyValue::Float(value)
+        }
+    }
+
+    pub(crate) fn double(value: f64) -> Self {
+        if value.is_nan() {
+            PropertyValue::Null
+        } else {
+            PropertyValue::Double(value)
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == PropertyValue::Null
+    }
+
+    /// Returns whether the value can be stored in a property of `data_type`.
+    pub fn is_type(&self, data_type: DataType) -> bool {
+        matches!(
+            (self, data_type),
+            (PropertyValue::Null, _)
+                | (PropertyValue::Byte(_), DataType::Byte)
+                | (PropertyValue::Int(_), DataType::Int)
+                | (PropertyValue::Long(_), DataType::Long)
+                | (PropertyValue::Float(_), DataType::Float)
+                | (PropertyValue::Double(_), DataType::Double)
+                | (PropertyValue::String(_), DataType::String)
+                | (PropertyValue::ByteList(_), DataType::ByteList)
+                | (PropertyValue::IntList(_), DataType::IntList)
+                | (PropertyValue::LongList(_), DataType::LongList)
+                | (PropertyValue::FloatList(_), DataType::FloatList)
+                | (PropertyValue::DoubleList(_), DataType::DoubleList)
+                | (PropertyValue::StringList(_), DataType::StringList)
+        )
+    }
+
+    /// Hashes the value like [IsarObject::hash_property] hashes scalar properties.
+    pub fn hash<H: Hasher>(&self, case_sensitive: bool, hasher: &mut H) {
+        match self {
+            PropertyValue::Null => {}
+            PropertyValue::Byte(value) => hasher.write_u8(*value),
+            PropertyValue::Int(value) => hasher.write_i32(*value),
+            PropertyValue::Long(value) => hasher.write_i64(*value),
+            PropertyValue::Float(value) => hasher.write(&value.to_le_bytes()),
+            PropertyValue::Double(value) => hasher.write(&value.to_le_bytes()),
+            PropertyValue::String(value) => {
+                hasher.write_usize(value.len());
+                if case_sensitive {
+                    hasher.write(value.as_bytes());
+                } else {
+                    hasher.write(value.to_lowercase().as_bytes());
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    /// Compares scalar values like [IsarObject::compare_property_with_collation] compares
+    /// properties. `Null` is smaller than every other value. Values of different types are ordered
+    /// by their type and lists are considered equal.
+    pub fn compare(&self, other: &PropertyValue, collation: Option<Collation>) -> Ordering {
+        match (self, other) {
+            (PropertyValue::Null, PropertyValue::Null) => Ordering::Equal,
+            (PropertyValue::Null, _) => Ordering::Less,
+            (_, PropertyValue::Null) => Ordering::Greater,
+            (PropertyValue::Byte(v1), PropertyValue::Byte(v2)) => v1.cmp(v2),
+            (PropertyValue::Int(v1), PropertyValue::Int(v2)) => v1.cmp(v2),
+            (PropertyValue::Long(v1), PropertyValue::Long(v2)) => v1.cmp(v2),
+            (PropertyValue::Float(v1), PropertyValue::Float(v2)) => v1
+                .partial_cmp(v2)
+                .unwrap_or_else(|| v1.is_nan().cmp(&v2.is_nan())),
+            (PropertyValue::Double(v1), PropertyValue::Double(v2)) => v1
+                .partial_cmp(v2)
+                .unwrap_or_else(|| v1.is_nan().cmp(&v2.is_nan())),
+            (PropertyValue::String(v1), PropertyValue::String(v2)) => {
+                if let Some(collation) = collation {
+                    collation.compare(v1, v2, true)
+                } else {
+                    v1.cmp(v2)
+                }
+            }
+            _ => self.type_index().cmp(&other.type_index()),
+        }
+    }
+
+    fn type_index(&self) -> u8 {
+        match self {
+            PropertyValue::Null => 0,
+            PropertyValue::Byte(_) => 1,
+            PropertyValue::Int(_) => 2,
+            PropertyValue::Long(_) => 3,
+            PropertyValue::Float(_) => 4,
+            PropertyValue::Double(_) => 5,
+            PropertyValue::String(_) => 6,
+            PropertyValue::ByteList(_) => 7,
+            PropertyValue::IntList(_) => 8,
+            PropertyValue::LongList(_) => 9,
+            PropertyValue::FloatList(_) => 10,
+            PropertyValue::DoubleList(_) => 11,
+            PropertyValue::StringList(_) => 12,
+        }
+    }
+}
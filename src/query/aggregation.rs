@@ -0,0 +1,366 @@
+use crate::error::{illegal_arg, Result};
+use crate::object::data_type::DataType;
+use crate::object::isar_object::Property;
+use crate::object::property_value::PropertyValue;
+use crate::query::Query;
+use crate::txn::IsarTxn;
+use hashbrown::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::hash::Hasher;
+use wyhash::WyHash;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AggregationOp {
+    Min,
+    Max,
+    Sum,
+    Average,
+    /// Counts all results if no property is given and the non-null values otherwise.
+    Count,
+    CountDistinct,
+    Median,
+    /// Linearly interpolated percentile between 0.0 and 1.0.
+    Percentile(f64),
+    /// Population standard deviation.
+    StandardDeviation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregationResult {
+    Long(i64),
+    Double(f64),
+    String(String),
+    Null,
+}
+
+struct Aggregator {
+    op: AggregationOp,
+    data_type: Option<DataType>,
+    count: usize,
+    long_sum: i64,
+    double_sum: f64,
+    min_max: Option<PropertyValue>,
+    values: Vec<f64>,
+    hashes: HashSet<u64>,
+}
+
+impl Aggregator {
+    fn new(op: AggregationOp, property: Option<Property>) -> Result<Self> {
+        let data_type = property.map(|p| p.data_type);
+        let numeric = matches!(
+            data_type,
+            Some(DataType::Byte)
+                | Some(DataType::Int)
+                | Some(DataType::Long)
+                | Some(DataType::Float)
+                | Some(DataType::Double)
+        );
+        let supported = match op {
+            AggregationOp::Count => true,
+            AggregationOp::CountDistinct | AggregationOp::Min | AggregationOp::Max => {
+                numeric || data_type == Some(DataType::String)
+            }
+            AggregationOp::Percentile(p) if !(0.0..=1.0).contains(&p) => {
+                return illegal_arg("Percentile has to be between 0.0 and 1.0.");
+            }
+            _ => numeric,
+        };
+        if !supported {
+            return illegal_arg("Aggregation is not supported for this property.");
+        }
+        Ok(Aggregator {
+            op,
+            data_type,
+            count: 0,
+            long_sum: 0,
+            double_sum: 0.0,
+            min_max: None,
+            values: vec![],
+            hashes: HashSet::new(),
+        })
+    }
+
+    fn add(&mut self, value: Option<PropertyValue>) {
+        let value = match value {
+            Some(PropertyValue::Null) => return,
+            Some(value) => value,
+            None => {
+                self.count += 1;
+                return;
+            }
+        };
+        self.count += 1;
+        match self.op {
+            AggregationOp::Count => {}
+            AggregationOp::CountDistinct => {
+                let mut hasher = WyHash::default();
+                value.hash(true, &mut hasher);
+                self.hashes.insert(hasher.finish());
+            }
+            AggregationOp::Min | AggregationOp::Max => {
+                let replace = if let Some(current) = &self.min_max {
+                    let ord = value.compare(current, None);
+                    (self.op == AggregationOp::Min && ord == Ordering::Less)
+                        || (self.op == AggregationOp::Max && ord == Ordering::Greater)
+                } else {
+                    true
+                };
+                if replace {
+                    self.min_max = Some(value);
+                }
+            }
+            AggregationOp::Sum | AggregationOp::Average => match value {
+                PropertyValue::Byte(value) => {
+                    self.long_sum = self.long_sum.saturating_add(value as i64)
+                }
+                PropertyValue::Int(value) => {
+                    self.long_sum = self.long_sum.saturating_add(value as i64)
+                }
+                PropertyValue::Long(value) => self.long_sum = self.long_sum.saturating_add(value),
+                PropertyValue::Float(value) => self.double_sum += value as f64,
+                PropertyValue::Double(value) => self.double_sum += value,
+                _ => unreachable!(),
+            },
+            AggregationOp::Median
+            | AggregationOp::Percentile(_)
+            | AggregationOp::StandardDeviation => {
+                let value = match value {
+                    PropertyValue::Byte(value) => value as f64,
+                    PropertyValue::Int(value) => value as f64,
+                    PropertyValue::Long(value) => value as f64,
+                    PropertyValue::Float(value) => value as f64,
+                    PropertyValue::Double(value) => value,
+                    _ => unreachable!(),
+                };
+                self.values.push(value);
+            }
+        }
+    }
+
+    fn is_floating(&self) -> bool {
+        matches!(
+            self.data_type,
+            Some(DataType::Float) | Some(DataType::Double)
+        )
+    }
+
+    fn finish(mut self) -> AggregationResult {
+        match self.op {
+            AggregationOp::Count => AggregationResult::Long(self.count as i64),
+            AggregationOp::CountDistinct => AggregationResult::Long(self.hashes.len() as i64),
+            AggregationOp::Min | AggregationOp::Max => match self.min_max {
+                Some(PropertyValue::Byte(value)) => AggregationResult::Long(value as i64),
+                Some(PropertyValue::Int(value)) => AggregationResult::Long(value as i64),
+                Some(PropertyValue::Long(value)) => AggregationResult::Long(value),
+                Some(PropertyValue::Float(value)) => AggregationResult::Double(value as f64),
+                Some(PropertyValue::Double(value)) => AggregationResult::Double(value),
+                Some(PropertyValue::String(value)) => AggregationResult::String(value),
+                _ => AggregationResult::Null,
+            },
+            AggregationOp::Sum => {
+                if self.is_floating() {
+                    AggregationResult::Double(self.double_sum)
+                } else {
+                    AggregationResult::Long(self.long_sum)
+                }
+            }
+            _ if self.count == 0 => AggregationResult::Null,
+            AggregationOp::Average => {
+                let sum = if self.is_floating() {
+                    self.double_sum
+                } else {
+                    self.long_sum as f64
+                };
+                AggregationResult::Double(sum / self.count as f64)
+            }
+            AggregationOp::Median => AggregationResult::Double(self.percentile(0.5)),
+            AggregationOp::Percentile(p) => AggregationResult::Double(self.percentile(p)),
+            AggregationOp::StandardDeviation => {
+                let count = self.values.len() as f64;
+                let mean = self.values.iter().sum::<f64>() / count;
+                let variance = self
+                    .values
+                    .iter()
+                    .map(|value| (value - mean) * (value - mean))
+                    .sum::<f64>()
+                    / count;
+                AggregationResult::Double(variance.sqrt())
+            }
+        }
+    }
+
+    fn percentile(&mut self, p: f64) -> f64 {
+        self.values
+            .sort_unstable_by(|v1, v2| v1.partial_cmp(v2).unwrap());
+        let position = p * (self.values.len() - 1) as f64;
+        let lower = self.values[position.floor() as usize];
+        let upper = self.values[position.ceil() as usize];
+        lower + (upper - lower) * position.fract()
+    }
+}
+
+impl<'txn> Query {
+    /// Aggregates the values of `property` of all results. `property` may only be `None` for
+    /// [AggregationOp::Count]. Null values are ignored.
+    pub fn aggregate(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        op: AggregationOp,
+        property: Option<Property>,
+    ) -> Result<AggregationResult> {
+        let mut groups = self.aggregate_grouped(txn, &[], op, property)?;
+        if let Some((_, result)) = groups.pop() {
+            Ok(result)
+        } else {
+            Ok(Aggregator::new(op, property)?.finish())
+        }
+    }
+
+    /// Aggregates the values of `property` for every distinct combination of values of
+    /// `group_by`. The groups are returned in the order of their first result.
+    pub fn aggregate_grouped(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        group_by: &[Property],
+        op: AggregationOp,
+        property: Option<Property>,
+    ) -> Result<Vec<(Vec<PropertyValue>, AggregationResult)>> {
+        if property.is_none() && op != AggregationOp::Count {
+            return illegal_arg("Aggregation requires a property.");
+        }
+        if group_by.iter().any(|p| p.data_type.is_list()) {
+            return illegal_arg("Lists cannot be grouped.");
+        }
+        Aggregator::new(op, property)?;
+
+        let mut properties = group_by.to_vec();
+        properties.extend(property);
+
+        let mut groups: Vec<(Vec<PropertyValue>, Aggregator)> = vec![];
+        let mut group_indexes: HashMap<u64, Vec<usize>> = HashMap::new();
+        self.find_values_while(txn, &properties, |_, mut values| {
+            let value = if property.is_some() {
+                values.pop()
+            } else {
+                None
+            };
+            let mut hasher = WyHash::default();
+            for value in &values {
+                value.hash(true, &mut hasher);
+            }
+            let indexes = group_indexes.entry(hasher.finish()).or_default();
+            let index = if let Some(index) = indexes.iter().find(|i| groups[**i].0 == values) {
+                *index
+            } else {
+                indexes.push(groups.len());
+                groups.push((values, Aggregator::new(op, property).unwrap()));
+                groups.len() - 1
+            };
+            groups[index].1.add(value);
+            true
+        })?;
+
+        let results = groups
+            .into_iter()
+            .map(|(values, aggregator)| (values, aggregator.finish()))
+            .collect();
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::isar_object::IsarObject;
+    use crate::{col, isar};
+
+    #[test]
+    fn test_aggregate() {
+        isar!(isar, col => col!(oid => DataType::Long, group => DataType::String, value => DataType::Int));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        let data = vec![
+            (1, Some("a"), Some(3)),
+            (2, Some("b"), Some(1)),
+            (3, Some("a"), None),
+            (4, None, Some(7)),
+            (5, Some("a"), Some(5)),
+            (6, Some("b"), Some(1)),
+        ];
+        for (oid, group, value) in data {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_string(group);
+            builder.write_int(value.unwrap_or(IsarObject::NULL_INT));
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let group = col.get_properties().get(1).unwrap().1;
+        let value = col.get_properties().get(2).unwrap().1;
+        let query = col.new_query_builder().build();
+
+        let mut aggregate = |op, property| query.aggregate(&mut txn, op, property).unwrap();
+        assert_eq!(
+            aggregate(AggregationOp::Count, None),
+            AggregationResult::Long(6)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Count, Some(value)),
+            AggregationResult::Long(5)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::CountDistinct, Some(value)),
+            AggregationResult::Long(4)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Sum, Some(value)),
+            AggregationResult::Long(17)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Average, Some(value)),
+            AggregationResult::Double(3.4)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Median, Some(value)),
+            AggregationResult::Double(3.0)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Percentile(0.25), Some(value)),
+            AggregationResult::Double(1.0)
+        );
+        assert_eq!(
+            aggregate(AggregationOp::Percentile(0.875), Some(value)),
+            AggregationResult::Double(6.0)
+        );
+        match aggregate(AggregationOp::StandardDeviation, Some(value)) {
+            AggregationResult::Double(std_dev) => assert!((std_dev - 5.44f64.sqrt()).abs() < 1e-9),
+            _ => panic!(),
+        }
+        assert_eq!(
+            aggregate(AggregationOp::Max, Some(group)),
+            AggregationResult::String("b".to_string())
+        );
+        assert!(query
+            .aggregate(&mut txn, AggregationOp::Sum, Some(group))
+            .is_err());
+
+        let groups = query
+            .aggregate_grouped(&mut txn, &[group], AggregationOp::Sum, Some(value))
+            .unwrap();
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    vec![PropertyValue::String("a".to_string())],
+                    AggregationResult::Long(8)
+                ),
+                (
+                    vec![PropertyValue::String("b".to_string())],
+                    AggregationResult::Long(2)
+                ),
+                (vec![PropertyValue::Null], AggregationResult::Long(7)),
+            ]
+        );
+
+        txn.abort();
+        isar.close();
+    }
+}
@@ -0,0 +1,154 @@
+use crate::error::Result;
+use crate::index::index_stats::read_object_count;
+use crate::query::where_clause::WhereClause;
+use crate::query::Query;
+use crate::txn::IsarTxn;
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortStrategy {
+    None,
+    /// The where clause returns the results in the requested order.
+    WhereClause,
+    /// Only the best `offset + limit` results are kept in memory.
+    TopK,
+    /// All results are sorted in memory or on disk if they exceed the sort memory budget.
+    External,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhereClauseExplanation {
+    /// One of `id`, `index`, `ngram` and `intersection`.
+    pub kind: &'static str,
+    pub index_id: Option<u16>,
+    /// Estimate based on the collection statistics. Not available for ngram and intersection
+    /// where clauses.
+    pub estimated_rows: Option<f64>,
+    pub scanned_rows: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryExplanation {
+    pub where_clauses: Vec<WhereClauseExplanation>,
+    /// Objects returned by the where clauses which did not match the filter.
+    pub filtered_out: usize,
+    pub results: usize,
+    pub sort: SortStrategy,
+    pub in_memory_distinct: bool,
+    pub duration_micros: u64,
+}
+
+pub(crate) struct ExecutionStats {
+    pub scanned: Vec<usize>,
+    pub filtered_out: usize,
+}
+
+impl<'txn> Query {
+    /// Executes the query without returning the results and reports how they were found.
+    pub fn explain(&self, txn: &mut IsarTxn<'txn>) -> Result<QueryExplanation> {
+        txn.read(|cursors| {
+            let object_count = read_object_count(&mut cursors.info, self.col_id)?;
+            let mut where_clauses = vec![];
+            for wc in &self.where_clauses {
+                let (kind, index_id, estimated_rows) = match wc {
+                    WhereClause::Id(wc) => ("id", None, Some(wc.estimate_rows(object_count))),
+                    WhereClause::Index(wc) => {
+                        let estimated_rows = wc.estimate_rows(cursors)?;
+                        ("index", Some(wc.get_index().id), Some(estimated_rows))
+                    }
+                    WhereClause::NGram(_) => ("ngram", None, None),
+                    WhereClause::Intersection(_) => ("intersection", None, None),
+                };
+                where_clauses.push(WhereClauseExplanation {
+                    kind,
+                    index_id,
+                    estimated_rows,
+                    scanned_rows: 0,
+                });
+            }
+
+            let mut stats = ExecutionStats {
+                scanned: vec![0; self.where_clauses.len()],
+                filtered_out: 0,
+            };
+            let mut results = 0;
+            let start = Instant::now();
+            self.find_all_with_stats(cursors, false, Some(&mut stats), |_| {
+                results += 1;
+                Ok(true)
+            })?;
+            let duration_micros = start.elapsed().as_micros() as u64;
+
+            for (wc, scanned_rows) in where_clauses.iter_mut().zip(stats.scanned) {
+                wc.scanned_rows = scanned_rows;
+            }
+            Ok(QueryExplanation {
+                where_clauses,
+                filtered_out: stats.filtered_out,
+                results,
+                sort: self.get_sort_strategy(),
+                in_memory_distinct: !self.distinct.is_empty(),
+                duration_micros,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::data_type::DataType;
+    use crate::query::filter::IntBetweenCond;
+    use crate::query::Sort;
+    use crate::{col, ind, isar};
+
+    #[test]
+    fn test_explain() {
+        isar!(isar, col => col!(oid => DataType::Long, a => DataType::Int, b => DataType::Int; ind!(a)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..10 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int(oid as i32);
+            builder.write_int((oid % 2) as i32);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let a = col.get_properties().get(1).unwrap().1;
+        let b = col.get_properties().get(2).unwrap().1;
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(a, 2, 4).unwrap());
+        qb.add_sort(a, Sort::Ascending);
+        let explanation = qb.build().explain(&mut txn).unwrap();
+        assert_eq!(explanation.where_clauses.len(), 1);
+        assert_eq!(explanation.where_clauses[0].kind, "index");
+        assert_eq!(explanation.where_clauses[0].scanned_rows, 3);
+        assert_eq!(explanation.filtered_out, 0);
+        assert_eq!(explanation.results, 3);
+        assert_eq!(explanation.sort, SortStrategy::WhereClause);
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(b, 1, 1).unwrap());
+        qb.add_sort(a, Sort::Descending);
+        qb.set_limit(2);
+        let explanation = qb.build().explain(&mut txn).unwrap();
+        assert_eq!(explanation.where_clauses[0].kind, "id");
+        assert_eq!(explanation.where_clauses[0].estimated_rows, Some(10.0));
+        assert_eq!(explanation.where_clauses[0].scanned_rows, 10);
+        assert_eq!(explanation.filtered_out, 5);
+        assert_eq!(explanation.results, 2);
+        assert_eq!(explanation.sort, SortStrategy::TopK);
+        assert!(!explanation.in_memory_distinct);
+
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["whereClauses"][0]["scannedRows"], 10);
+        assert_eq!(json["sort"], "topK");
+
+        txn.abort();
+        isar.close();
+    }
+}
@@ -0,0 +1,286 @@
+use crate::error::{IsarError, Result};
+use crate::lmdb::cursor::Cursor;
+use crate::lmdb::IntKey;
+use crate::object::collation::Collation;
+use crate::object::isar_object::{IsarObject, Property};
+use crate::query::{Query, Sort};
+use byteorder::{ByteOrder, LittleEndian};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/*
+Objects are buffered until the size of the buffered objects exceeds the memory budget. The buffer
+only holds references into the memory mapped database, so the budget bounds the object bytes
+referenced at once rather than memory owned by the sorter. The buffer is then sorted and the ids
+of its objects are appended to a temporary file as a run. Finally the runs and the remaining
+buffer are merged using a heap of their smallest objects while the objects are loaded again using
+their ids, which is a random lookup per object.
+
+All runs share a single file and only a small chunk of every run is read at once. If there are
+more than MAX_MERGE_RUNS runs, groups of runs are merged into new runs first.
+ */
+
+/// Small values in tests exercise spilling and merge passes.
+const MIN_MEMORY_BUDGET: usize = if cfg!(test) { 0 } else { 1024 * 1024 };
+const MAX_MERGE_RUNS: usize = if cfg!(test) { 2 } else { 64 };
+const RUN_CHUNK_IDS: usize = 256;
+
+pub(crate) struct ExternalSorter<'a, 'txn> {
+    sort: &'a [(Property, Sort, Option<Collation>)],
+    buffer: Vec<IsarObject<'txn>>,
+    buffered_bytes: usize,
+    memory_budget: usize,
+    file: Option<File>,
+    file_len: u64,
+    runs: Vec<Run>,
+    oid_property: Property,
+    col_id: u16,
+}
+
+impl<'a, 'txn> ExternalSorter<'a, 'txn> {
+    pub fn new(
+        sort: &'a [(Property, Sort, Option<Collation>)],
+        memory_budget: usize,
+        oid_property: Property,
+        col_id: u16,
+    ) -> Self {
+        ExternalSorter {
+            sort,
+            buffer: vec![],
+            buffered_bytes: 0,
+            memory_budget: memory_budget.max(MIN_MEMORY_BUDGET),
+            file: None,
+            file_len: 0,
+            runs: vec![],
+            oid_property,
+            col_id,
+        }
+    }
+
+    pub fn add(&mut self, object: IsarObject<'txn>) -> Result<()> {
+        self.buffered_bytes += object.as_bytes().len() + std::mem::size_of::<IsarObject>();
+        self.buffer.push(object);
+        if self.buffered_bytes >= self.memory_budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        let sort = self.sort;
+        self.buffer
+            .sort_unstable_by(|o1, o2| Query::compare_objects(sort, o1, o2));
+        if self.file.is_none() {
+            self.file = Some(tempfile::tempfile()?);
+        }
+        let file = self.file.as_mut().unwrap();
+        let mut bytes = vec![0; self.buffer.len() * 8];
+        for (object, id_bytes) in self.buffer.iter().zip(bytes.chunks_exact_mut(8)) {
+            LittleEndian::write_i64(id_bytes, object.read_long(self.oid_property));
+        }
+        let start = self.file_len;
+        write_at(file, &mut self.file_len, &bytes)?;
+        self.runs.push(Run::new(start, self.file_len));
+        self.buffer.clear();
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Calls `callback` with all objects in sorted order.
+    pub fn finish<F>(mut self, data: &mut Cursor<'txn>, mut callback: F) -> Result<()>
+    where
+        F: FnMut(IsarObject<'txn>) -> Result<bool>,
+    {
+        let sort = self.sort;
+        self.buffer
+            .sort_unstable_by(|o1, o2| Query::compare_objects(sort, o1, o2));
+        let buffer = std::mem::take(&mut self.buffer).into_iter();
+        let file = if let Some(file) = &mut self.file {
+            file
+        } else {
+            for object in buffer {
+                if !callback(object)? {
+                    break;
+                }
+            }
+            return Ok(());
+        };
+
+        while self.runs.len() > MAX_MERGE_RUNS {
+            let group = self.runs.drain(..MAX_MERGE_RUNS).collect();
+            let mut merger = Merger::new(sort, self.col_id, group, vec![].into_iter());
+            merger.init(file, data)?;
+            let start = self.file_len;
+            let mut bytes = Vec::with_capacity(RUN_CHUNK_IDS * 8);
+            while let Some(object) = merger.next(file, data)? {
+                let id = object.read_long(self.oid_property);
+                bytes.extend_from_slice(&id.to_le_bytes());
+                if bytes.len() == RUN_CHUNK_IDS * 8 {
+                    write_at(file, &mut self.file_len, &bytes)?;
+                    bytes.clear();
+                }
+            }
+            write_at(file, &mut self.file_len, &bytes)?;
+            self.runs.push(Run::new(start, self.file_len));
+        }
+
+        let runs = std::mem::take(&mut self.runs);
+        let mut merger = Merger::new(sort, self.col_id, runs, buffer);
+        merger.init(file, data)?;
+        while let Some(object) = merger.next(file, data)? {
+            if !callback(object)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_at(file: &mut File, file_len: &mut u64, bytes: &[u8]) -> Result<()> {
+    file.seek(SeekFrom::Start(*file_len))?;
+    file.write_all(bytes)?;
+    *file_len += bytes.len() as u64;
+    Ok(())
+}
+
+/// Byte range of a run in the file and the chunk of ids which is currently read.
+struct Run {
+    pos: u64,
+    end: u64,
+    chunk: Vec<i64>,
+    next: usize,
+}
+
+impl Run {
+    fn new(start: u64, end: u64) -> Self {
+        Run {
+            pos: start,
+            end,
+            chunk: vec![],
+            next: 0,
+        }
+    }
+
+    fn next_id(&mut self, file: &mut File) -> Result<Option<i64>> {
+        if self.next == self.chunk.len() {
+            if self.pos == self.end {
+                return Ok(None);
+            }
+            let len = ((self.end - self.pos) as usize).min(RUN_CHUNK_IDS * 8);
+            let mut bytes = vec![0; len];
+            file.seek(SeekFrom::Start(self.pos))?;
+            file.read_exact(&mut bytes)?;
+            self.pos += len as u64;
+            self.chunk.clear();
+            self.chunk
+                .extend(bytes.chunks_exact(8).map(LittleEndian::read_i64));
+            self.next = 0;
+        }
+        let id = self.chunk[self.next];
+        self.next += 1;
+        Ok(Some(id))
+    }
+}
+
+/// The smallest remaining object of a run or of the buffer.
+struct Head<'a, 'txn> {
+    object: IsarObject<'txn>,
+    source: usize,
+    sort: &'a [(Property, Sort, Option<Collation>)],
+}
+
+impl Ord for Head<'_, '_> {
+    // reversed to turn the max-heap into a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        Query::compare_objects(self.sort, &other.object, &self.object)
+            .then_with(|| other.source.cmp(&self.source))
+    }
+}
+
+impl PartialOrd for Head<'_, '_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head<'_, '_> {}
+
+struct Merger<'a, 'txn> {
+    sort: &'a [(Property, Sort, Option<Collation>)],
+    col_id: u16,
+    runs: Vec<Run>,
+    buffer: std::vec::IntoIter<IsarObject<'txn>>,
+    heads: BinaryHeap<Head<'a, 'txn>>,
+}
+
+impl<'a, 'txn> Merger<'a, 'txn> {
+    fn new(
+        sort: &'a [(Property, Sort, Option<Collation>)],
+        col_id: u16,
+        runs: Vec<Run>,
+        buffer: std::vec::IntoIter<IsarObject<'txn>>,
+    ) -> Self {
+        Merger {
+            sort,
+            col_id,
+            runs,
+            buffer,
+            heads: BinaryHeap::new(),
+        }
+    }
+
+    fn init(&mut self, file: &mut File, data: &mut Cursor<'txn>) -> Result<()> {
+        for source in 0..=self.runs.len() {
+            self.advance(source, file, data)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the next object of `source` to the heads. The source following the runs is the
+    /// buffer.
+    fn advance(&mut self, source: usize, file: &mut File, data: &mut Cursor<'txn>) -> Result<()> {
+        let object = if let Some(run) = self.runs.get_mut(source) {
+            if let Some(id) = run.next_id(file)? {
+                let (_, object) =
+                    data.move_to(IntKey::new(self.col_id, id))?
+                        .ok_or(IsarError::DbCorrupted {
+                            message: "Could not find sorted object.".to_string(),
+                        })?;
+                Some(IsarObject::from_bytes(object))
+            } else {
+                None
+            }
+        } else {
+            self.buffer.next()
+        };
+        if let Some(object) = object {
+            self.heads.push(Head {
+                object,
+                source,
+                sort: self.sort,
+            });
+        }
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        file: &mut File,
+        data: &mut Cursor<'txn>,
+    ) -> Result<Option<IsarObject<'txn>>> {
+        if let Some(head) = self.heads.pop() {
+            self.advance(head.source, file, data)?;
+            Ok(Some(head.object))
+        } else {
+            Ok(None)
+        }
+    }
+}
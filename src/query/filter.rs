@@ -2,11 +2,20 @@ use crate::collection::IsarCollection;
 use crate::error::{illegal_arg, IsarError, Result};
 use crate::link::Link;
 use crate::lmdb::cursor::Cursor;
+use crate::object::collation::Collation;
+use crate::object::data_type::DataType;
 use crate::object::isar_object::{IsarObject, Property};
+use crate::object::property_value::PropertyValue;
+use crate::query::edit_distance::edit_distance;
 use crate::query::fast_wild_match::fast_wild_match;
+use crate::query::where_clause::WhereClause;
 use enum_dispatch::enum_dispatch;
+use enum_ordinalize::Ordinalize;
 use hashbrown::HashSet;
 use paste::paste;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
 #[enum_dispatch]
 #[derive(Clone)]
@@ -20,19 +29,150 @@ pub enum Filter {
     ByteListContains(ByteListContainsCond),
     IntListContains(IntListContainsCond),
     LongListContains(LongListContainsCond),
+    ByteListBetween(ByteListBetweenCond),
+    IntListBetween(IntListBetweenCond),
+    LongListBetween(LongListBetweenCond),
+    FloatListBetween(FloatListBetweenCond),
+    DoubleListBetween(DoubleListBetweenCond),
+    ByteListContainsValues(ByteListContainsValuesCond),
+    IntListContainsValues(IntListContainsValuesCond),
+    LongListContainsValues(LongListContainsValuesCond),
+    FloatListContainsValues(FloatListContainsValuesCond),
+    DoubleListContainsValues(DoubleListContainsValuesCond),
+    ListLength(ListLengthCond),
+
+    ByteIn(ByteInCond),
+    IntIn(IntInCond),
+    LongIn(LongInCond),
+    FloatIn(FloatInCond),
+    DoubleIn(DoubleInCond),
+    StringIn(StringInCond),
 
     StringBetween(StringBetweenCond),
     StringStartsWith(StringStartsWithCond),
     StringEndsWith(StringEndsWithCond),
     StringMatches(StringMatchesCond),
+    StringContains(StringContainsCond),
+    StringFuzzy(StringFuzzyCond),
+    StringRegex(StringRegexCond),
 
     StringListContains(StringListContainsCond),
+    StringListRegex(StringListRegexCond),
+    StringListBetween(StringListBetweenCond),
+    StringListContainsValues(StringListContainsValuesCond),
+
+    IsNull(IsNullCond),
+    IsNotNull(IsNotNullCond),
+
+    PropertyCompare(PropertyCompareCond),
+    PropertyOffsetBetween(PropertyOffsetBetweenCond),
+
+    Param(ParamCond),
 
     And(AndCond),
     Or(OrCond),
     Not(NotCond),
     Static(StaticCond),
     Link(LinkCond),
+    WhereClause(WhereClauseCond),
+}
+
+impl Filter {
+    /// Returns whether the filter contains the parameter `name` and fails if `value` cannot be
+    /// bound to it.
+    pub(crate) fn check_param(&self, name: &str, value: &PropertyValue) -> Result<bool> {
+        let mut found = false;
+        match self {
+            Filter::Param(c) if c.name == name => {
+                c.check(value)?;
+                found = true;
+            }
+            Filter::And(AndCond { filters }) | Filter::Or(OrCond { filters }) => {
+                for filter in filters {
+                    found |= filter.check_param(name, value)?;
+                }
+            }
+            Filter::Not(c) => found = c.filter.check_param(name, value)?,
+            Filter::Link(c) => found = c.filter.check_param(name, value)?,
+            Filter::WhereClause(c) => {
+                for wc in &c.where_clauses {
+                    found |= wc.check_param(name, value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(found)
+    }
+
+    /// Binds `value` to all parameters named `name`. The value has to be checked using
+    /// `check_param()`.
+    pub(crate) fn bind_param(&mut self, name: &str, value: &PropertyValue) -> Result<()> {
+        match self {
+            Filter::Param(c) if c.name == name => c.bind(value),
+            Filter::And(AndCond { filters }) | Filter::Or(OrCond { filters }) => {
+                for filter in filters {
+                    filter.bind_param(name, value)?;
+                }
+            }
+            Filter::Not(c) => c.filter.bind_param(name, value)?,
+            Filter::Link(c) => c.filter.bind_param(name, value)?,
+            Filter::WhereClause(c) => {
+                for wc in &mut c.where_clauses {
+                    wc.bind_param(name, value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if every object matching this filter is guaranteed to match `other`.
+    ///
+    /// The check is conservative: `false` does not mean that there is an object matching only
+    /// one of the filters.
+    pub(crate) fn implies(&self, other: &Filter) -> bool {
+        match other {
+            Filter::Static(q) if q.value => return true,
+            Filter::And(q) => return q.filters.iter().all(|f| self.implies(f)),
+            _ => {}
+        }
+        match self {
+            Filter::Static(p) if !p.value => return true,
+            Filter::Or(p) => return p.filters.iter().all(|f| f.implies(other)),
+            Filter::And(p) if p.filters.iter().any(|f| f.implies(other)) => return true,
+            _ => {}
+        }
+        if let Filter::Or(q) = other {
+            if q.filters.iter().any(|f| self.implies(f)) {
+                return true;
+            }
+        }
+
+        match (self, other) {
+            (Filter::ByteBetween(p), Filter::ByteBetween(q)) => {
+                p.property == q.property && q.lower <= p.lower && p.upper <= q.upper
+            }
+            (Filter::IntBetween(p), Filter::IntBetween(q)) => {
+                p.property == q.property && q.lower <= p.lower && p.upper <= q.upper
+            }
+            (Filter::LongBetween(p), Filter::LongBetween(q)) => {
+                p.property == q.property && q.lower <= p.lower && p.upper <= q.upper
+            }
+            (Filter::FloatBetween(p), Filter::FloatBetween(q)) => {
+                let nan = p.lower.is_nan() || p.upper.is_nan() || q.lower.is_nan();
+                p.property == q.property && !nan && q.lower <= p.lower && p.upper <= q.upper
+            }
+            (Filter::DoubleBetween(p), Filter::DoubleBetween(q)) => {
+                let nan = p.lower.is_nan() || p.upper.is_nan() || q.lower.is_nan();
+                p.property == q.property && !nan && q.lower <= p.lower && p.upper <= q.upper
+            }
+            (Filter::StringBetween(p), Filter::StringBetween(q)) => p.implies(q),
+            (Filter::IsNull(p), Filter::IsNull(q)) => p.property == q.property,
+            (Filter::IsNotNull(p), Filter::IsNotNull(q)) => p.property == q.property,
+            (Filter::Not(p), Filter::Not(q)) => q.filter.implies(&p.filter),
+            _ => false,
+        }
+    }
 }
 
 pub(crate) struct FilterCursors<'txn, 'a>(&'a mut Cursor<'txn>, &'a mut Cursor<'txn>);
@@ -56,9 +196,9 @@ macro_rules! filter_between_struct {
         paste! {
             #[derive(Clone)]
             pub struct [<$name Cond>] {
-                upper: $type,
-                lower: $type,
-                property: Property,
+                pub(crate) upper: $type,
+                pub(crate) lower: $type,
+                pub(crate) property: Property,
             }
 
             impl [<$name Cond>] {
@@ -85,12 +225,17 @@ macro_rules! primitive_filter_between {
         paste! {
             impl Condition for [<$name Cond>] {
                 fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
-                    let val = object.$prop_accessor(self.property);
-                    Ok(self.lower <= val && self.upper >= val)
+                    Ok(self.matches(object.$prop_accessor(self.property)))
                 }
 
                 fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
             }
+
+            impl [<$name Cond>] {
+                pub(crate) fn matches(&self, val: $type) -> bool {
+                    self.lower <= val && self.upper >= val
+                }
+            }
         }
     };
 }
@@ -102,18 +247,22 @@ macro_rules! float_filter_between {
         paste! {
             impl Condition for [<$name Cond>] {
                 fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
-                    let val = object.$prop_accessor(self.property);
-                    let result = if self.upper.is_nan() {
+                    Ok(self.matches(object.$prop_accessor(self.property)))
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+
+            impl [<$name Cond>] {
+                pub(crate) fn matches(&self, val: $type) -> bool {
+                    if self.upper.is_nan() {
                         self.lower.is_nan() && val.is_nan()
                     } else if self.lower.is_nan() {
                         self.upper >= val || val.is_nan()
                     } else {
                         self.lower <= val && self.upper >= val
-                    };
-                    Ok(result)
+                    }
                 }
-
-                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
             }
         }
     };
@@ -131,8 +280,8 @@ macro_rules! filter_not_equal_struct {
         paste! {
             #[derive(Clone)]
             pub struct [<$name Cond>] {
-                value: $type,
-                property: Property,
+                pub(crate) value: $type,
+                pub(crate) property: Property,
             }
 
             impl [<$name Cond>] {
@@ -173,12 +322,196 @@ primitive_list_filter!(ByteListContains, Byte, u8, read_byte_list);
 primitive_list_filter!(IntListContains, Int, i32, read_int_list);
 primitive_list_filter!(LongListContains, Long, i64, read_long_list);
 
+#[macro_export]
+macro_rules! primitive_filter_in {
+    ($name:ident, $data_type:ident, $type:ty, $prop_accessor:ident) => {
+        paste! {
+            #[derive(Clone)]
+            pub struct [<$name Cond>] {
+                pub(crate) property: Property,
+                pub(crate) values: HashSet<$type>,
+            }
+
+            impl [<$name Cond>] {
+                pub fn filter(property: Property, values: &[$type]) -> Result<Filter> {
+                    if property.data_type == $crate::object::data_type::DataType::$data_type {
+                        Ok(Filter::$name(Self::new(property, values)))
+                    } else {
+                        illegal_arg("Property does not support this filter.")
+                    }
+                }
+
+                fn new(property: Property, values: &[$type]) -> Self {
+                    let values = values.iter().copied().collect();
+                    Self { property, values }
+                }
+
+                /// Returns the matching set entry.
+                pub(crate) fn find(&self, value: $type) -> Option<$type> {
+                    self.values.get(&value).copied()
+                }
+
+                pub(crate) fn len(&self) -> usize {
+                    self.values.len()
+                }
+            }
+
+            impl Condition for [<$name Cond>] {
+                fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+                    Ok(self.find(object.$prop_accessor(self.property)).is_some())
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+        }
+    };
+}
+
+/// Floats are stored as bits. All NaNs (null) and both zeros are considered equal.
+#[macro_export]
+macro_rules! float_filter_in {
+    ($name:ident, $data_type:ident, $type:ty, $bits:ty, $prop_accessor:ident) => {
+        paste! {
+            #[derive(Clone)]
+            pub struct [<$name Cond>] {
+                pub(crate) property: Property,
+                pub(crate) values: HashSet<$bits>,
+            }
+
+            impl [<$name Cond>] {
+                pub fn filter(property: Property, values: &[$type]) -> Result<Filter> {
+                    if property.data_type == $crate::object::data_type::DataType::$data_type {
+                        Ok(Filter::$name(Self::new(property, values)))
+                    } else {
+                        illegal_arg("Property does not support this filter.")
+                    }
+                }
+
+                fn new(property: Property, values: &[$type]) -> Self {
+                    let values = values.iter().map(|v| Self::to_bits(*v)).collect();
+                    Self { property, values }
+                }
+
+                /// Returns the matching set entry.
+                pub(crate) fn find(&self, value: $type) -> Option<$bits> {
+                    self.values.get(&Self::to_bits(value)).copied()
+                }
+
+                pub(crate) fn len(&self) -> usize {
+                    self.values.len()
+                }
+
+                fn to_bits(value: $type) -> $bits {
+                    if value.is_nan() {
+                        <$type>::NAN.to_bits()
+                    } else if value == 0.0 {
+                        0
+                    } else {
+                        value.to_bits()
+                    }
+                }
+
+                pub(crate) fn get_values(&self) -> Vec<$type> {
+                    self.values.iter().map(|bits| <$type>::from_bits(*bits)).collect()
+                }
+            }
+
+            impl Condition for [<$name Cond>] {
+                fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+                    Ok(self.find(object.$prop_accessor(self.property)).is_some())
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+        }
+    };
+}
+
+primitive_filter_in!(ByteIn, Byte, u8, read_byte);
+primitive_filter_in!(IntIn, Int, i32, read_int);
+primitive_filter_in!(LongIn, Long, i64, read_long);
+float_filter_in!(FloatIn, Float, f32, u32, read_float);
+float_filter_in!(DoubleIn, Double, f64, u64, read_double);
+
+#[derive(Clone)]
+pub struct StringInCond {
+    pub(crate) property: Property,
+    pub(crate) values: HashSet<String>,
+    pub(crate) null: bool,
+    pub(crate) case_sensitive: bool,
+}
+
+impl StringInCond {
+    pub fn filter(
+        property: Property,
+        values: &[Option<&str>],
+        case_sensitive: bool,
+    ) -> Result<Filter> {
+        if property.data_type == crate::object::data_type::DataType::String {
+            Ok(Filter::StringIn(Self::new(
+                property,
+                values,
+                case_sensitive,
+            )))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+
+    fn new(property: Property, values: &[Option<&str>], case_sensitive: bool) -> Self {
+        let null = values.contains(&None);
+        let values = values
+            .iter()
+            .flatten()
+            .map(|value| {
+                if case_sensitive {
+                    value.to_string()
+                } else {
+                    value.to_lowercase()
+                }
+            })
+            .collect();
+        StringInCond {
+            property,
+            values,
+            null,
+            case_sensitive,
+        }
+    }
+
+    /// Returns the matching set entry. `Some(None)` means that null is part of the set.
+    pub(crate) fn find(&self, value: Option<&str>) -> Option<Option<&str>> {
+        match value {
+            Some(value) if self.case_sensitive => self.values.get(value).map(|v| Some(v.as_str())),
+            Some(value) => self
+                .values
+                .get(&value.to_lowercase())
+                .map(|v| Some(v.as_str())),
+            None if self.null => Some(None),
+            None => None,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.values.len() + self.null as usize
+    }
+}
+
+impl Condition for StringInCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        Ok(self.find(object.read_string(self.property)).is_some())
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
 #[derive(Clone)]
 pub struct StringBetweenCond {
-    property: Property,
-    lower: Option<String>,
-    upper: Option<String>,
-    case_sensitive: bool,
+    pub(crate) property: Property,
+    pub(crate) lower: Option<String>,
+    pub(crate) upper: Option<String>,
+    pub(crate) case_sensitive: bool,
+    pub(crate) collation: Option<Collation>,
 }
 
 impl StringBetweenCond {
@@ -188,6 +521,36 @@ impl StringBetweenCond {
         upper: Option<&str>,
         case_sensitive: bool,
     ) -> Result<Filter> {
+        Self::collated_filter(property, lower, upper, case_sensitive, None)
+    }
+
+    pub fn collated_filter(
+        property: Property,
+        lower: Option<&str>,
+        upper: Option<&str>,
+        case_sensitive: bool,
+        collation: Option<Collation>,
+    ) -> Result<Filter> {
+        if property.data_type == crate::object::data_type::DataType::String {
+            Ok(Filter::StringBetween(Self::new(
+                property,
+                lower,
+                upper,
+                case_sensitive,
+                collation,
+            )))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+
+    fn new(
+        property: Property,
+        lower: Option<&str>,
+        upper: Option<&str>,
+        case_sensitive: bool,
+        collation: Option<Collation>,
+    ) -> Self {
         let lower = if case_sensitive {
             lower.map(|s| s.to_string())
         } else {
@@ -198,26 +561,59 @@ impl StringBetweenCond {
         } else {
             upper.map(|s| s.to_lowercase())
         };
-        if property.data_type == crate::object::data_type::DataType::String {
-            Ok(Filter::StringBetween(StringBetweenCond {
-                property,
-                lower,
-                upper,
-                case_sensitive,
-            }))
-        } else {
-            illegal_arg("Property does not support this filter.")
+        StringBetweenCond {
+            property,
+            lower,
+            upper,
+            case_sensitive,
+            collation,
+        }
+    }
+}
+
+impl StringBetweenCond {
+    fn implies(&self, other: &StringBetweenCond) -> bool {
+        if self.property != other.property
+            || self.case_sensitive != other.case_sensitive
+            || self.collation != other.collation
+        {
+            return false;
+        }
+        let compare = |s1: &str, s2: &str| {
+            if let Some(collation) = self.collation {
+                collation.compare(s1, s2, self.case_sensitive)
+            } else {
+                s1.cmp(s2)
+            }
+        };
+        match (&self.lower, &self.upper, &other.lower, &other.upper) {
+            (Some(lower), Some(upper), Some(other_lower), Some(other_upper)) => {
+                compare(other_lower, lower) != Ordering::Greater
+                    && compare(upper, other_upper) != Ordering::Greater
+            }
+            _ => self.lower == other.lower && self.upper == other.upper,
         }
     }
 }
 
 impl Condition for StringBetweenCond {
     fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
-        let other_str = object.read_string(self.property);
-        let result = match (self.lower.as_ref(), other_str) {
+        Ok(self.matches(object.read_string(self.property)))
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+impl StringBetweenCond {
+    pub(crate) fn matches(&self, other_str: Option<&str>) -> bool {
+        match (self.lower.as_ref(), other_str) {
             (Some(lower), Some(other)) => {
                 if let Some(upper) = self.upper.as_ref() {
-                    if self.case_sensitive {
+                    if let Some(collation) = self.collation {
+                        collation.compare(lower, other, self.case_sensitive) != Ordering::Greater
+                            && collation.compare(upper, other, self.case_sensitive)
+                                != Ordering::Less
+                    } else if self.case_sensitive {
                         lower.as_str() <= other && upper.as_str() >= other
                     } else {
                         let lowercase_other = other.to_lowercase();
@@ -230,11 +626,8 @@ impl Condition for StringBetweenCond {
             (Some(_), None) => false,
             (None, Some(_)) => true,
             (None, None) => self.upper.is_none(),
-        };
-        Ok(result)
+        }
     }
-
-    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
 }
 
 #[macro_export]
@@ -243,9 +636,9 @@ macro_rules! string_filter_struct {
         paste! {
             #[derive(Clone)]
             pub struct [<$name Cond>] {
-                property: Property,
-                value: Option<String>,
-                case_sensitive: bool,
+                pub(crate) property: Property,
+                pub(crate) value: Option<String>,
+                pub(crate) case_sensitive: bool,
             }
 
             impl [<$name Cond>] {
@@ -312,11 +705,108 @@ macro_rules! string_filter {
     (StringMatches $filter_str:ident, $other_str:ident) => {
         fast_wild_match($other_str, $filter_str)
     };
+
+    (StringContains $filter_str:ident, $other_str:ident) => {
+        $other_str.contains($filter_str.as_str())
+    };
 }
 
 string_filter!(StringStartsWith);
 string_filter!(StringEndsWith);
 string_filter!(StringMatches);
+string_filter!(StringContains);
+
+#[derive(Clone)]
+pub struct StringFuzzyCond {
+    pub(crate) property: Property,
+    pub(crate) value: String,
+    pub(crate) max_distance: usize,
+    pub(crate) case_sensitive: bool,
+}
+
+impl StringFuzzyCond {
+    pub fn filter(
+        property: Property,
+        value: &str,
+        max_distance: usize,
+        case_sensitive: bool,
+    ) -> Result<Filter> {
+        let value = if case_sensitive {
+            value.to_string()
+        } else {
+            value.to_lowercase()
+        };
+        if property.data_type == crate::object::data_type::DataType::String {
+            Ok(Filter::StringFuzzy(StringFuzzyCond {
+                property,
+                value,
+                max_distance,
+                case_sensitive,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for StringFuzzyCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let result = if let Some(other_str) = object.read_string(self.property) {
+            if self.case_sensitive {
+                edit_distance(&self.value, other_str, self.max_distance).is_some()
+            } else {
+                let lowercase_str = other_str.to_lowercase();
+                edit_distance(&self.value, &lowercase_str, self.max_distance).is_some()
+            }
+        } else {
+            false
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+fn build_regex(pattern: &str, case_sensitive: bool) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .or_else(|_| illegal_arg("Invalid regular expression."))
+}
+
+#[derive(Clone)]
+pub struct StringRegexCond {
+    pub(crate) property: Property,
+    pub(crate) regex: Regex,
+    pub(crate) case_sensitive: bool,
+}
+
+impl StringRegexCond {
+    pub fn filter(property: Property, pattern: &str, case_sensitive: bool) -> Result<Filter> {
+        if property.data_type == DataType::String {
+            Ok(Filter::StringRegex(StringRegexCond {
+                property,
+                regex: build_regex(pattern, case_sensitive)?,
+                case_sensitive,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for StringRegexCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let result = if let Some(other_str) = object.read_string(self.property) {
+            self.regex.is_match(other_str)
+        } else {
+            false
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
 
 string_filter_struct!(StringListContains);
 
@@ -333,17 +823,614 @@ impl Condition for StringListContainsCond {
     fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
 }
 
+/// Matches lists with at least one element matching the pattern.
 #[derive(Clone)]
-pub struct AndCond {
-    filters: Vec<Filter>,
+pub struct StringListRegexCond {
+    pub(crate) property: Property,
+    pub(crate) regex: Regex,
+    pub(crate) case_sensitive: bool,
 }
 
-impl Condition for AndCond {
-    fn evaluate(
-        &self,
-        object: IsarObject,
-        mut cursors: Option<&mut FilterCursors>,
-    ) -> Result<bool> {
+impl StringListRegexCond {
+    pub fn filter(property: Property, pattern: &str, case_sensitive: bool) -> Result<Filter> {
+        if property.data_type == DataType::StringList {
+            Ok(Filter::StringListRegex(StringListRegexCond {
+                property,
+                regex: build_regex(pattern, case_sensitive)?,
+                case_sensitive,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for StringListRegexCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let list = object.read_string_list(self.property);
+        if let Some(list) = list {
+            Ok(list.iter().flatten().any(|s| self.regex.is_match(s)))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+/// Matches lists with any (or if `all` is set, all) elements between `lower` and `upper`. Empty
+/// lists match if `all` is set.
+#[macro_export]
+macro_rules! list_filter_between {
+    ($name:ident, $data_type:ident, $type:ty, $prop_accessor:ident, $element:ident) => {
+        paste! {
+            #[derive(Clone)]
+            pub struct [<$name Cond>] {
+                pub(crate) property: Property,
+                pub(crate) element: [<$element Cond>],
+                pub(crate) all: bool,
+            }
+
+            impl [<$name Cond>] {
+                pub fn filter(property: Property, lower: $type, upper: $type, all: bool) -> Result<Filter> {
+                    if property.data_type == $crate::object::data_type::DataType::$data_type {
+                        let element = [<$element Cond>] { property, lower, upper };
+                        Ok(Filter::$name(Self { property, element, all }))
+                    } else {
+                        illegal_arg("Property does not support this filter.")
+                    }
+                }
+            }
+
+            impl Condition for [<$name Cond>] {
+                fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+                    let result = if let Some(list) = object.$prop_accessor(self.property) {
+                        if self.all {
+                            list.iter().all(|v| self.element.matches(*v))
+                        } else {
+                            list.iter().any(|v| self.element.matches(*v))
+                        }
+                    } else {
+                        false
+                    };
+                    Ok(result)
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+        }
+    };
+}
+
+/// Matches lists containing any (or if `all` is set, all) of the values.
+#[macro_export]
+macro_rules! list_filter_contains_values {
+    ($name:ident, $data_type:ident, $type:ty, $prop_accessor:ident, $element:ident) => {
+        paste! {
+            #[derive(Clone)]
+            pub struct [<$name Cond>] {
+                pub(crate) property: Property,
+                pub(crate) values: [<$element Cond>],
+                pub(crate) all: bool,
+            }
+
+            impl [<$name Cond>] {
+                pub fn filter(property: Property, values: &[$type], all: bool) -> Result<Filter> {
+                    if property.data_type == $crate::object::data_type::DataType::$data_type {
+                        let values = [<$element Cond>]::new(property, values);
+                        Ok(Filter::$name(Self { property, values, all }))
+                    } else {
+                        illegal_arg("Property does not support this filter.")
+                    }
+                }
+            }
+
+            impl Condition for [<$name Cond>] {
+                fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+                    let result = if let Some(list) = object.$prop_accessor(self.property) {
+                        list_contains_values(list.iter().copied(), self.all, self.values.len(), |v| {
+                            self.values.find(v)
+                        })
+                    } else {
+                        false
+                    };
+                    Ok(result)
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+        }
+    };
+}
+
+fn list_contains_values<T, K: Eq + std::hash::Hash>(
+    mut list: impl Iterator<Item = T>,
+    all: bool,
+    values_len: usize,
+    find: impl Fn(T) -> Option<K>,
+) -> bool {
+    if all {
+        let found: HashSet<K> = list.filter_map(find).collect();
+        found.len() == values_len
+    } else {
+        list.any(|v| find(v).is_some())
+    }
+}
+
+list_filter_between!(ByteListBetween, ByteList, u8, read_byte_list, ByteBetween);
+list_filter_between!(IntListBetween, IntList, i32, read_int_list, IntBetween);
+list_filter_between!(LongListBetween, LongList, i64, read_long_list, LongBetween);
+list_filter_between!(
+    FloatListBetween,
+    FloatList,
+    f32,
+    read_float_list,
+    FloatBetween
+);
+list_filter_between!(
+    DoubleListBetween,
+    DoubleList,
+    f64,
+    read_double_list,
+    DoubleBetween
+);
+
+list_filter_contains_values!(ByteListContainsValues, ByteList, u8, read_byte_list, ByteIn);
+list_filter_contains_values!(IntListContainsValues, IntList, i32, read_int_list, IntIn);
+list_filter_contains_values!(
+    LongListContainsValues,
+    LongList,
+    i64,
+    read_long_list,
+    LongIn
+);
+list_filter_contains_values!(
+    FloatListContainsValues,
+    FloatList,
+    f32,
+    read_float_list,
+    FloatIn
+);
+list_filter_contains_values!(
+    DoubleListContainsValues,
+    DoubleList,
+    f64,
+    read_double_list,
+    DoubleIn
+);
+
+/// Matches lists with any (or if `all` is set, all) elements between `lower` and `upper`. Empty
+/// lists match if `all` is set.
+#[derive(Clone)]
+pub struct StringListBetweenCond {
+    pub(crate) property: Property,
+    pub(crate) element: StringBetweenCond,
+    pub(crate) all: bool,
+}
+
+impl StringListBetweenCond {
+    pub fn filter(
+        property: Property,
+        lower: Option<&str>,
+        upper: Option<&str>,
+        case_sensitive: bool,
+        all: bool,
+    ) -> Result<Filter> {
+        if property.data_type == crate::object::data_type::DataType::StringList {
+            let element = StringBetweenCond::new(property, lower, upper, case_sensitive, None);
+            Ok(Filter::StringListBetween(StringListBetweenCond {
+                property,
+                element,
+                all,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for StringListBetweenCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let result = if let Some(list) = object.read_string_list(self.property) {
+            if self.all {
+                list.iter().all(|v| self.element.matches(*v))
+            } else {
+                list.iter().any(|v| self.element.matches(*v))
+            }
+        } else {
+            false
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+/// Matches lists containing any (or if `all` is set, all) of the values.
+#[derive(Clone)]
+pub struct StringListContainsValuesCond {
+    pub(crate) property: Property,
+    pub(crate) values: StringInCond,
+    pub(crate) all: bool,
+}
+
+impl StringListContainsValuesCond {
+    pub fn filter(
+        property: Property,
+        values: &[Option<&str>],
+        case_sensitive: bool,
+        all: bool,
+    ) -> Result<Filter> {
+        if property.data_type == crate::object::data_type::DataType::StringList {
+            let values = StringInCond::new(property, values, case_sensitive);
+            Ok(Filter::StringListContainsValues(
+                StringListContainsValuesCond {
+                    property,
+                    values,
+                    all,
+                },
+            ))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for StringListContainsValuesCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let result = if let Some(list) = object.read_string_list(self.property) {
+            list_contains_values(list.into_iter(), self.all, self.values.len(), |v| {
+                self.values.find(v)
+            })
+        } else {
+            false
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+/// Matches lists of any type with a length between `lower` and `upper`. Null lists never match.
+#[derive(Clone)]
+pub struct ListLengthCond {
+    pub(crate) property: Property,
+    pub(crate) lower: usize,
+    pub(crate) upper: usize,
+}
+
+impl ListLengthCond {
+    pub fn filter(property: Property, lower: usize, upper: usize) -> Result<Filter> {
+        if property.data_type.is_list() {
+            Ok(Filter::ListLength(ListLengthCond {
+                property,
+                lower,
+                upper,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+}
+
+impl Condition for ListLengthCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let result = if let Some(length) = object.read_list_length(self.property) {
+            self.lower <= length && self.upper >= length
+        } else {
+            false
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+#[macro_export]
+macro_rules! null_filter {
+    ($name:ident, $null:expr) => {
+        paste! {
+            #[derive(Clone)]
+            pub struct [<$name Cond>] {
+                pub(crate) property: Property,
+            }
+
+            impl [<$name Cond>] {
+                pub fn filter(property: Property) -> Filter {
+                    Filter::$name(Self { property })
+                }
+            }
+
+            impl Condition for [<$name Cond>] {
+                fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+                    Ok(object.is_null(self.property) == $null)
+                }
+
+                fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+            }
+        }
+    };
+}
+
+null_filter!(IsNull, true);
+null_filter!(IsNotNull, false);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Ordinalize, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum Comparison {
+    Equal = 0,
+    Less = 1,
+    Greater = 2,
+}
+
+/// Compares two properties of the same object like [IsarObject::compare_property]. Null is
+/// smaller than every other value.
+#[derive(Clone)]
+pub struct PropertyCompareCond {
+    pub(crate) property: Property,
+    pub(crate) comparison: Comparison,
+    pub(crate) other_property: Property,
+}
+
+impl PropertyCompareCond {
+    pub fn filter(
+        property: Property,
+        comparison: Comparison,
+        other_property: Property,
+    ) -> Result<Filter> {
+        if property.data_type != other_property.data_type {
+            return illegal_arg("Both properties have to be of the same type.");
+        }
+        match property.data_type {
+            DataType::Byte
+            | DataType::Int
+            | DataType::Long
+            | DataType::Float
+            | DataType::Double
+            | DataType::String => Ok(Filter::PropertyCompare(PropertyCompareCond {
+                property,
+                comparison,
+                other_property,
+            })),
+            _ => illegal_arg("Property does not support this filter."),
+        }
+    }
+}
+
+impl Condition for PropertyCompareCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let ord = object.compare_property_to(self.property, &object, self.other_property);
+        let result = match self.comparison {
+            Comparison::Equal => ord == Ordering::Equal,
+            Comparison::Less => ord == Ordering::Less,
+            Comparison::Greater => ord == Ordering::Greater,
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+/// Matches objects where `property - other_property` is between `lower` and `upper`. Objects
+/// where either property is null do not match.
+#[derive(Clone)]
+pub struct PropertyOffsetBetweenCond {
+    pub(crate) property: Property,
+    pub(crate) other_property: Property,
+    pub(crate) lower: f64,
+    pub(crate) upper: f64,
+}
+
+impl PropertyOffsetBetweenCond {
+    pub fn filter(
+        property: Property,
+        other_property: Property,
+        lower: f64,
+        upper: f64,
+    ) -> Result<Filter> {
+        let numeric = |p: Property| {
+            matches!(
+                p.data_type,
+                DataType::Byte
+                    | DataType::Int
+                    | DataType::Long
+                    | DataType::Float
+                    | DataType::Double
+            )
+        };
+        if numeric(property) && numeric(other_property) {
+            Ok(Filter::PropertyOffsetBetween(PropertyOffsetBetweenCond {
+                property,
+                other_property,
+                lower,
+                upper,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+
+    fn as_integer(value: &PropertyValue) -> Option<i64> {
+        match value {
+            PropertyValue::Byte(value) => Some(*value as i64),
+            PropertyValue::Int(value) => Some(*value as i64),
+            PropertyValue::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_float(value: &PropertyValue) -> Option<f64> {
+        match value {
+            PropertyValue::Float(value) => Some(*value as f64),
+            PropertyValue::Double(value) => Some(*value),
+            value => Self::as_integer(value).map(|value| value as f64),
+        }
+    }
+}
+
+impl Condition for PropertyOffsetBetweenCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let value = PropertyValue::read(object, self.property);
+        let other = PropertyValue::read(object, self.other_property);
+        if let (Some(value), Some(other)) = (Self::as_integer(&value), Self::as_integer(&other)) {
+            // the offset of two longs is exact while f64 loses precision above 2^53
+            let offset = value as i128 - other as i128;
+            let in_range = !self.lower.is_nan()
+                && !self.upper.is_nan()
+                && self.lower.ceil() as i128 <= offset
+                && offset <= self.upper.floor() as i128;
+            Ok(in_range)
+        } else if let (Some(value), Some(other)) = (Self::as_float(&value), Self::as_float(&other))
+        {
+            let offset = value - other;
+            Ok(self.lower <= offset && offset <= self.upper)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Ordinalize, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum ParamOp {
+    Equal = 0,
+    Less = 1,
+    LessOrEqual = 2,
+    Greater = 3,
+    GreaterOrEqual = 4,
+    StartsWith = 5,
+    EndsWith = 6,
+    Contains = 7,
+    Matches = 8,
+}
+
+/// Compares a property with the value bound to the parameter `name`. Null is smaller than every
+/// other value. Does not match anything until a value is bound.
+#[derive(Clone)]
+pub struct ParamCond {
+    pub(crate) name: String,
+    pub(crate) property: Property,
+    pub(crate) op: ParamOp,
+    pub(crate) case_sensitive: bool,
+    value: Option<PropertyValue>,
+}
+
+impl ParamCond {
+    pub fn filter(
+        name: &str,
+        property: Property,
+        op: ParamOp,
+        case_sensitive: bool,
+    ) -> Result<Filter> {
+        let string_op = matches!(
+            op,
+            ParamOp::StartsWith | ParamOp::EndsWith | ParamOp::Contains | ParamOp::Matches
+        );
+        let supported = match property.data_type {
+            DataType::Byte
+            | DataType::Int
+            | DataType::Long
+            | DataType::Float
+            | DataType::Double => !string_op,
+            DataType::String => true,
+            _ => false,
+        };
+        if supported {
+            Ok(Filter::Param(ParamCond {
+                name: name.to_string(),
+                property,
+                op,
+                case_sensitive,
+                value: None,
+            }))
+        } else {
+            illegal_arg("Property does not support this filter.")
+        }
+    }
+
+    fn check(&self, value: &PropertyValue) -> Result<()> {
+        if value.is_type(self.property.data_type) {
+            Ok(())
+        } else {
+            illegal_arg("Parameter value does not match the property type.")
+        }
+    }
+
+    fn bind(&mut self, value: &PropertyValue) {
+        let value = match value {
+            PropertyValue::Int(value) => PropertyValue::int(*value),
+            PropertyValue::Long(value) => PropertyValue::long(*value),
+            PropertyValue::Float(value) => PropertyValue::float(*value),
+            PropertyValue::Double(value) => PropertyValue::double(*value),
+            PropertyValue::String(value) if !self.case_sensitive => {
+                PropertyValue::String(value.to_lowercase())
+            }
+            _ => value.clone(),
+        };
+        self.value = Some(value);
+    }
+
+    fn matches_ord(&self, ord: Ordering) -> bool {
+        match self.op {
+            ParamOp::Equal => ord == Ordering::Equal,
+            ParamOp::Less => ord == Ordering::Less,
+            ParamOp::LessOrEqual => ord != Ordering::Greater,
+            ParamOp::Greater => ord == Ordering::Greater,
+            ParamOp::GreaterOrEqual => ord != Ordering::Less,
+            _ => false,
+        }
+    }
+
+    fn matches_str(&self, string: &str, value: &str) -> bool {
+        match self.op {
+            ParamOp::StartsWith => string.starts_with(value),
+            ParamOp::EndsWith => string.ends_with(value),
+            ParamOp::Contains => string.contains(value),
+            ParamOp::Matches => fast_wild_match(string, value),
+            _ => self.matches_ord(string.cmp(value)),
+        }
+    }
+}
+
+impl Condition for ParamCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let value = if let Some(value) = &self.value {
+            value
+        } else {
+            return Ok(false);
+        };
+        let result = match value {
+            PropertyValue::String(value) => match object.read_string(self.property) {
+                Some(string) if self.case_sensitive => self.matches_str(string, value),
+                Some(string) => self.matches_str(&string.to_lowercase(), value),
+                None => self.matches_ord(Ordering::Less),
+            },
+            _ => {
+                let ord = PropertyValue::read(object, self.property).compare(value, None);
+                self.matches_ord(ord)
+            }
+        };
+        Ok(result)
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+#[derive(Clone)]
+pub struct AndCond {
+    pub(crate) filters: Vec<Filter>,
+}
+
+impl Condition for AndCond {
+    fn evaluate(
+        &self,
+        object: IsarObject,
+        mut cursors: Option<&mut FilterCursors>,
+    ) -> Result<bool> {
         for filter in &self.filters {
             if !filter.evaluate(object, cursors.as_deref_mut())? {
                 return Ok(false);
@@ -367,7 +1454,7 @@ impl AndCond {
 
 #[derive(Clone)]
 pub struct OrCond {
-    filters: Vec<Filter>,
+    pub(crate) filters: Vec<Filter>,
 }
 
 impl Condition for OrCond {
@@ -399,7 +1486,7 @@ impl OrCond {
 
 #[derive(Clone)]
 pub struct NotCond {
-    filter: Box<Filter>,
+    pub(crate) filter: Box<Filter>,
 }
 
 impl Condition for NotCond {
@@ -422,7 +1509,7 @@ impl NotCond {
 
 #[derive(Clone)]
 pub struct StaticCond {
-    value: bool,
+    pub(crate) value: bool,
 }
 
 impl Condition for StaticCond {
@@ -441,9 +1528,9 @@ impl StaticCond {
 
 #[derive(Clone)]
 pub struct LinkCond {
-    link: Link,
+    pub(crate) link: Link,
     id_property: Property,
-    filter: Box<Filter>,
+    pub(crate) filter: Box<Filter>,
 }
 
 impl Condition for LinkCond {
@@ -482,3 +1569,28 @@ impl LinkCond {
         }))
     }
 }
+
+/// Matches objects which are returned by any of the where clauses.
+#[derive(Clone)]
+pub struct WhereClauseCond {
+    pub(crate) where_clauses: Vec<WhereClause>,
+    id_property: Property,
+}
+
+impl Condition for WhereClauseCond {
+    fn evaluate(&self, object: IsarObject, _: Option<&mut FilterCursors>) -> Result<bool> {
+        let oid = object.read_long(self.id_property);
+        Ok(self.where_clauses.iter().any(|wc| wc.matches(oid, object)))
+    }
+
+    fn get_linked_collections(&self, _: &mut HashSet<u16>) {}
+}
+
+impl WhereClauseCond {
+    pub(crate) fn filter(where_clauses: Vec<WhereClause>, id_property: Property) -> Filter {
+        Filter::WhereClause(WhereClauseCond {
+            where_clauses,
+            id_property,
+        })
+    }
+}
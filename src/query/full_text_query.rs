@@ -0,0 +1,371 @@
+use crate::error::{IsarError, Result};
+use crate::index::analyzer::Analyzer;
+use crate::index::Index;
+use crate::lmdb::{ByteKey, IntKey};
+use crate::object::isar_object::IsarObject;
+use crate::query::filter::{Condition, Filter, FilterCursors};
+use crate::txn::{Cursors, IsarTxn};
+use hashbrown::{HashMap, HashSet};
+use std::cmp::Ordering;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+#[derive(Clone, Debug)]
+pub enum FullTextExpr {
+    Term(String),
+    Prefix(String),
+    Phrase(String),
+    And(Vec<FullTextExpr>),
+    Or(Vec<FullTextExpr>),
+}
+
+/// [FullTextExpr] after running it through the analyzer of the index.
+enum Node {
+    Terms(Vec<String>),
+    Prefix(String),
+    Phrase(Vec<String>),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+}
+
+impl Node {
+    fn analyze(expr: &FullTextExpr, analyzer: &Analyzer) -> Option<Node> {
+        match expr {
+            FullTextExpr::Term(term) => {
+                let tokens = analyzer.analyze(term);
+                (!tokens.is_empty()).then_some(Node::Terms(tokens))
+            }
+            FullTextExpr::Prefix(prefix) => analyzer.analyze_prefix(prefix).map(Node::Prefix),
+            FullTextExpr::Phrase(phrase) => {
+                let tokens = analyzer.analyze(phrase);
+                match tokens.len() {
+                    0 => None,
+                    1 => Some(Node::Terms(tokens)),
+                    _ => Some(Node::Phrase(tokens)),
+                }
+            }
+            FullTextExpr::And(exprs) => {
+                let nodes: Vec<Node> = exprs
+                    .iter()
+                    .filter_map(|e| Node::analyze(e, analyzer))
+                    .collect();
+                (!nodes.is_empty()).then_some(Node::And(nodes))
+            }
+            FullTextExpr::Or(exprs) => {
+                let nodes: Vec<Node> = exprs
+                    .iter()
+                    .filter_map(|e| Node::analyze(e, analyzer))
+                    .collect();
+                (!nodes.is_empty()).then_some(Node::Or(nodes))
+            }
+        }
+    }
+
+    fn matches(&self, tokens: &[String]) -> bool {
+        match self {
+            Node::Terms(terms) => terms.iter().all(|t| tokens.contains(t)),
+            Node::Prefix(prefix) => tokens.iter().any(|t| t.starts_with(prefix.as_str())),
+            Node::Phrase(phrase) => tokens.windows(phrase.len()).any(|w| w == phrase.as_slice()),
+            Node::And(nodes) => nodes.iter().all(|n| n.matches(tokens)),
+            Node::Or(nodes) => nodes.iter().any(|n| n.matches(tokens)),
+        }
+    }
+}
+
+/// Maps each term to the objects containing it and the term frequency within each object.
+type Postings = HashMap<String, HashMap<i64, u32>>;
+
+pub struct FullTextQuery {
+    index: Index,
+    expr: FullTextExpr,
+    filter: Option<Filter>,
+    limit: usize,
+}
+
+impl<'txn> FullTextQuery {
+    pub(crate) fn new(index: Index, expr: FullTextExpr) -> Self {
+        FullTextQuery {
+            index,
+            expr,
+            filter: None,
+            limit: usize::MAX,
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = Some(filter);
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// Returns the matching objects together with their relevance, best match first.
+    pub fn find_all_vec(&self, txn: &mut IsarTxn<'txn>) -> Result<Vec<(IsarObject<'txn>, f64)>> {
+        txn.read(|cursors| self.find_all_internal(cursors))
+    }
+
+    pub(crate) fn find_all_internal(
+        &self,
+        cursors: &mut Cursors<'txn>,
+    ) -> Result<Vec<(IsarObject<'txn>, f64)>> {
+        let node = Node::analyze(&self.expr, self.index.get_analyzer().unwrap());
+        let node = if let Some(node) = node {
+            node
+        } else {
+            return Ok(vec![]);
+        };
+
+        let mut postings = Postings::new();
+        let candidates = self.get_candidates(cursors, &node, &mut postings)?;
+
+        let (doc_count, token_count) = self.index.get_full_text_stats(cursors)?;
+        let avg_doc_len = if doc_count > 0 {
+            token_count as f64 / doc_count as f64
+        } else {
+            0.0
+        };
+
+        let mut results = vec![];
+        for oid in candidates {
+            let key = IntKey::new(self.index.get_col_id(), oid);
+            let (_, bytes) = cursors.data.move_to(key)?.ok_or(IsarError::DbCorrupted {
+                message: "Could not find object specified in index.".to_string(),
+            })?;
+            let object = IsarObject::from_bytes(bytes);
+
+            if let Some(filter) = &self.filter {
+                let mut filter_cursors = FilterCursors::new(&mut cursors.data2, &mut cursors.links);
+                if !filter.evaluate(object, Some(&mut filter_cursors))? {
+                    continue;
+                }
+            }
+
+            let tokens = self.index.get_full_text_tokens(object);
+            if !node.matches(&tokens) {
+                continue;
+            }
+
+            let doc_len = tokens.len() as f64;
+            let mut score = 0.0;
+            for docs in postings.values() {
+                if let Some(tf) = docs.get(&oid) {
+                    let df = docs.len() as f64;
+                    let idf = (1.0 + (doc_count as f64 - df + 0.5) / (df + 0.5)).ln();
+                    let tf = *tf as f64;
+                    let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc_len / avg_doc_len);
+                    score += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+                }
+            }
+            results.push((oid, object, score));
+        }
+
+        results.sort_unstable_by(|(oid1, _, score1), (oid2, _, score2)| {
+            score2
+                .partial_cmp(score1)
+                .unwrap_or(Ordering::Equal)
+                .then(oid1.cmp(oid2))
+        });
+        Ok(results
+            .into_iter()
+            .take(self.limit)
+            .map(|(_, object, score)| (object, score))
+            .collect())
+    }
+
+    /// Returns a superset of the matching objects. Phrases are verified later.
+    fn get_candidates(
+        &self,
+        cursors: &mut Cursors,
+        node: &Node,
+        postings: &mut Postings,
+    ) -> Result<HashSet<i64>> {
+        let candidates = match node {
+            Node::Terms(terms) | Node::Phrase(terms) => {
+                let mut result: Option<HashSet<i64>> = None;
+                for term in terms {
+                    self.load_term(cursors, term, postings)?;
+                    let oids = postings.get(term).unwrap().keys().copied().collect();
+                    result = Some(Self::intersect(result, oids));
+                }
+                result.unwrap_or_default()
+            }
+            Node::Prefix(prefix) => {
+                let mut result = HashSet::new();
+                self.scan_terms(cursors, prefix.as_bytes(), &[0xff], |term, oid, tf| {
+                    result.insert(oid);
+                    postings.entry(term).or_default().insert(oid, tf);
+                })?;
+                result
+            }
+            Node::And(nodes) => {
+                let mut result: Option<HashSet<i64>> = None;
+                for node in nodes {
+                    let oids = self.get_candidates(cursors, node, postings)?;
+                    result = Some(Self::intersect(result, oids));
+                }
+                result.unwrap_or_default()
+            }
+            Node::Or(nodes) => {
+                let mut result = HashSet::new();
+                for node in nodes {
+                    result.extend(self.get_candidates(cursors, node, postings)?);
+                }
+                result
+            }
+        };
+        Ok(candidates)
+    }
+
+    fn intersect(set: Option<HashSet<i64>>, other: HashSet<i64>) -> HashSet<i64> {
+        if let Some(set) = set {
+            set.intersection(&other).copied().collect()
+        } else {
+            other
+        }
+    }
+
+    fn load_term(&self, cursors: &mut Cursors, term: &str, postings: &mut Postings) -> Result<()> {
+        if !postings.contains_key(term) {
+            let mut docs = HashMap::new();
+            let mut term_bytes = term.as_bytes().to_vec();
+            term_bytes.push(0);
+            self.scan_terms(cursors, &term_bytes, &[], |_, oid, tf| {
+                docs.insert(oid, tf);
+            })?;
+            postings.insert(term.to_string(), docs);
+        }
+        Ok(())
+    }
+
+    fn scan_terms(
+        &self,
+        cursors: &mut Cursors,
+        prefix: &[u8],
+        upper_suffix: &[u8],
+        mut callback: impl FnMut(String, i64, u32),
+    ) -> Result<()> {
+        let mut lower = self.index.get_prefix();
+        lower.extend_from_slice(prefix);
+        let mut upper = lower.clone();
+        upper.extend_from_slice(upper_suffix);
+        upper.extend_from_slice(&[0xff; 5]);
+
+        cursors.index.iter_between(
+            ByteKey::new(&lower),
+            ByteKey::new(&upper),
+            false,
+            true,
+            |_, key, oid| {
+                // prefix + term + 0 + term frequency
+                let term_end = key.len() - 5;
+                let term = String::from_utf8_lossy(&key[2..term_end]).to_string();
+                let tf = u32::from_be_bytes([
+                    key[term_end + 1],
+                    key[term_end + 2],
+                    key[term_end + 3],
+                    key[term_end + 4],
+                ]);
+                callback(term, IntKey::from_bytes(oid).get_id(), tf);
+                Ok(true)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::isar;
+    use crate::object::data_type::DataType;
+    use crate::schema::collection_schema::{
+        AnalyzerSchema, CollectionSchema, IndexPropertySchema, IndexSchema, PropertySchema,
+    };
+
+    fn search(
+        txn: &mut IsarTxn,
+        col: &crate::collection::IsarCollection,
+        expr: FullTextExpr,
+    ) -> Vec<i64> {
+        let query = col.new_full_text_query(0, expr).unwrap();
+        query
+            .find_all_vec(txn)
+            .unwrap()
+            .iter()
+            .map(|(o, _)| o.read_long(col.get_oid_property()))
+            .collect()
+    }
+
+    #[test]
+    fn test_full_text_query() {
+        let index = IndexSchema::new(
+            vec![IndexPropertySchema::full_text(
+                "text",
+                false,
+                AnalyzerSchema::english(),
+            )],
+            false,
+            false,
+        );
+        let properties = vec![
+            PropertySchema::new("id", DataType::Long),
+            PropertySchema::new("text", DataType::String),
+        ];
+        let schema = CollectionSchema::new("col", "id", properties, vec![index], vec![]);
+        isar!(isar, col => schema.clone());
+
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        let texts = [
+            "The quick brown fox jumps over the lazy dog",
+            "A lazy afternoon with a brown dog",
+            "Foxes are quick. Foxes are clever. Foxes everywhere!",
+            "Nothing to see here",
+        ];
+        for (i, text) in texts.iter().enumerate() {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(i as i64 + 1);
+            builder.write_string(Some(*text));
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let term = |t: &str| FullTextExpr::Term(t.to_string());
+        assert_eq!(search(&mut txn, col, term("fox")), vec![3, 1]);
+        assert_eq!(search(&mut txn, col, term("the")), Vec::<i64>::new());
+        assert_eq!(
+            search(
+                &mut txn,
+                col,
+                FullTextExpr::And(vec![term("lazy"), term("dogs")])
+            ),
+            vec![2, 1]
+        );
+        assert_eq!(
+            search(
+                &mut txn,
+                col,
+                FullTextExpr::Or(vec![term("clever"), term("see")])
+            )
+            .len(),
+            2
+        );
+        assert_eq!(
+            search(&mut txn, col, FullTextExpr::Phrase("brown dog".to_string())),
+            vec![2]
+        );
+        assert_eq!(
+            search(&mut txn, col, FullTextExpr::Prefix("ever".to_string())),
+            vec![3]
+        );
+
+        col.delete(&mut txn, 3).unwrap();
+        assert_eq!(search(&mut txn, col, term("fox")), vec![1]);
+        txn.read(|cursors| {
+            let (docs, _) = col.get_indexes()[0].get_full_text_stats(cursors)?;
+            assert_eq!(docs, 3);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -1,6 +1,8 @@
+use crate::error::illegal_arg;
 use crate::lmdb::cursor::Cursor;
-use crate::lmdb::IntKey;
+use crate::lmdb::{IntKey, MAX_ID, MIN_ID};
 use crate::object::isar_object::IsarObject;
+use crate::object::property_value::PropertyValue;
 use crate::query::Sort;
 use crate::{collection::IsarCollection, error::Result};
 use hashbrown::HashSet;
@@ -8,9 +10,11 @@ use hashbrown::HashSet;
 #[derive(Clone)]
 pub(crate) struct IdWhereClause {
     prefix: u16,
-    lower: i64,
-    upper: i64,
-    sort: Sort,
+    pub(crate) lower: i64,
+    pub(crate) upper: i64,
+    pub(crate) sort: Sort,
+    pub(crate) lower_param: Option<String>,
+    pub(crate) upper_param: Option<String>,
 }
 
 impl IdWhereClause {
@@ -20,17 +24,129 @@ impl IdWhereClause {
             lower,
             upper,
             sort,
+            lower_param: None,
+            upper_param: None,
         }
     }
 
+    /// Creates a where clause whose bounds are parameters. It does not match anything until both
+    /// parameters are bound.
+    pub(crate) fn new_param(
+        col: &IsarCollection,
+        lower_param: &str,
+        upper_param: &str,
+        sort: Sort,
+    ) -> Self {
+        IdWhereClause {
+            prefix: col.get_id(),
+            lower: MAX_ID,
+            upper: MIN_ID,
+            sort,
+            lower_param: Some(lower_param.to_string()),
+            upper_param: Some(upper_param.to_string()),
+        }
+    }
+
+    fn has_param(&self, name: &str) -> bool {
+        self.lower_param.as_deref() == Some(name) || self.upper_param.as_deref() == Some(name)
+    }
+
+    /// Returns whether a bound is the parameter `name` and fails if `value` cannot be bound to it.
+    pub(crate) fn check_param(&self, name: &str, value: &PropertyValue) -> Result<bool> {
+        if !self.has_param(name) {
+            Ok(false)
+        } else if let PropertyValue::Long(_) = value {
+            Ok(true)
+        } else {
+            illegal_arg("Parameter value does not match the property type.")
+        }
+    }
+
+    /// Binds `value` to the parameter `name`. The value has to be checked using `check_param()`.
+    pub(crate) fn bind_param(&mut self, name: &str, value: &PropertyValue) {
+        if let PropertyValue::Long(id) = value {
+            let id = (*id).clamp(MIN_ID, MAX_ID);
+            if self.lower_param.as_deref() == Some(name) {
+                self.lower = id;
+            }
+            if self.upper_param.as_deref() == Some(name) {
+                self.upper = id;
+            }
+        }
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: Sort) {
+        self.sort = sort;
+    }
+
     pub fn is_empty(&self) -> bool {
         self.upper < self.lower
     }
 
+    /// Returns a where clause for the ids following `id` in the order of this where clause.
+    pub(crate) fn resume_after(&self, id: i64) -> Self {
+        let mut wc = self.clone();
+        if self.sort == Sort::Ascending {
+            wc.lower = wc.lower.max(id.saturating_add(1));
+        } else {
+            wc.upper = wc.upper.min(id.saturating_sub(1));
+        }
+        wc
+    }
+
+    pub(crate) fn estimate_rows(&self, object_count: u64) -> f64 {
+        let span = (self.upper as i128 - self.lower as i128 + 1).max(0) as f64;
+        span.min(object_count as f64)
+    }
+
     pub(crate) fn id_matches(&self, oid: i64) -> bool {
         self.lower <= oid && self.upper >= oid
     }
 
+    pub(crate) fn iter_ids(
+        &self,
+        data: &mut Cursor,
+        mut callback: impl FnMut(i64) -> Result<bool>,
+    ) -> Result<bool> {
+        data.iter_between(
+            IntKey::new(self.prefix, self.lower),
+            IntKey::new(self.prefix, self.upper),
+            false,
+            self.sort == Sort::Ascending,
+            |_, id, _| callback(IntKey::from_bytes(id).get_id()),
+        )
+    }
+
+    pub(crate) fn iter_step<'txn>(
+        &self,
+        data: &mut Cursor<'txn>,
+        first: bool,
+    ) -> Result<Option<(i64, IsarObject<'txn>)>> {
+        let entry = data.iter_between_step(
+            IntKey::new(self.prefix, self.lower),
+            IntKey::new(self.prefix, self.upper),
+            false,
+            self.sort == Sort::Ascending,
+            first,
+        )?;
+        Ok(entry.map(|(id, object)| {
+            let id = IntKey::from_bytes(id).get_id();
+            (id, IsarObject::from_bytes(object))
+        }))
+    }
+
+    /// Returns the id of the first entry in the range in the order of this where clause.
+    pub(crate) fn first_id(&self, data: &mut Cursor) -> Result<Option<i64>> {
+        let entry = data.iter_between_step(
+            IntKey::new(self.prefix, self.lower),
+            IntKey::new(self.prefix, self.upper),
+            false,
+            self.sort == Sort::Ascending,
+            true,
+        )?;
+        Ok(entry.map(|(id, _)| IntKey::from_bytes(id).get_id()))
+    }
+
     pub(crate) fn iter<'txn, F>(
         &self,
         data: &mut Cursor<'txn>,
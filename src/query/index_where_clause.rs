@@ -1,19 +1,35 @@
 use crate::error::{illegal_arg, IsarError, Result};
-use crate::index::index_key::IndexKey;
+use crate::index::index_key::{IndexKey, KeyParam};
 use crate::index::Index;
 use crate::lmdb::cursor::Cursor;
-use crate::lmdb::{ByteKey, IntKey};
+use crate::lmdb::{ByteKey, IntKey, Key};
 use crate::object::isar_object::IsarObject;
+use crate::object::property_value::PropertyValue;
+use crate::query::filter::Filter;
 use crate::query::Sort;
+use crate::txn::Cursors;
 use hashbrown::HashSet;
 
 #[derive(Clone)]
 pub(crate) struct IndexWhereClause {
-    lower_key: Vec<u8>,
-    upper_key: Vec<u8>,
+    pub(crate) lower_key: Vec<u8>,
+    pub(crate) upper_key: Vec<u8>,
     index: Index,
-    skip_duplicates: bool,
-    sort: Sort,
+    pub(crate) skip_duplicates: bool,
+    pub(crate) sort: Sort,
+    pub(crate) params: Option<Box<KeyParams>>,
+}
+
+/// The keys of a where clause with parameters before the values are inserted.
+#[derive(Clone)]
+pub(crate) struct KeyParams {
+    pub(crate) lower: Vec<u8>,
+    pub(crate) lower_params: Vec<KeyParam>,
+    pub(crate) include_lower: bool,
+    pub(crate) upper: Vec<u8>,
+    pub(crate) upper_params: Vec<KeyParam>,
+    pub(crate) include_upper: bool,
+    values: Vec<(String, Option<PropertyValue>)>,
 }
 
 impl IndexWhereClause {
@@ -35,13 +51,160 @@ impl IndexWhereClause {
             index,
             skip_duplicates,
             sort,
+            params: None,
         })
     }
 
+    /// Creates a where clause whose keys contain parameters. It does not match anything until
+    /// all parameters are bound.
+    pub(crate) fn with_params(
+        lower: IndexKey,
+        include_lower: bool,
+        upper: IndexKey,
+        include_upper: bool,
+        skip_duplicates: bool,
+        sort: Sort,
+    ) -> Result<Self> {
+        if lower.index != upper.index {
+            return illegal_arg("Lower key index does not match upper key index");
+        }
+        let mut values: Vec<(String, Option<PropertyValue>)> = vec![];
+        for param in lower.params.iter().chain(&upper.params) {
+            if !values.iter().any(|(name, _)| *name == param.name) {
+                values.push((param.name.clone(), None));
+            }
+        }
+        let mut wc = IndexWhereClause {
+            lower_key: vec![],
+            upper_key: vec![],
+            index: lower.index.clone(),
+            skip_duplicates,
+            sort,
+            params: Some(Box::new(KeyParams {
+                lower: lower.bytes,
+                lower_params: lower.params,
+                include_lower,
+                upper: upper.bytes,
+                upper_params: upper.params,
+                include_upper,
+                values,
+            })),
+        };
+        wc.update_keys()?;
+        Ok(wc)
+    }
+
+    /// Returns whether the keys contain the parameter `name` and fails if `value` cannot be bound
+    /// to it.
+    pub(crate) fn check_param(&self, name: &str, value: &PropertyValue) -> Result<bool> {
+        let params = if let Some(params) = &self.params {
+            params
+        } else {
+            return Ok(false);
+        };
+        let mut found = false;
+        for param in params.lower_params.iter().chain(&params.upper_params) {
+            if param.name == name {
+                let ip = &self.index.properties[param.position];
+                if !value.is_type(ip.property.data_type) {
+                    return illegal_arg("Parameter value does not match the property type.");
+                }
+                found = true;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Binds `value` to the parameter `name`. The value has to be checked using `check_param()`.
+    pub(crate) fn bind_param(&mut self, name: &str, value: &PropertyValue) -> Result<()> {
+        let params = if let Some(params) = &mut self.params {
+            params
+        } else {
+            return Ok(());
+        };
+        if let Some((_, bound)) = params.values.iter_mut().find(|(n, _)| *n == name) {
+            *bound = Some(value.clone());
+            self.update_keys()?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the keys from the templates reusing their buffers. The range is empty as long as
+    /// a parameter is unbound.
+    fn update_keys(&mut self) -> Result<()> {
+        let params = if let Some(params) = self.params.as_deref() {
+            params
+        } else {
+            return Ok(());
+        };
+        let mut empty = params.values.iter().any(|(_, value)| value.is_none());
+        if !empty {
+            let buffer = std::mem::take(&mut self.lower_key);
+            self.lower_key = IndexKey::from_template(
+                &self.index,
+                &params.lower,
+                &params.lower_params,
+                &params.values,
+                buffer,
+            )?
+            .bytes;
+            let buffer = std::mem::take(&mut self.upper_key);
+            self.upper_key = IndexKey::from_template(
+                &self.index,
+                &params.upper,
+                &params.upper_params,
+                &params.values,
+                buffer,
+            )?
+            .bytes;
+            let (include_lower, include_upper) = (params.include_lower, params.include_upper);
+            empty = !self.try_exclude(include_lower, include_upper);
+        }
+        if empty {
+            let prefix = self.index.get_prefix();
+            self.upper_key.clear();
+            self.upper_key.extend_from_slice(&prefix);
+            self.lower_key.clear();
+            self.lower_key.extend_from_slice(&prefix);
+            self.lower_key.push(u8::MAX);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_index(&self) -> &Index {
+        &self.index
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: Sort) {
+        self.sort = sort;
+    }
+
     pub fn is_empty(&self) -> bool {
         ByteKey::new(&self.lower_key) > ByteKey::new(&self.upper_key)
     }
 
+    pub fn is_multi_entry(&self) -> bool {
+        self.index.multiple()
+    }
+
+    pub(crate) fn skips_duplicates(&self) -> bool {
+        self.skip_duplicates
+    }
+
+    pub(crate) fn estimate_rows(&self, cursors: &mut Cursors) -> Result<f64> {
+        let stats = self.index.get_stats(cursors)?;
+        Ok(stats.estimate_between(&self.lower_key, &self.upper_key))
+    }
+
+    /// Partial indexes may only be used if the query filter implies the index filter.
+    pub fn is_usable(&self, filter: Option<&Filter>) -> bool {
+        match (self.index.get_filter(), filter) {
+            (None, _) => true,
+            (Some(index_filter), Some(filter)) => filter.implies(index_filter),
+            (Some(_), None) => false,
+        }
+    }
+
     pub(crate) fn object_matches(&self, object: IsarObject) -> bool {
         let mut key_matches = false;
         self.index
@@ -75,6 +238,130 @@ impl IndexWhereClause {
         )
     }
 
+    pub(crate) fn iter_ids_step(&self, index: &mut Cursor, first: bool) -> Result<Option<i64>> {
+        let entry = self.iter_entries_step(index, first)?;
+        Ok(entry.map(|(_, id)| id))
+    }
+
+    pub(crate) fn iter_entries_step<'txn>(
+        &self,
+        index: &mut Cursor<'txn>,
+        first: bool,
+    ) -> Result<Option<(&'txn [u8], i64)>> {
+        let entry = index.iter_between_step(
+            ByteKey::new(&self.lower_key),
+            ByteKey::new(&self.upper_key),
+            self.skip_duplicates,
+            self.sort == Sort::Ascending,
+            first,
+        )?;
+        Ok(entry.map(|(key, id)| (key, IntKey::from_bytes(id).get_id())))
+    }
+
+    /// Returns the id of the first entry in the range in the order of this where clause.
+    pub(crate) fn first_id(&self, index: &mut Cursor) -> Result<Option<i64>> {
+        let entry = index.iter_between_step(
+            ByteKey::new(&self.lower_key),
+            ByteKey::new(&self.upper_key),
+            false,
+            self.sort == Sort::Ascending,
+            true,
+        )?;
+        Ok(entry.map(|(_, id)| IntKey::from_bytes(id).get_id()))
+    }
+
+    /// Moves to the entry following (`key`, `id`) in the order of this where clause and returns
+    /// it. The entry (`key`, `id`) itself does not have to exist anymore. The iteration can be
+    /// continued using `iter_entries_step()` with `first = false`.
+    pub(crate) fn resume_after<'txn>(
+        &self,
+        index: &mut Cursor<'txn>,
+        key: &[u8],
+        id: i64,
+    ) -> Result<Option<(&'txn [u8], i64)>> {
+        let ascending = self.sort == Sort::Ascending;
+        let id_key = IntKey::new(self.index.get_col_id(), id);
+        let entry = match index.move_to_key_val_gte(ByteKey::new(key), id_key.as_bytes())? {
+            Some((key, val)) if ascending && !self.skip_duplicates && val != id_key.as_bytes() => {
+                Some((key, val))
+            }
+            Some(_) => return self.iter_entries_step(index, false),
+            // all remaining ids of the key are smaller than `id`
+            None if index.move_to(ByteKey::new(key))?.is_some() => {
+                let last = index.move_to_last_dup()?;
+                if ascending || self.skip_duplicates {
+                    return self.iter_entries_step(index, false);
+                }
+                last
+            }
+            // the key does not exist anymore
+            None => {
+                let greater = index.move_to_gte(ByteKey::new(key))?;
+                if ascending {
+                    greater
+                } else if greater.is_some() {
+                    index.move_to_prev()?
+                } else {
+                    index.move_to_last()?
+                }
+            }
+        };
+
+        Ok(entry.and_then(|(key, id)| {
+            let in_range = if ascending {
+                ByteKey::new(key) <= ByteKey::new(&self.upper_key)
+            } else {
+                ByteKey::new(key) >= ByteKey::new(&self.lower_key)
+            };
+            if in_range {
+                Some((key, IntKey::from_bytes(id).get_id()))
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Calls `callback` with the key and the id of every entry.
+    pub(crate) fn iter_entries<'txn, F>(
+        &self,
+        index: &mut Cursor<'txn>,
+        mut callback: F,
+    ) -> Result<bool>
+    where
+        F: FnMut(&'txn [u8], i64) -> Result<bool>,
+    {
+        index.iter_between(
+            ByteKey::new(&self.lower_key),
+            ByteKey::new(&self.upper_key),
+            self.skip_duplicates,
+            self.sort == Sort::Ascending,
+            |_, key, id| callback(key, IntKey::from_bytes(id).get_id()),
+        )
+    }
+
+    /// Calls `callback` with every key and whether it differs from the previous key.
+    pub(crate) fn iter_keys<'txn, F>(
+        &self,
+        index: &mut Cursor<'txn>,
+        mut callback: F,
+    ) -> Result<bool>
+    where
+        F: FnMut(&'txn [u8], bool) -> Result<bool>,
+    {
+        let mut previous_key: Option<&[u8]> = None;
+        index.iter_between(
+            ByteKey::new(&self.lower_key),
+            ByteKey::new(&self.upper_key),
+            false,
+            self.sort == Sort::Ascending,
+            |_, key, _| {
+                let new_key = previous_key != Some(key);
+                previous_key = Some(key);
+                callback(key, new_key)
+            },
+        )
+    }
+
     pub(crate) fn iter<'txn, F>(
         &self,
         data: &mut Cursor<'txn>,
@@ -0,0 +1,217 @@
+use crate::collection::IsarCollection;
+use crate::error::{IsarError, Result};
+use crate::lmdb::cursor::Cursor;
+use crate::lmdb::IntKey;
+use crate::object::isar_object::IsarObject;
+use crate::query::where_clause::WhereClause;
+use hashbrown::HashSet;
+use std::cmp::Ordering;
+
+/*
+The where clauses of a group are unioned and the groups are intersected. Only the ids of every
+group are read from the index db. The intersection is computed on sorted id sets so objects are
+only loaded if they are part of every group.
+
+Groups consisting only of id where clauses do not need to be read, their ids are checked directly.
+ */
+#[derive(Clone)]
+pub(crate) struct IntersectionWhereClause {
+    pub(crate) groups: Vec<Vec<WhereClause>>,
+    needs_verification: bool,
+    col_id: u16,
+}
+
+impl IntersectionWhereClause {
+    pub(crate) fn new(col: &IsarCollection, groups: Vec<Vec<WhereClause>>) -> Self {
+        let needs_verification = groups
+            .iter()
+            .flatten()
+            .any(|wc| matches!(wc, WhereClause::NGram(_) | WhereClause::Intersection(_)));
+        IntersectionWhereClause {
+            groups,
+            needs_verification,
+            col_id: col.get_id(),
+        }
+    }
+
+    pub(crate) fn get_col_id(&self) -> u16 {
+        self.col_id
+    }
+
+    pub(crate) fn get_groups(&self) -> &[Vec<WhereClause>] {
+        &self.groups
+    }
+
+    pub(crate) fn object_matches(&self, id: i64, object: IsarObject) -> bool {
+        self.groups
+            .iter()
+            .all(|group| group.iter().any(|wc| wc.matches(id, object)))
+    }
+
+    fn is_id_group(group: &[WhereClause]) -> bool {
+        !group.is_empty() && group.iter().all(|wc| matches!(wc, WhereClause::Id(_)))
+    }
+
+    fn get_group_ids(
+        group: &[WhereClause],
+        data: &mut Cursor,
+        index: &mut Cursor,
+    ) -> Result<Vec<i64>> {
+        let mut ids = vec![];
+        for wc in group {
+            match wc {
+                WhereClause::Id(wc) => {
+                    wc.iter_ids(data, |id| {
+                        ids.push(id);
+                        Ok(true)
+                    })?;
+                }
+                WhereClause::Index(wc) => {
+                    wc.iter_ids(index, |_, id| {
+                        ids.push(id.get_id());
+                        Ok(true)
+                    })?;
+                }
+                WhereClause::NGram(wc) => ids.extend(wc.get_candidate_ids(data, index)?),
+                WhereClause::Intersection(wc) => ids.extend(wc.get_ids(data, index)?),
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    fn intersect(ids1: Vec<i64>, ids2: &[i64]) -> Vec<i64> {
+        let mut result = Vec::with_capacity(ids1.len().min(ids2.len()));
+        let mut i = 0;
+        let mut j = 0;
+        while i < ids1.len() && j < ids2.len() {
+            match ids1[i].cmp(&ids2[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    result.push(ids1[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the sorted ids which are part of every group.
+    pub(crate) fn get_ids(&self, data: &mut Cursor, index: &mut Cursor) -> Result<Vec<i64>> {
+        let mut result: Option<Vec<i64>> = None;
+        for group in self.groups.iter().filter(|g| !Self::is_id_group(g)) {
+            if result.as_ref().is_some_and(|ids| ids.is_empty()) {
+                return Ok(vec![]);
+            }
+            let ids = Self::get_group_ids(group, data, index)?;
+            result = Some(match result {
+                Some(result) => Self::intersect(result, &ids),
+                None => ids,
+            });
+        }
+
+        let mut id_groups = self.groups.iter().filter(|g| Self::is_id_group(g));
+        let mut result = if let Some(result) = result {
+            result
+        } else if let Some(group) = id_groups.next() {
+            Self::get_group_ids(group, data, index)?
+        } else {
+            return Ok(vec![]);
+        };
+        for group in id_groups {
+            result.retain(|id| {
+                group
+                    .iter()
+                    .any(|wc| matches!(wc, WhereClause::Id(wc) if wc.id_matches(*id)))
+            });
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn iter<'txn, F>(
+        &self,
+        data: &mut Cursor<'txn>,
+        index: &mut Cursor<'txn>,
+        mut result_ids: Option<&mut HashSet<i64>>,
+        mut callback: F,
+    ) -> Result<bool>
+    where
+        F: FnMut(&mut Cursor<'txn>, IsarObject<'txn>) -> Result<bool>,
+    {
+        for id in self.get_ids(data, index)? {
+            if let Some(result_ids) = result_ids.as_deref_mut() {
+                if result_ids.contains(&id) {
+                    continue;
+                }
+            }
+
+            let entry = data.move_to(IntKey::new(self.col_id, id))?;
+            let (_, object) = entry.ok_or(IsarError::DbCorrupted {
+                message: "Could not find object specified in index.".to_string(),
+            })?;
+            let object = IsarObject::from_bytes(object);
+            if self.needs_verification && !self.object_matches(id, object) {
+                continue;
+            }
+            if let Some(result_ids) = result_ids.as_deref_mut() {
+                result_ids.insert(id);
+            }
+            if !callback(data, object)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::data_type::DataType;
+    use crate::query::Sort;
+    use crate::{col, ind, isar};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_intersection() {
+        isar!(isar, col => col!(oid => DataType::Long, city => DataType::Int, age => DataType::Int; ind!(city), ind!(age)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..40 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int((oid % 4) as i32);
+            builder.write_int(oid as i32);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let mut qb = col.new_query_builder();
+        let mut city = col.new_index_key(0).unwrap();
+        city.add_int(1);
+        qb.add_index_where_clause(city.clone(), true, city, true, false, Sort::Ascending)
+            .unwrap();
+        qb.add_where_clause_group();
+        let mut lower = col.new_index_key(1).unwrap();
+        lower.add_int(20);
+        let mut upper = col.new_index_key(1).unwrap();
+        upper.add_int(30);
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Ascending)
+            .unwrap();
+        qb.add_id_where_clause(35, 37, Sort::Ascending).unwrap();
+        qb.add_where_clause_group();
+        qb.add_id_where_clause(0, 36, Sort::Ascending).unwrap();
+
+        let oids = qb
+            .build()
+            .find_all_vec(&mut txn)
+            .unwrap()
+            .iter()
+            .map(|o| o.read_long(col.get_oid_property()))
+            .collect_vec();
+        assert_eq!(oids, vec![21, 25, 29]);
+
+        txn.abort();
+        isar.close();
+    }
+}
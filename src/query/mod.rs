@@ -1,26 +1,56 @@
 use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::hash::Hasher;
 
 use hashbrown::HashSet;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use wyhash::WyHash;
 
 use crate::collection::IsarCollection;
-use crate::error::Result;
+use crate::error::{illegal_arg, IsarError, Result};
+use crate::index::Index;
+use crate::lmdb::IntKey;
+use crate::object::collation::Collation;
+use crate::object::data_type::DataType;
 use crate::object::isar_object::{IsarObject, Property};
 use crate::object::json_encode_decode::JsonEncodeDecode;
+use crate::object::property_value::PropertyValue;
+use crate::query::explain::{ExecutionStats, SortStrategy};
+use crate::query::external_sort::ExternalSorter;
 use crate::query::filter::{Condition, Filter, StaticCond};
+use crate::query::query_iter::QueryIter;
 use crate::query::where_clause::WhereClause;
+use crate::schema::collection_schema::IndexType;
 use crate::txn::{Cursors, IsarTxn};
 
+pub mod aggregation;
+mod edit_distance;
+pub mod explain;
+mod external_sort;
 mod fast_wild_match;
 pub mod filter;
+pub mod full_text_query;
 pub mod id_where_clause;
 pub mod index_where_clause;
+pub mod intersection_where_clause;
+pub mod ngram_where_clause;
+pub mod pagination;
+mod planner;
 pub mod query_builder;
+pub mod query_iter;
+pub mod query_json;
+pub mod query_parser;
 mod where_clause;
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// Sorted queries with a smaller `offset + limit` only keep the best results in memory.
+const MAX_TOP_K: usize = 1000;
+
+pub const DEFAULT_SORT_MEMORY_BUDGET: usize = 32 * 1024 * 1024;
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Sort {
     Ascending,
     Descending,
@@ -31,15 +61,44 @@ pub enum Case {
     Insensitive,
 }
 
+struct TopKEntry<'a, 'txn> {
+    object: IsarObject<'txn>,
+    sort: &'a [(Property, Sort, Option<Collation>)],
+}
+
+impl Ord for TopKEntry<'_, '_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Query::compare_objects(self.sort, &self.object, &other.object)
+    }
+}
+
+impl PartialOrd for TopKEntry<'_, '_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopKEntry<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TopKEntry<'_, '_> {}
+
 #[derive(Clone)]
 pub struct Query {
     where_clauses: Vec<WhereClause>,
     where_clauses_overlapping: bool,
     filter: Option<Filter>,
-    sort: Vec<(Property, Sort)>,
+    sort: Vec<(Property, Sort, Option<Collation>)>,
     distinct: Vec<(Property, bool)>,
     offset: usize,
     limit: usize,
+    oid_property: Property,
+    col_id: u16,
+    where_clause_sorted: bool,
+    sort_memory_budget: usize,
 }
 
 impl<'txn> Query {
@@ -47,23 +106,98 @@ impl<'txn> Query {
     pub(crate) fn new(
         where_clauses: Vec<WhereClause>,
         filter: Option<Filter>,
-        sort: Vec<(Property, Sort)>,
+        sort: Vec<(Property, Sort, Option<Collation>)>,
         distinct: Vec<(Property, bool)>,
         offset: usize,
         limit: usize,
+        collection: &IsarCollection,
+        sort_memory_budget: usize,
     ) -> Self {
+        let oid_property = collection.get_oid_property();
+        let mut where_clauses = where_clauses;
+        let where_clauses_overlapping =
+            where_clauses.len() > 1 || where_clauses.iter().any(|wc| wc.is_multi_entry());
+        let where_clause_sorted = Self::sort_where_clause(&mut where_clauses, &sort, oid_property);
         Query {
             where_clauses,
-            where_clauses_overlapping: true,
+            where_clauses_overlapping,
             filter,
             sort,
             distinct,
             offset,
             limit,
+            oid_property,
+            col_id: collection.get_id(),
+            where_clause_sorted,
+            sort_memory_budget,
         }
     }
 
-    pub(crate) fn execute_raw<F>(&self, cursors: &mut Cursors<'txn>, mut callback: F) -> Result<()>
+    /// Changes the order of a single where clause to the requested sort if its keys are ordered
+    /// like the sort properties. Returns whether the where clause yields the requested order.
+    fn sort_where_clause(
+        where_clauses: &mut [WhereClause],
+        sort: &[(Property, Sort, Option<Collation>)],
+        oid_property: Property,
+    ) -> bool {
+        let direction = if let Some((_, direction, _)) = sort.first() {
+            *direction
+        } else {
+            return false;
+        };
+        if sort.iter().any(|(_, d, _)| *d != direction) {
+            return false;
+        }
+        match where_clauses {
+            [WhereClause::Id(wc)] if sort.len() == 1 && sort[0].0 == oid_property => {
+                wc.set_sort(direction);
+                true
+            }
+            [WhereClause::Index(wc)] => {
+                let index = wc.get_index();
+                let key_ordered =
+                    !index.multiple() && Self::index_sort_prefix(index, sort) == sort.len();
+                if key_ordered {
+                    wc.set_sort(direction);
+                }
+                key_ordered
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the number of leading sort properties with the direction of the first one whose
+    /// order is the order of the keys of `index`.
+    pub(crate) fn index_sort_prefix(
+        index: &Index,
+        sort: &[(Property, Sort, Option<Collation>)],
+    ) -> usize {
+        sort.iter()
+            .zip(&index.properties)
+            .take_while(|((p, d, c), ip)| {
+                *d == sort[0].1
+                    && ip.property == *p
+                    && (p.data_type != DataType::String
+                        || (ip.index_type == IndexType::Value
+                            && ip.case_sensitive == Some(true)
+                            && ip.collation == *c))
+            })
+            .count()
+    }
+
+    pub(crate) fn execute_raw<F>(&self, cursors: &mut Cursors<'txn>, callback: F) -> Result<()>
+    where
+        F: FnMut(IsarObject<'txn>) -> Result<bool>,
+    {
+        self.execute_raw_with_stats(cursors, None, callback)
+    }
+
+    fn execute_raw_with_stats<F>(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        mut stats: Option<&mut ExecutionStats>,
+        mut callback: F,
+    ) -> Result<()>
     where
         F: FnMut(IsarObject<'txn>) -> Result<bool>,
     {
@@ -76,12 +210,18 @@ impl<'txn> Query {
         let static_filter = StaticCond::filter(true);
         let filter = self.filter.as_ref().unwrap_or(&static_filter);
 
-        for where_clause in &self.where_clauses {
+        for (i, where_clause) in self.where_clauses.iter().enumerate() {
             let result =
                 where_clause.iter(cursors, result_ids.as_mut(), |filter_cursors, object| {
+                    if let Some(stats) = &mut stats {
+                        stats.scanned[i] += 1;
+                    }
                     if filter.evaluate(object, Some(filter_cursors))? {
                         callback(object)
                     } else {
+                        if let Some(stats) = &mut stats {
+                            stats.filtered_out += 1;
+                        }
                         Ok(true)
                     }
                 })?;
@@ -93,17 +233,22 @@ impl<'txn> Query {
         Ok(())
     }
 
-    fn execute_unsorted<F>(&self, cursors: &mut Cursors<'txn>, callback: F) -> Result<()>
+    fn execute_unsorted<F>(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        stats: Option<&mut ExecutionStats>,
+        callback: F,
+    ) -> Result<()>
     where
         F: FnMut(IsarObject<'txn>) -> Result<bool>,
     {
         if !self.distinct.is_empty() {
-            let callback = self.add_distinct_unsorted(callback);
             let callback = self.add_offset_limit_unsorted(callback);
-            self.execute_raw(cursors, callback)
+            let callback = self.add_distinct_unsorted(callback);
+            self.execute_raw_with_stats(cursors, stats, callback)
         } else {
             let callback = self.add_offset_limit_unsorted(callback);
-            self.execute_raw(cursors, callback)
+            self.execute_raw_with_stats(cursors, stats, callback)
         }
     }
 
@@ -150,57 +295,208 @@ impl<'txn> Query {
         }
     }
 
-    fn execute_sorted(&self, cursors: &mut Cursors<'txn>) -> Result<Vec<IsarObject<'txn>>> {
-        let mut results = vec![];
-        self.execute_raw(cursors, |object| {
-            results.push(object);
+    pub(crate) fn compare_objects(
+        sort: &[(Property, Sort, Option<Collation>)],
+        o1: &IsarObject,
+        o2: &IsarObject,
+    ) -> Ordering {
+        for (p, sort, collation) in sort {
+            let ord = o1.compare_property_with_collation(o2, *p, *collation);
+            if ord != Ordering::Equal {
+                return if *sort == Sort::Ascending {
+                    ord
+                } else {
+                    ord.reverse()
+                };
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Keeps only the best `offset + limit` results in a bounded heap.
+    fn execute_top_k(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        stats: Option<&mut ExecutionStats>,
+    ) -> Result<Vec<IsarObject<'txn>>> {
+        let k = self.offset.saturating_add(self.limit);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.execute_raw_with_stats(cursors, stats, |object| {
+            heap.push(TopKEntry {
+                object,
+                sort: &self.sort,
+            });
+            if heap.len() > k {
+                heap.pop();
+            }
             Ok(true)
         })?;
+        let results = heap
+            .into_sorted_vec()
+            .into_iter()
+            .skip(self.offset)
+            .map(|entry| entry.object)
+            .collect();
+        Ok(results)
+    }
 
-        results.sort_unstable_by(|o1, o2| {
-            for (p, sort) in &self.sort {
-                let ord = o1.compare_property(o2, *p);
-                if ord != Ordering::Equal {
-                    return if *sort == Sort::Ascending {
-                        ord
-                    } else {
-                        ord.reverse()
-                    };
-                }
-            }
-            Ordering::Equal
-        });
+    /// Sorts the results using at most `sort_memory_budget` bytes. Larger results are sorted on
+    /// disk.
+    fn execute_sorted<F>(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        stats: Option<&mut ExecutionStats>,
+        callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(IsarObject<'txn>) -> Result<bool>,
+    {
+        let mut sorter = ExternalSorter::new(
+            &self.sort,
+            self.sort_memory_budget,
+            self.oid_property,
+            self.col_id,
+        );
+        self.execute_raw_with_stats(cursors, stats, |object| {
+            sorter.add(object)?;
+            Ok(true)
+        })?;
 
+        let callback = self.add_offset_limit_unsorted(callback);
         if !self.distinct.is_empty() {
-            Ok(self.add_distinct_sorted(results))
+            let callback = self.add_distinct_unsorted(callback);
+            sorter.finish(&mut cursors.data, callback)
         } else {
-            Ok(results)
+            sorter.finish(&mut cursors.data, callback)
         }
     }
 
-    fn add_distinct_sorted(&self, results: Vec<IsarObject<'txn>>) -> Vec<IsarObject<'txn>> {
-        let properties = self.distinct.clone();
-        let mut hashes = HashSet::new();
-        results
-            .into_iter()
-            .filter(|object| {
-                let mut hasher = WyHash::default();
-                for (property, case_sensitive) in &properties {
-                    object.hash_property(*property, *case_sensitive, &mut hasher);
-                }
-                let hash = hasher.finish();
-                hashes.insert(hash)
-            })
-            .collect()
+    /// Whether the results and the values of `properties` can be read from the keys of the where
+    /// clauses without loading objects.
+    fn is_covered(&self, properties: &[Property]) -> bool {
+        if self.filter.is_some() || (!self.sort.is_empty() && !self.where_clause_sorted) {
+            return false;
+        }
+        let required = properties
+            .iter()
+            .chain(self.distinct.iter().map(|(p, _)| p))
+            .filter(|p| **p != self.oid_property)
+            .collect_vec();
+        self.where_clauses.iter().all(|wc| match wc {
+            WhereClause::Id(_) => required.is_empty(),
+            WhereClause::Index(wc) => {
+                let index = wc.get_index();
+                required.iter().all(|p| index.covers_property(**p))
+            }
+            _ => false,
+        })
     }
 
-    fn add_offset_limit_sorted(
+    /// Executes the query using only the keys of the where clauses. Objects are only loaded if a
+    /// value cannot be restored from its key.
+    fn execute_covered<F>(
         &self,
-        results: Vec<IsarObject<'txn>>,
-    ) -> impl IntoIterator<Item = IsarObject<'txn>> {
+        cursors: &mut Cursors<'txn>,
+        properties: &[Property],
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(i64, Vec<PropertyValue>) -> Result<bool>,
+    {
+        let required = properties
+            .iter()
+            .chain(self.distinct.iter().map(|(p, _)| p))
+            .copied()
+            .collect_vec();
+        let mut result_ids = if self.where_clauses_overlapping {
+            Some(HashSet::<i64>::new())
+        } else {
+            None
+        };
+        let mut hashes = HashSet::new();
         let offset = self.offset;
-        let limit = self.limit;
-        results.into_iter().skip(offset).take(limit)
+        let max_count = self.limit.saturating_add(offset);
+        let mut count = 0;
+        let mut emit = |id: i64, mut values: Vec<PropertyValue>| -> Result<bool> {
+            if let Some(result_ids) = &mut result_ids {
+                if !result_ids.insert(id) {
+                    return Ok(true);
+                }
+            }
+            if !self.distinct.is_empty() {
+                let mut hasher = WyHash::default();
+                let distinct_values = &values[properties.len()..];
+                for ((_, case_sensitive), value) in self.distinct.iter().zip(distinct_values) {
+                    value.hash(*case_sensitive, &mut hasher);
+                }
+                if !hashes.insert(hasher.finish()) {
+                    return Ok(true);
+                }
+            }
+            count += 1;
+            if count > max_count {
+                Ok(false)
+            } else if count > offset {
+                values.truncate(properties.len());
+                callback(id, values)
+            } else {
+                Ok(true)
+            }
+        };
+
+        let Cursors { data, index, .. } = cursors;
+        for where_clause in &self.where_clauses {
+            let result = match where_clause {
+                WhereClause::Id(wc) => wc.iter_ids(data, |id| {
+                    let values = required.iter().map(|_| PropertyValue::Long(id)).collect();
+                    emit(id, values)
+                })?,
+                WhereClause::Index(wc) => {
+                    let wc_index = wc.get_index();
+                    wc.iter_entries(index, |key, id| {
+                        let key_values = if !required.is_empty() {
+                            wc_index.read_key_values(key)
+                        } else {
+                            vec![]
+                        };
+                        let mut object = None;
+                        let mut values = Vec::with_capacity(required.len());
+                        for property in &required {
+                            let value = if *property == self.oid_property {
+                                Some(PropertyValue::Long(id))
+                            } else {
+                                wc_index
+                                    .properties
+                                    .iter()
+                                    .position(|ip| ip.property == *property)
+                                    .and_then(|i| key_values[i].clone())
+                            };
+                            let value = if let Some(value) = value {
+                                value
+                            } else {
+                                if object.is_none() {
+                                    let key = IntKey::new(wc_index.get_col_id(), id);
+                                    let (_, bytes) =
+                                        data.move_to(key)?.ok_or(IsarError::DbCorrupted {
+                                            message: "Could not find object specified in index."
+                                                .to_string(),
+                                        })?;
+                                    object = Some(IsarObject::from_bytes(bytes));
+                                }
+                                PropertyValue::read(object.unwrap(), *property)
+                            };
+                            values.push(value);
+                        }
+                        emit(id, values)
+                    })?
+                }
+                _ => unreachable!(),
+            };
+            if !result {
+                break;
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn matches_wc_filter(&self, id: i64, object: IsarObject) -> bool {
@@ -216,25 +512,76 @@ impl<'txn> Query {
         }
     }
 
+    fn get_sort_strategy(&self) -> SortStrategy {
+        if self.sort.is_empty() {
+            SortStrategy::None
+        } else if self.where_clause_sorted {
+            SortStrategy::WhereClause
+        } else if self.distinct.is_empty() && self.offset.saturating_add(self.limit) <= MAX_TOP_K {
+            SortStrategy::TopK
+        } else {
+            SortStrategy::External
+        }
+    }
+
     pub(crate) fn find_all_internal<F>(
         &self,
         cursors: &mut Cursors<'txn>,
         skip_sorting: bool,
+        callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(IsarObject<'txn>) -> Result<bool>,
+    {
+        self.find_all_with_stats(cursors, skip_sorting, None, callback)
+    }
+
+    fn find_all_with_stats<F>(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        skip_sorting: bool,
+        stats: Option<&mut ExecutionStats>,
         mut callback: F,
     ) -> Result<()>
     where
         F: FnMut(IsarObject<'txn>) -> Result<bool>,
     {
-        if self.sort.is_empty() || skip_sorting {
-            self.execute_unsorted(cursors, callback)?;
-        } else {
-            let results = self.execute_sorted(cursors)?;
-            let results_iter = self.add_offset_limit_sorted(results);
-            for object in results_iter {
-                if !callback(object)? {
-                    break;
+        match self.get_sort_strategy() {
+            _ if skip_sorting => self.execute_unsorted(cursors, stats, callback)?,
+            SortStrategy::None | SortStrategy::WhereClause => {
+                self.execute_unsorted(cursors, stats, callback)?
+            }
+            SortStrategy::TopK => {
+                for object in self.execute_top_k(cursors, stats)? {
+                    if !callback(object)? {
+                        break;
+                    }
                 }
             }
+            SortStrategy::External => self.execute_sorted(cursors, stats, callback)?,
+        }
+        Ok(())
+    }
+
+    /// Binds `value` to every parameter `name` of the filter and the where clauses. The query can
+    /// be executed again without being rebuilt. Nothing is bound if `value` does not match one of
+    /// the parameters.
+    pub fn bind(&mut self, name: &str, value: &PropertyValue) -> Result<()> {
+        let mut found = false;
+        for wc in &self.where_clauses {
+            found |= wc.check_param(name, value)?;
+        }
+        if let Some(filter) = &self.filter {
+            found |= filter.check_param(name, value)?;
+        }
+        if !found {
+            return illegal_arg("Unknown parameter.");
+        }
+        for wc in &mut self.where_clauses {
+            wc.bind_param(name, value)?;
+        }
+        if let Some(filter) = &mut self.filter {
+            filter.bind_param(name, value)?;
         }
         Ok(())
     }
@@ -246,6 +593,12 @@ impl<'txn> Query {
         txn.read(|cursors| self.find_all_internal(cursors, false, |object| Ok(callback(object))))
     }
 
+    /// Returns a lazy iterator over the results. The transaction is borrowed until the iterator
+    /// is dropped.
+    pub fn iter<'a>(&'a self, txn: &'a mut IsarTxn<'txn>) -> QueryIter<'a, 'txn> {
+        QueryIter::new(self, txn)
+    }
+
     pub fn find_all_vec(&self, txn: &mut IsarTxn<'txn>) -> Result<Vec<IsarObject<'txn>>> {
         let mut results = vec![];
         self.find_while(txn, |object| {
@@ -255,15 +608,170 @@ impl<'txn> Query {
         Ok(results)
     }
 
-    pub fn count(&self, txn: &mut IsarTxn) -> Result<u32> {
+    /// Calls `callback` with the id and the values of `properties` of every result. Queries
+    /// without filter and sort are answered from the index keys if the where clauses cover all
+    /// properties.
+    pub fn find_values_while<F>(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        properties: &[Property],
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(i64, Vec<PropertyValue>) -> bool,
+    {
+        txn.read(|cursors| {
+            if self.is_covered(properties) {
+                self.execute_covered(cursors, properties, |id, values| Ok(callback(id, values)))
+            } else {
+                self.find_all_internal(cursors, false, |object| {
+                    let id = object.read_long(self.oid_property);
+                    let values = properties
+                        .iter()
+                        .map(|p| PropertyValue::read(object, *p))
+                        .collect();
+                    Ok(callback(id, values))
+                })
+            }
+        })
+    }
+
+    pub fn find_values(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        properties: &[Property],
+    ) -> Result<Vec<Vec<PropertyValue>>> {
+        let mut results = vec![];
+        self.find_values_while(txn, properties, |_, values| {
+            results.push(values);
+            true
+        })?;
+        Ok(results)
+    }
+
+    pub fn count(&self, txn: &mut IsarTxn<'txn>) -> Result<u32> {
         let mut counter = 0;
-        self.find_while(txn, |_| {
+        self.find_values_while(txn, &[], |_, _| {
             counter += 1;
             true
         })?;
         Ok(counter)
     }
 
+    /// Assigns `values` to all results and returns the number of updated objects. Indexes and
+    /// unique constraints are handled like by [IsarCollection::put].
+    pub fn update(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        collection: &IsarCollection,
+        values: &[(Property, PropertyValue)],
+    ) -> Result<usize> {
+        if collection.get_id() != self.col_id {
+            return illegal_arg("The query does not belong to this collection.");
+        }
+        for (property, value) in values {
+            if *property == self.oid_property {
+                return illegal_arg("The id property cannot be updated.");
+            }
+            let exists = collection
+                .get_properties()
+                .iter()
+                .any(|(_, p)| p == property);
+            if !exists || !value.is_type(property.data_type) {
+                return illegal_arg("Invalid property value.");
+            }
+        }
+
+        let mut oids = vec![];
+        self.find_while(txn, |object| {
+            oids.push(object.read_long(self.oid_property));
+            true
+        })?;
+        collection.update_internal(txn, &oids, values)
+    }
+
+    /// Returns a copy of this query which returns at most `limit` results.
+    pub fn with_limit(&self, limit: usize) -> Query {
+        let mut query = self.clone();
+        query.limit = query.limit.min(limit);
+        query
+    }
+
+    /// Deletes all results including their index and link entries and returns the number of
+    /// deleted objects. A single where clause without filter, distinct and offset which yields
+    /// the results in the requested order is deleted by repeatedly removing the first object of
+    /// its range.
+    pub fn delete(&self, txn: &mut IsarTxn<'txn>, collection: &IsarCollection) -> Result<usize> {
+        if collection.get_id() != self.col_id {
+            return illegal_arg("The query does not belong to this collection.");
+        }
+        let range_only = self.filter.is_none()
+            && self.distinct.is_empty()
+            && self.offset == 0
+            && (self.sort.is_empty() || self.where_clause_sorted);
+        match self.where_clauses.as_slice() {
+            [WhereClause::Id(wc)] if range_only => txn.write(|cursors, mut change_set| {
+                let mut count = 0;
+                while count < self.limit {
+                    let id = if let Some(id) = wc.first_id(&mut cursors.data)? {
+                        id
+                    } else {
+                        break;
+                    };
+                    collection.delete_internal(cursors, true, change_set.as_deref_mut(), id)?;
+                    count += 1;
+                }
+                Ok(count)
+            }),
+            [WhereClause::Index(wc)] if range_only && !wc.skips_duplicates() => {
+                txn.write(|cursors, mut change_set| {
+                    let mut count = 0;
+                    while count < self.limit {
+                        let id = if let Some(id) = wc.first_id(&mut cursors.index)? {
+                            id
+                        } else {
+                            break;
+                        };
+                        let deleted = collection.delete_internal(
+                            cursors,
+                            true,
+                            change_set.as_deref_mut(),
+                            id,
+                        )?;
+                        if !deleted {
+                            return Err(IsarError::DbCorrupted {
+                                message: "Could not find object specified in index.".to_string(),
+                            });
+                        }
+                        count += 1;
+                    }
+                    Ok(count)
+                })
+            }
+            _ => {
+                let mut oids = vec![];
+                self.find_while(txn, |object| {
+                    oids.push(object.read_long(self.oid_property));
+                    true
+                })?;
+                txn.write(|cursors, mut change_set| {
+                    let mut count = 0;
+                    for oid in oids {
+                        if collection.delete_internal(
+                            cursors,
+                            true,
+                            change_set.as_deref_mut(),
+                            oid,
+                        )? {
+                            count += 1;
+                        }
+                    }
+                    Ok(count)
+                })
+            }
+        }
+    }
+
     pub fn export_json(
         &self,
         txn: &mut IsarTxn,
@@ -286,8 +794,11 @@ mod tests {
     use std::sync::Arc;
 
     use crate::instance::IsarInstance;
-    use crate::object::data_type::DataType;
-    use crate::query::filter::{IntBetweenCond, NotCond, OrCond};
+    use crate::query::filter::{
+        Comparison, IntBetweenCond, IntListBetweenCond, IntListContainsValuesCond, ListLengthCond,
+        NotCond, OrCond, ParamCond, ParamOp, PropertyCompareCond, PropertyOffsetBetweenCond,
+        StringListBetweenCond, StringListContainsValuesCond, StringListRegexCond, StringRegexCond,
+    };
     use crate::{col, ind, isar, set};
 
     use super::*;
@@ -406,6 +917,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_list_where_clause() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::IntList; ind!(field)));
+        let mut txn = isar.begin_txn(true, false)?;
+        for (oid, list) in vec![(1, vec![1, 2]), (2, vec![2, 3, 4]), (3, vec![5])] {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int_list(Some(&list));
+            col.put(&mut txn, o.finish())?;
+        }
+
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_int(2);
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_int(4);
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Ascending)?;
+
+        let oid_property = col.get_oid_property();
+        let oids: Vec<i64> = qb
+            .build()
+            .find_all_vec(&mut txn)?
+            .iter()
+            .map(|o| o.read_long(oid_property))
+            .collect();
+        assert_eq!(oids, vec![1, 2]);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
     #[test]
     fn test_multiple_where_clauses() -> Result<()> {
         let isar = fill_int_col(vec![1, 2, 2, 3, 3, 3, 4], false);
@@ -521,4 +1064,536 @@ mod tests {
         isar.close();
         Ok(())
     }
+
+    #[test]
+    fn test_find_values() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::Int, name => DataType::String; ind!(str field, IndexType::Value, None, name, IndexType::Value, Some(true))));
+        let mut txn = isar.begin_txn(true, false)?;
+        for (oid, field, name) in vec![(1, 2, "b"), (2, 1, "a"), (3, 2, "b"), (4, 3, "c")] {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int(field);
+            o.write_string(Some(name));
+            col.put(&mut txn, o.finish())?;
+        }
+
+        let properties = col.get_properties();
+        let field = properties.get(1).unwrap().1;
+        let name = properties.get(2).unwrap().1;
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_int(2);
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_int(3);
+        upper.add_max_suffix();
+
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(
+            lower.clone(),
+            true,
+            upper.clone(),
+            true,
+            false,
+            Sort::Ascending,
+        )?;
+        let query = qb.build();
+        assert!(query.is_covered(&[col.get_oid_property(), field, name]));
+        assert_eq!(
+            query.find_values(&mut txn, &[col.get_oid_property(), name])?,
+            vec![
+                vec![
+                    PropertyValue::Long(1),
+                    PropertyValue::String("b".to_string())
+                ],
+                vec![
+                    PropertyValue::Long(3),
+                    PropertyValue::String("b".to_string())
+                ],
+                vec![
+                    PropertyValue::Long(4),
+                    PropertyValue::String("c".to_string())
+                ],
+            ]
+        );
+        assert_eq!(query.count(&mut txn)?, 3);
+
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Ascending)?;
+        qb.add_distinct(field, true);
+        let query = qb.build();
+        assert!(query.is_covered(&[]));
+        assert_eq!(query.count(&mut txn)?, 2);
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(field, 1, 2)?);
+        qb.add_sort(field, Sort::Ascending);
+        let query = qb.build();
+        assert!(!query.is_covered(&[field]));
+        assert_eq!(
+            query.find_values(&mut txn, &[field])?,
+            vec![
+                vec![PropertyValue::Int(1)],
+                vec![PropertyValue::Int(2)],
+                vec![PropertyValue::Int(2)],
+            ]
+        );
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_where_clause() -> Result<()> {
+        let isar = fill_int_col(vec![5, 4, 4, 3, 2, 2, 1], false);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(false, false)?;
+
+        let int_property = col.get_properties().get(1).unwrap().1;
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_max_suffix();
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(
+            col.new_index_key(0).unwrap(),
+            true,
+            upper,
+            true,
+            false,
+            Sort::Ascending,
+        )?;
+        qb.add_sort(int_property, Sort::Descending);
+        qb.set_limit(3);
+        let query = qb.build();
+        assert!(query.where_clause_sorted);
+        assert_eq!(find(&mut txn, query), vec![(1, 5), (3, 4), (2, 4)]);
+
+        let mut qb = col.new_query_builder();
+        qb.add_sort(col.get_oid_property(), Sort::Descending);
+        qb.add_distinct(int_property, false);
+        let query = qb.build();
+        assert!(query.where_clause_sorted);
+        assert_eq!(
+            find(&mut txn, query),
+            vec![(7, 1), (6, 2), (4, 3), (3, 4), (1, 5)]
+        );
+
+        let mut qb = col.new_query_builder();
+        qb.add_sort(int_property, Sort::Ascending);
+        assert!(!qb.build().where_clause_sorted);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_top_k_and_external() -> Result<()> {
+        let isar = fill_int_col(vec![5, 4, 4, 3, 2, 2, 1, 6, 0], false);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(false, false)?;
+
+        let int_property = col.get_properties().get(1).unwrap().1;
+        let mut values = |offset: usize, limit: usize, budget: usize| {
+            let mut qb = col.new_query_builder();
+            qb.add_sort(int_property, Sort::Descending);
+            qb.set_offset(offset);
+            qb.set_limit(limit);
+            qb.set_sort_memory_budget(budget);
+            find(&mut txn, qb.build())
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(values(1, 3, DEFAULT_SORT_MEMORY_BUDGET), vec![5, 4, 4]);
+        assert_eq!(values(8, 3, DEFAULT_SORT_MEMORY_BUDGET), vec![0]);
+        let budget = std::mem::size_of::<IsarObject>() * 2;
+        assert_eq!(
+            values(0, usize::MAX, budget),
+            vec![6, 5, 4, 4, 3, 2, 2, 1, 0]
+        );
+        assert_eq!(values(2, usize::MAX, budget), vec![4, 4, 3, 2, 2, 1, 0]);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_update() -> Result<()> {
+        let isar = fill_int_col(vec![1, 2, 3, 2], false);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(true, false)?;
+
+        let int_property = col.get_properties().get(1).unwrap().1;
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(int_property, 2, 2)?);
+        let query = qb.build();
+        let count = query.update(&mut txn, col, &[(int_property, PropertyValue::Int(7))])?;
+        assert_eq!(count, 2);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(1, 1), (2, 7), (3, 3), (4, 7)]);
+
+        let oid_value = (col.get_oid_property(), PropertyValue::Long(5));
+        assert!(query.update(&mut txn, col, &[oid_value]).is_err());
+        let wrong_type = (int_property, PropertyValue::Long(5));
+        assert!(query.update(&mut txn, col, &[wrong_type]).is_err());
+
+        txn.abort();
+        isar.close();
+
+        let isar = fill_int_col(vec![1, 2, 3], true);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(true, false)?;
+        let query = col.new_query_builder().build();
+        let result = query.update(&mut txn, col, &[(int_property, PropertyValue::Int(5))]);
+        assert!(result.is_err());
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete() -> Result<()> {
+        let isar = fill_int_col(vec![1, 2, 2, 3, 4], false);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(true, false)?;
+
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_int(2);
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_int(2);
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Descending)?;
+        assert_eq!(qb.build().delete(&mut txn, col)?, 2);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(1, 1), (4, 3), (5, 4)]);
+
+        let int_property = col.get_properties().get(1).unwrap().1;
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(int_property, 3, 4)?);
+        qb.set_limit(1);
+        assert_eq!(qb.build().delete(&mut txn, col)?, 1);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(1, 1), (5, 4)]);
+
+        let q = col.new_query_builder().build().with_limit(1);
+        assert_eq!(q.delete(&mut txn, col)?, 1);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(5, 4)]);
+
+        assert_eq!(col.new_query_builder().build().delete(&mut txn, col)?, 1);
+        let q = col.new_query_builder().build();
+        assert!(find(&mut txn, q).is_empty());
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_sorted_limit() -> Result<()> {
+        let isar = fill_int_col(vec![4, 3, 2, 1, 5], false);
+        let col = isar.get_collection(0).unwrap();
+        let mut txn = isar.begin_txn(true, false)?;
+
+        let mut qb = col.new_query_builder();
+        qb.add_id_where_clause(1, 10, Sort::Descending)?;
+        assert_eq!(qb.build().with_limit(1).delete(&mut txn, col)?, 1);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(1, 4), (2, 3), (3, 2), (4, 1)]);
+
+        let int_property = col.get_properties().get(1).unwrap().1;
+        let mut qb = col.new_query_builder();
+        qb.add_sort(int_property, Sort::Descending);
+        assert_eq!(qb.build().with_limit(1).delete(&mut txn, col)?, 1);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(2, 3), (3, 2), (4, 1)]);
+
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_int(0);
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_int(10);
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(lower, true, upper, true, false, Sort::Descending)?;
+        assert_eq!(qb.build().with_limit(2).delete(&mut txn, col)?, 2);
+        let q = col.new_query_builder().build();
+        assert_eq!(find(&mut txn, q), vec![(4, 1)]);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_regex_filter() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, name => DataType::String, tags => DataType::StringList));
+        let mut txn = isar.begin_txn(true, false)?;
+        let data = vec![
+            (1, "Apple", vec![Some("red"), None]),
+            (2, "banana", vec![Some("yellow")]),
+            (3, "cherry", vec![]),
+        ];
+        for (oid, name, tags) in data {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_string(Some(name));
+            o.write_string_list(Some(&tags));
+            col.put(&mut txn, o.finish())?;
+        }
+        let name = col.get_properties().get(1).unwrap().1;
+        let tags = col.get_properties().get(2).unwrap().1;
+        let mut oids = |filter: Filter| {
+            let mut qb = col.new_query_builder();
+            qb.set_filter(filter);
+            qb.build()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        assert_eq!(
+            oids(StringRegexCond::filter(name, "^(a|b)", true)?),
+            vec![2]
+        );
+        assert_eq!(
+            oids(StringRegexCond::filter(name, "^(a|b)", false)?),
+            vec![1, 2]
+        );
+        assert_eq!(
+            oids(StringRegexCond::filter(name, "r{2}y$", true)?),
+            vec![3]
+        );
+        assert_eq!(
+            oids(StringListRegexCond::filter(tags, "^[rR]", true)?),
+            vec![1]
+        );
+        assert!(StringRegexCond::filter(name, "(", true).is_err());
+        assert!(StringRegexCond::filter(tags, "a", true).is_err());
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_property_compare_filter() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, a => DataType::Int, b => DataType::Int));
+        let mut txn = isar.begin_txn(true, false)?;
+        let data = vec![
+            (1, 5, 3),
+            (2, 2, 2),
+            (3, IsarObject::NULL_INT, 1),
+            (4, 4, 10),
+        ];
+        for (oid, a, b) in data {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int(a);
+            o.write_int(b);
+            col.put(&mut txn, o.finish())?;
+        }
+        let a = col.get_properties().get(1).unwrap().1;
+        let b = col.get_properties().get(2).unwrap().1;
+        let mut oids = |filter: Filter| {
+            let mut qb = col.new_query_builder();
+            qb.set_filter(filter);
+            qb.build()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        let compare = |comparison| PropertyCompareCond::filter(a, comparison, b).unwrap();
+        assert_eq!(oids(compare(Comparison::Greater)), vec![1]);
+        assert_eq!(oids(compare(Comparison::Equal)), vec![2]);
+        assert_eq!(oids(compare(Comparison::Less)), vec![3, 4]);
+        assert_eq!(
+            oids(PropertyOffsetBetweenCond::filter(a, b, 0.0, 2.0)?),
+            vec![1, 2]
+        );
+        let oid = col.get_oid_property();
+        assert!(PropertyCompareCond::filter(a, Comparison::Equal, oid).is_err());
+
+        // 2^60 + 3 and 2^60 are equal as f64
+        for (oid, a) in vec![((1 << 60) + 1, 1), ((1 << 60) + 3, 0)] {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int(a);
+            o.write_int(0);
+            col.put(&mut txn, o.finish())?;
+        }
+        let bound = (1i64 << 60) as f64;
+        let mut qb = col.new_query_builder();
+        qb.set_filter(PropertyOffsetBetweenCond::filter(oid, a, bound, bound)?);
+        let results = qb.build().find_all_vec(&mut txn)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].read_long(oid), (1 << 60) + 1);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_filters() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, values => DataType::IntList, tags => DataType::StringList));
+        let mut txn = isar.begin_txn(true, false)?;
+        let data = vec![
+            (1, Some(vec![1, 5, 9]), Some(vec![Some("a"), Some("B")])),
+            (2, Some(vec![4, 5]), Some(vec![Some("b"), None])),
+            (3, Some(vec![]), Some(vec![])),
+            (4, None, None),
+        ];
+        for (oid, values, tags) in data {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int_list(values.as_deref());
+            o.write_string_list(tags.as_deref());
+            col.put(&mut txn, o.finish())?;
+        }
+        let values = col.get_properties().get(1).unwrap().1;
+        let tags = col.get_properties().get(2).unwrap().1;
+        let mut oids = |filter: Filter| {
+            let mut qb = col.new_query_builder();
+            qb.set_filter(filter);
+            qb.build()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        assert_eq!(
+            oids(IntListBetweenCond::filter(values, 4, 6, false)?),
+            vec![1, 2]
+        );
+        // the empty list of 3 has no element outside of the range
+        assert_eq!(
+            oids(IntListBetweenCond::filter(values, 4, 6, true)?),
+            vec![2, 3]
+        );
+        assert_eq!(
+            oids(IntListContainsValuesCond::filter(values, &[5, 9], false)?),
+            vec![1, 2]
+        );
+        assert_eq!(
+            oids(IntListContainsValuesCond::filter(values, &[5, 9], true)?),
+            vec![1]
+        );
+        assert_eq!(oids(ListLengthCond::filter(values, 1, 3)?), vec![1, 2]);
+        assert_eq!(oids(ListLengthCond::filter(tags, 0, 0)?), vec![3]);
+        assert_eq!(
+            oids(StringListBetweenCond::filter(
+                tags,
+                Some("a"),
+                Some("b"),
+                false,
+                true
+            )?),
+            vec![1, 3]
+        );
+        assert_eq!(
+            oids(StringListContainsValuesCond::filter(
+                tags,
+                &[Some("b"), None],
+                false,
+                true
+            )?),
+            vec![2]
+        );
+        assert_eq!(
+            oids(StringListContainsValuesCond::filter(
+                tags,
+                &[Some("b"), None],
+                true,
+                false
+            )?),
+            vec![2]
+        );
+        assert!(IntListBetweenCond::filter(tags, 0, 1, false).is_err());
+        assert!(ListLengthCond::filter(col.get_oid_property(), 0, 1).is_err());
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepared_query() -> Result<()> {
+        isar!(isar, col => col!(oid => DataType::Long, a => DataType::Int, s => DataType::String; ind!(a)));
+        let mut txn = isar.begin_txn(true, false)?;
+        let data = vec![
+            (1, 1, "alice"),
+            (2, 2, "Bob"),
+            (3, 3, "bea"),
+            (4, 4, "carl"),
+        ];
+        for (oid, a, s) in data {
+            let mut o = col.new_object_builder(None);
+            o.write_long(oid);
+            o.write_int(a);
+            o.write_string(Some(s));
+            col.put(&mut txn, o.finish())?;
+        }
+        let s = col.get_properties().get(2).unwrap().1;
+        let mut oids = |query: &Query| {
+            query
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        let mut lower = col.new_index_key(0).unwrap();
+        lower.add_param(0, "min")?;
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_param(0, "max")?;
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(lower, true, upper, false, false, Sort::Ascending)?;
+        qb.set_filter(ParamCond::filter("prefix", s, ParamOp::StartsWith, false)?);
+        let mut query = qb.build();
+        assert!(oids(&query).is_empty());
+
+        query.bind("min", &PropertyValue::Int(1))?;
+        query.bind("max", &PropertyValue::Int(4))?;
+        assert!(oids(&query).is_empty());
+        query.bind("prefix", &PropertyValue::String("B".to_string()))?;
+        assert_eq!(oids(&query), vec![2, 3]);
+        query.bind("min", &PropertyValue::Int(3))?;
+        assert_eq!(oids(&query), vec![3]);
+        assert!(query.bind("prefix", &PropertyValue::Int(1)).is_err());
+        assert!(query.bind("unknown", &PropertyValue::Int(1)).is_err());
+        assert_eq!(oids(&query), vec![3]);
+
+        let json = query.to_json(&isar, col)?;
+        let mut restored = Query::from_json(&isar, col, &json)?;
+        assert!(oids(&restored).is_empty());
+        for (name, value) in vec![
+            ("min", PropertyValue::Int(1)),
+            ("max", PropertyValue::Int(3)),
+            ("prefix", PropertyValue::String("a".to_string())),
+        ] {
+            restored.bind(name, &value)?;
+        }
+        assert_eq!(oids(&restored), vec![1]);
+
+        let mut qb = col.new_query_builder();
+        qb.add_param_id_where_clause("id", "id", Sort::Ascending);
+        let mut query = qb.build();
+        query.bind("id", &PropertyValue::Long(2))?;
+        assert_eq!(oids(&query), vec![2]);
+        query.bind("id", &PropertyValue::Long(4))?;
+        assert_eq!(oids(&query), vec![4]);
+
+        txn.abort();
+        isar.close();
+        Ok(())
+    }
 }
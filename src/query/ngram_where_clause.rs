@@ -0,0 +1,253 @@
+use crate::collection::IsarCollection;
+use crate::error::{illegal_arg, IsarError, Result};
+use crate::index::index_key::IndexKey;
+use crate::index::{Index, NGRAM_SIZE};
+use crate::lmdb::cursor::Cursor;
+use crate::lmdb::{ByteKey, IntKey, MAX_ID, MIN_ID};
+use crate::object::isar_object::IsarObject;
+use crate::query::filter::{
+    Condition, Filter, StringContainsCond, StringFuzzyCond, StringMatchesCond,
+};
+use crate::query::id_where_clause::IdWhereClause;
+use crate::query::Sort;
+use crate::schema::collection_schema::IndexType;
+use hashbrown::{HashMap, HashSet};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NGramQuery {
+    Contains(String),
+    /// Pattern with `*` and `?` wildcards, see [StringMatchesCond].
+    Wildcard(String),
+    Fuzzy {
+        value: String,
+        #[serde(rename = "maxDistance")]
+        max_distance: usize,
+    },
+}
+
+/*
+The n-grams of the query only select candidates. Every candidate is verified using the equivalent
+string filter. If the query is too short to contain a usable n-gram, all objects are verified.
+ */
+#[derive(Clone)]
+pub(crate) struct NGramWhereClause {
+    keys: Vec<Vec<u8>>,
+    min_matches: usize,
+    filter: Filter,
+    full_scan: Option<IdWhereClause>,
+    col_id: u16,
+    pub(crate) index_id: u16,
+    pub(crate) query: NGramQuery,
+}
+
+impl NGramWhereClause {
+    pub(crate) fn new(col: &IsarCollection, index: &Index, query: NGramQuery) -> Result<Self> {
+        let ip = index.properties.first().unwrap();
+        if ip.index_type != IndexType::NGram {
+            return illegal_arg("Index is not an n-gram index.");
+        }
+        let case_sensitive = ip.case_sensitive.unwrap();
+        let with_case = |str: &str| {
+            if case_sensitive {
+                str.to_string()
+            } else {
+                str.to_lowercase()
+            }
+        };
+
+        let (grams, min_matches, filter) = match &query {
+            NGramQuery::Contains(value) => {
+                let grams = Index::create_ngrams(&with_case(value));
+                let filter =
+                    StringContainsCond::filter(ip.property, Some(value.as_str()), case_sensitive)?;
+                let min_matches = grams.len();
+                (grams, min_matches, filter)
+            }
+            NGramQuery::Wildcard(pattern) => {
+                let grams = with_case(pattern)
+                    .split(['*', '?'])
+                    .flat_map(Index::create_ngrams)
+                    .unique()
+                    .collect_vec();
+                let filter =
+                    StringMatchesCond::filter(ip.property, Some(pattern.as_str()), case_sensitive)?;
+                let min_matches = grams.len();
+                (grams, min_matches, filter)
+            }
+            NGramQuery::Fuzzy {
+                value,
+                max_distance,
+            } => {
+                // every edit destroys at most NGRAM_SIZE of the n-grams
+                let grams = Index::create_ngrams(&with_case(value));
+                let lost = NGRAM_SIZE.saturating_mul(*max_distance);
+                let min_matches = grams.len().saturating_sub(lost);
+                let filter =
+                    StringFuzzyCond::filter(ip.property, value, *max_distance, case_sensitive)?;
+                (grams, min_matches, filter)
+            }
+        };
+
+        let keys = grams
+            .iter()
+            .map(|gram| {
+                let mut key = IndexKey::new(index);
+                key.bytes.extend_from_slice(gram.as_bytes());
+                key.bytes
+            })
+            .collect_vec();
+        let full_scan = if min_matches == 0 {
+            Some(IdWhereClause::new(col, MIN_ID, MAX_ID, Sort::Ascending))
+        } else {
+            None
+        };
+        Ok(NGramWhereClause {
+            keys,
+            min_matches,
+            filter,
+            full_scan,
+            col_id: col.get_id(),
+            index_id: index.id,
+            query,
+        })
+    }
+
+    pub(crate) fn object_matches(&self, object: IsarObject) -> bool {
+        self.filter.evaluate(object, None).unwrap_or(false)
+    }
+
+    fn get_candidates(&self, index: &mut Cursor) -> Result<Vec<i64>> {
+        let mut counts = HashMap::<i64, usize>::new();
+        for key in &self.keys {
+            index.iter_dups(ByteKey::new(key), |_, _, id| {
+                *counts.entry(IntKey::from_bytes(id).get_id()).or_insert(0) += 1;
+                Ok(true)
+            })?;
+        }
+        let mut candidates = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= self.min_matches)
+            .map(|(id, _)| id)
+            .collect_vec();
+        candidates.sort_unstable();
+        Ok(candidates)
+    }
+
+    /// Returns the sorted ids of all candidates. They still have to be verified.
+    pub(crate) fn get_col_id(&self) -> u16 {
+        self.col_id
+    }
+
+    pub(crate) fn get_candidate_ids(
+        &self,
+        data: &mut Cursor,
+        index: &mut Cursor,
+    ) -> Result<Vec<i64>> {
+        if let Some(full_scan) = &self.full_scan {
+            let mut ids = vec![];
+            full_scan.iter_ids(data, |id| {
+                ids.push(id);
+                Ok(true)
+            })?;
+            Ok(ids)
+        } else {
+            self.get_candidates(index)
+        }
+    }
+
+    pub(crate) fn iter<'txn, F>(
+        &self,
+        data: &mut Cursor<'txn>,
+        index: &mut Cursor<'txn>,
+        mut result_ids: Option<&mut HashSet<i64>>,
+        mut callback: F,
+    ) -> Result<bool>
+    where
+        F: FnMut(&mut Cursor<'txn>, IsarObject<'txn>) -> Result<bool>,
+    {
+        if let Some(full_scan) = &self.full_scan {
+            return full_scan.iter(data, result_ids, |data, _, object| {
+                if self.object_matches(object) {
+                    callback(data, object)
+                } else {
+                    Ok(true)
+                }
+            });
+        }
+
+        for id in self.get_candidates(index)? {
+            if let Some(result_ids) = result_ids.as_deref_mut() {
+                if result_ids.contains(&id) {
+                    continue;
+                }
+            }
+
+            let entry = data.move_to(IntKey::new(self.col_id, id))?;
+            let (_, object) = entry.ok_or(IsarError::DbCorrupted {
+                message: "Could not find object specified in index.".to_string(),
+            })?;
+            let object = IsarObject::from_bytes(object);
+            if !self.object_matches(object) {
+                continue;
+            }
+            if let Some(result_ids) = result_ids.as_deref_mut() {
+                result_ids.insert(id);
+            }
+            if !callback(data, object)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::data_type::DataType;
+    use crate::{col, ind, isar};
+
+    #[test]
+    fn test_ngram_where_clause() {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::String; ind!(str field, IndexType::NGram, Some(false))));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        let values = ["Hello World", "yellow", "hello", "help", "lo"];
+        for (i, value) in values.iter().enumerate() {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(i as i64 + 1);
+            builder.write_string(Some(*value));
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let mut find = |query: NGramQuery| {
+            let mut qb = col.new_query_builder();
+            qb.add_ngram_where_clause(0, query).unwrap();
+            qb.build()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        assert_eq!(
+            find(NGramQuery::Contains("ELLO".to_string())),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            find(NGramQuery::Contains("lo".to_string())),
+            vec![1, 2, 3, 5]
+        );
+        assert_eq!(find(NGramQuery::Wildcard("h*wor?d".to_string())), vec![1]);
+        assert_eq!(
+            find(NGramQuery::Fuzzy {
+                value: "helo".to_string(),
+                max_distance: 1
+            }),
+            vec![3, 4]
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use crate::error::{illegal_arg, IsarError, Result};
+use crate::index::index_key::IndexKey;
+use crate::lmdb::IntKey;
+use crate::object::collation::Collation;
+use crate::object::isar_object::{IsarObject, Property};
+use crate::object::property_value::PropertyValue;
+use crate::query::filter::{Condition, FilterCursors};
+use crate::query::where_clause::WhereClause;
+use crate::query::{Query, Sort, TopKEntry};
+use crate::txn::{Cursors, IsarTxn};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/*
+A page token stores the position of the last result of a page. Queries with a single where clause
+that yields the requested order continue directly after that entry by seeking the data or index
+cursor. All other queries are sorted by the sort properties and the oid and only keep results
+that sort after the values stored in the token.
+
+If the keys of a single index where clause are ordered by the first sort properties, the index is
+sought to the values of these properties in the token and the scan stops once the page is full
+and the values change. Other where clauses are scanned completely for every page.
+ */
+
+/// Opaque continuation token of [Query::find_page].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageToken(Vec<u8>);
+
+impl PageToken {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        PageToken(bytes.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn encode(data: &TokenData) -> Self {
+        PageToken(serde_json::to_vec(data).unwrap())
+    }
+
+    fn decode(&self) -> Result<TokenData> {
+        serde_json::from_slice(&self.0).or_else(|_| illegal_arg("Invalid page token."))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenData {
+    id: i64,
+    key: Option<Vec<u8>>,
+    values: Vec<PropertyValue>,
+}
+
+impl<'txn> Query {
+    /// Returns up to `page_size` results following the position of `token` and a token for the
+    /// next page. The token is `None` once all results have been returned. Offset and limit of
+    /// the query are ignored.
+    ///
+    /// Queries with a single id or non multi-entry index where clause that yields the requested
+    /// order resume directly after the last result. If the index is only ordered by the first sort
+    /// properties, the results with the same values as the last result are scanned again. All
+    /// other queries scan every result of the query for each page.
+    pub fn find_page(
+        &self,
+        txn: &mut IsarTxn<'txn>,
+        token: Option<&PageToken>,
+        page_size: usize,
+    ) -> Result<(Vec<IsarObject<'txn>>, Option<PageToken>)> {
+        if page_size == 0 {
+            return illegal_arg("Page size must be greater than zero.");
+        }
+        if !self.distinct.is_empty() {
+            return illegal_arg("Distinct queries do not support pagination.");
+        }
+        let token = token.map(|t| t.decode()).transpose()?;
+
+        let (results, next) = txn.read(|cursors| {
+            let seekable = match self.where_clauses.as_slice() {
+                [WhereClause::Id(_)] => true,
+                [WhereClause::Index(wc)] => !wc.is_multi_entry(),
+                _ => false,
+            };
+            let prefix = match self.where_clauses.as_slice() {
+                [WhereClause::Index(wc)] if !wc.is_multi_entry() && !wc.skips_duplicates() => {
+                    Self::index_sort_prefix(wc.get_index(), &self.sort)
+                }
+                _ => 0,
+            };
+            if seekable && (self.sort.is_empty() || self.where_clause_sorted) {
+                self.find_page_seek(cursors, token.as_ref(), page_size)
+            } else {
+                self.find_page_sorted(cursors, token.as_ref(), page_size, prefix)
+            }
+        })?;
+        Ok((results, next.map(|data| PageToken::encode(&data))))
+    }
+
+    fn find_page_seek(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        token: Option<&TokenData>,
+        page_size: usize,
+    ) -> Result<(Vec<IsarObject<'txn>>, Option<TokenData>)> {
+        let mut filter_cursors = FilterCursors::new(&mut cursors.data2, &mut cursors.links);
+        let mut results = vec![];
+        match &self.where_clauses[0] {
+            WhereClause::Id(wc) => {
+                let wc = if let Some(token) = token {
+                    wc.resume_after(token.id)
+                } else {
+                    wc.clone()
+                };
+                let mut next = wc.iter_step(&mut cursors.data, true)?;
+                while let Some((id, object)) = next {
+                    if self.page_filter_matches(object, &mut filter_cursors)? {
+                        results.push(object);
+                        if results.len() == page_size {
+                            let token = TokenData {
+                                id,
+                                key: None,
+                                values: vec![],
+                            };
+                            return Ok((results, Some(token)));
+                        }
+                    }
+                    next = wc.iter_step(&mut cursors.data, false)?;
+                }
+            }
+            WhereClause::Index(wc) => {
+                let mut next = if let Some(token) = token {
+                    let key = if let Some(key) = &token.key {
+                        key
+                    } else {
+                        return illegal_arg("Invalid page token.");
+                    };
+                    wc.resume_after(&mut cursors.index, key, token.id)?
+                } else {
+                    wc.iter_entries_step(&mut cursors.index, true)?
+                };
+                let col_id = wc.get_index().get_col_id();
+                while let Some((key, id)) = next {
+                    let (_, object) = cursors.data.move_to(IntKey::new(col_id, id))?.ok_or(
+                        IsarError::DbCorrupted {
+                            message: "Could not find object specified in index.".to_string(),
+                        },
+                    )?;
+                    let object = IsarObject::from_bytes(object);
+                    if self.page_filter_matches(object, &mut filter_cursors)? {
+                        results.push(object);
+                        if results.len() == page_size {
+                            let token = TokenData {
+                                id,
+                                key: Some(key.to_vec()),
+                                values: vec![],
+                            };
+                            return Ok((results, Some(token)));
+                        }
+                    }
+                    next = wc.iter_entries_step(&mut cursors.index, false)?;
+                }
+            }
+            _ => unreachable!(),
+        }
+        Ok((results, None))
+    }
+
+    fn page_filter_matches(
+        &self,
+        object: IsarObject,
+        filter_cursors: &mut FilterCursors,
+    ) -> Result<bool> {
+        if let Some(filter) = &self.filter {
+            filter.evaluate(object, Some(filter_cursors))
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Keeps the first `page_size` results after the token in a bounded heap. The oid is added
+    /// to the sort so every result has a distinct position. The first `prefix` sort properties
+    /// are ordered by the index where clause.
+    fn find_page_sorted(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        token: Option<&TokenData>,
+        page_size: usize,
+        prefix: usize,
+    ) -> Result<(Vec<IsarObject<'txn>>, Option<TokenData>)> {
+        let mut sort = self.sort.clone();
+        if !sort.iter().any(|(p, _, _)| *p == self.oid_property) {
+            sort.push((self.oid_property, Sort::Ascending, None));
+        }
+        if let Some(token) = token {
+            let valid = token.values.len() == sort.len()
+                && sort
+                    .iter()
+                    .zip(&token.values)
+                    .all(|((p, _, _), value)| value.is_type(p.data_type));
+            if !valid {
+                return illegal_arg("Invalid page token.");
+            }
+        }
+
+        let mut heap: BinaryHeap<TopKEntry> = BinaryHeap::new();
+        let mut more = false;
+        let mut add = |object: IsarObject<'txn>| {
+            if let Some(token) = token {
+                let ord = Self::compare_to_values(&sort, object, &token.values);
+                if ord != Ordering::Greater {
+                    return true;
+                }
+            }
+            if prefix > 0 && heap.len() == page_size {
+                // all remaining results sort after the page
+                let last = &heap.peek().unwrap().object;
+                if Self::compare_objects(&sort[..prefix], &object, last) == Ordering::Greater {
+                    more = true;
+                    return false;
+                }
+            }
+            heap.push(TopKEntry {
+                object,
+                sort: &sort,
+            });
+            if heap.len() > page_size {
+                heap.pop();
+                more = true;
+            }
+            true
+        };
+        if prefix > 0 {
+            self.seek_index_prefix(cursors, token, &sort[..prefix], add)?;
+        } else {
+            self.execute_raw(cursors, |object| Ok(add(object)))?;
+        }
+
+        let results: Vec<IsarObject> = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| entry.object)
+            .collect();
+        let next = if more {
+            results.last().map(|object| TokenData {
+                id: object.read_long(self.oid_property),
+                key: None,
+                values: sort
+                    .iter()
+                    .map(|(p, _, _)| PropertyValue::read(*object, *p))
+                    .collect(),
+            })
+        } else {
+            None
+        };
+        Ok((results, next))
+    }
+
+    /// Calls `callback` with the results of the index where clause in the order of the `prefix`
+    /// sort properties, starting at the values of these properties in `token`.
+    fn seek_index_prefix<F>(
+        &self,
+        cursors: &mut Cursors<'txn>,
+        token: Option<&TokenData>,
+        prefix: &[(Property, Sort, Option<Collation>)],
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(IsarObject<'txn>) -> bool,
+    {
+        let mut wc = if let [WhereClause::Index(wc)] = self.where_clauses.as_slice() {
+            wc.clone()
+        } else {
+            unreachable!()
+        };
+        let direction = prefix[0].1;
+        wc.set_sort(direction);
+        if let Some(token) = token {
+            let index = wc.get_index();
+            let mut key = IndexKey::new(index);
+            for (ip, value) in index.properties.iter().zip(&token.values[..prefix.len()]) {
+                key.add_value(ip, value)?;
+            }
+            if direction == Sort::Ascending {
+                let key = key.bytes;
+                if key > wc.lower_key {
+                    wc.lower_key = key;
+                }
+            } else {
+                key.add_max_suffix();
+                let key = key.bytes;
+                if key < wc.upper_key {
+                    wc.upper_key = key;
+                }
+            }
+        }
+
+        let mut filter_cursors = FilterCursors::new(&mut cursors.data2, &mut cursors.links);
+        let col_id = wc.get_index().get_col_id();
+        let mut next = wc.iter_entries_step(&mut cursors.index, true)?;
+        while let Some((_, id)) = next {
+            let (_, object) =
+                cursors
+                    .data
+                    .move_to(IntKey::new(col_id, id))?
+                    .ok_or(IsarError::DbCorrupted {
+                        message: "Could not find object specified in index.".to_string(),
+                    })?;
+            let object = IsarObject::from_bytes(object);
+            if self.page_filter_matches(object, &mut filter_cursors)? && !callback(object) {
+                break;
+            }
+            next = wc.iter_entries_step(&mut cursors.index, false)?;
+        }
+        Ok(())
+    }
+
+    fn compare_to_values(
+        sort: &[(Property, Sort, Option<Collation>)],
+        object: IsarObject,
+        values: &[PropertyValue],
+    ) -> Ordering {
+        for ((p, sort, collation), value) in sort.iter().zip(values) {
+            let ord = PropertyValue::read(object, *p).compare(value, *collation);
+            if ord != Ordering::Equal {
+                return if *sort == Sort::Ascending {
+                    ord
+                } else {
+                    ord.reverse()
+                };
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::data_type::DataType;
+    use crate::query::filter::IntBetweenCond;
+    use crate::{col, ind, isar};
+    use itertools::Itertools;
+
+    fn next_page(
+        query: &Query,
+        txn: &mut IsarTxn,
+        token: Option<&PageToken>,
+        page_size: usize,
+    ) -> (Vec<i64>, Option<PageToken>) {
+        let oid = Property {
+            offset: 2,
+            data_type: DataType::Long,
+        };
+        let (objects, token) = query.find_page(txn, token, page_size).unwrap();
+        let oids = objects.iter().map(|o| o.read_long(oid)).collect_vec();
+        (oids, token)
+    }
+
+    #[test]
+    fn test_find_page() {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::Int; ind!(field)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..10 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int((oid % 3) as i32);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let field = col.get_properties().get(1).unwrap().1;
+
+        let mut qb = col.new_query_builder();
+        qb.add_id_where_clause(0, 100, Sort::Ascending).unwrap();
+        qb.set_filter(IntBetweenCond::filter(field, 1, 2).unwrap());
+        let query = qb.build();
+        let (oids, token) = next_page(&query, &mut txn, None, 3);
+        assert_eq!(oids, vec![1, 2, 4]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 3);
+        assert_eq!(oids, vec![5, 7, 8]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 3);
+        assert!(oids.is_empty() && token.is_none());
+
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_max_suffix();
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(
+            col.new_index_key(0).unwrap(),
+            true,
+            upper,
+            true,
+            false,
+            Sort::Ascending,
+        )
+        .unwrap();
+        qb.add_sort(field, Sort::Descending);
+        let query = qb.build();
+        let (oids, token) = next_page(&query, &mut txn, None, 4);
+        assert_eq!(oids, vec![8, 5, 2, 7]);
+        // the page continues after the deleted entry
+        col.delete(&mut txn, 7).unwrap();
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![4, 1, 9, 6]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![3, 0]);
+        assert!(token.is_none());
+
+        let mut upper = col.new_index_key(0).unwrap();
+        upper.add_max_suffix();
+        let mut qb = col.new_query_builder();
+        qb.add_index_where_clause(
+            col.new_index_key(0).unwrap(),
+            true,
+            upper,
+            true,
+            false,
+            Sort::Ascending,
+        )
+        .unwrap();
+        qb.add_sort(field, Sort::Descending);
+        qb.add_sort(col.get_oid_property(), Sort::Descending);
+        let query = qb.build();
+        assert!(!query.where_clause_sorted);
+        let (oids, token) = next_page(&query, &mut txn, None, 4);
+        assert_eq!(oids, vec![8, 5, 2, 4]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![1, 9, 6, 3]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![0]);
+        assert!(token.is_none());
+
+        let mut qb = col.new_query_builder();
+        qb.add_sort(field, Sort::Ascending);
+        let query = qb.build();
+        let (oids, token) = next_page(&query, &mut txn, None, 4);
+        assert_eq!(oids, vec![0, 3, 6, 9]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![1, 4, 2, 5]);
+        let (oids, token) = next_page(&query, &mut txn, token.as_ref(), 4);
+        assert_eq!(oids, vec![8]);
+        assert!(token.is_none());
+
+        let invalid = PageToken::from_bytes(b"invalid");
+        assert!(query.find_page(&mut txn, Some(&invalid), 4).is_err());
+        let mismatched = PageToken::from_bytes(br#"{"id":0,"values":[{"String":"a"},{"Long":1}]}"#);
+        assert!(query.find_page(&mut txn, Some(&mismatched), 4).is_err());
+
+        txn.abort();
+        isar.close();
+    }
+}
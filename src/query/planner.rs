@@ -0,0 +1,703 @@
+use crate::collection::IsarCollection;
+use crate::error::Result;
+use crate::index::index_key::IndexKey;
+use crate::index::index_stats::{read_object_count, IndexStats};
+use crate::index::{Index, IndexProperty, MAX_STRING_INDEX_SIZE};
+use crate::lmdb::{ByteKey, MAX_ID, MIN_ID};
+use crate::object::data_type::DataType;
+use crate::object::isar_object::IsarObject;
+use crate::query::filter::{
+    AndCond, ByteBetweenCond, DoubleBetweenCond, Filter, FloatBetweenCond, IntBetweenCond,
+    LongBetweenCond, OrCond, StringBetweenCond,
+};
+use crate::query::id_where_clause::IdWhereClause;
+use crate::query::index_where_clause::IndexWhereClause;
+use crate::query::intersection_where_clause::IntersectionWhereClause;
+use crate::query::where_clause::WhereClause;
+use crate::query::Sort;
+use crate::schema::collection_schema::IndexType;
+use crate::txn::Cursors;
+use itertools::Itertools;
+
+/// Cost of reading an object using an index relative to reading it during a full scan.
+const INDEX_LOOKUP_COST: f64 = 2.0;
+
+/// Cost of reading an id from an index relative to reading an object during a full scan.
+const ID_SCAN_COST: f64 = 0.1;
+
+/// Estimated number of results of a range if there are no statistics.
+const UNKNOWN_RANGE_COST: f64 = 100.0;
+
+struct Plan {
+    where_clauses: Vec<WhereClause>,
+    cost: f64,
+    /// Conditions which are answered exactly by the where clauses.
+    consumed: Vec<usize>,
+}
+
+impl Plan {
+    fn empty(consumed: Vec<usize>) -> Self {
+        Plan {
+            where_clauses: vec![],
+            cost: 0.0,
+            consumed,
+        }
+    }
+}
+
+/// Key bytes of a single index property between which all objects matching a condition are.
+struct KeyRange {
+    lower: Vec<u8>,
+    upper: Vec<u8>,
+    /// The upper key only is a prefix of the keys in the range.
+    prefix: bool,
+    /// Every key in the range matches the condition.
+    exact: bool,
+}
+
+impl KeyRange {
+    fn is_equal(&self) -> bool {
+        !self.prefix && self.lower == self.upper
+    }
+}
+
+/*
+The planner chooses where clauses which return a superset of the objects matching a filter.
+
+Conditions of an AND are alternatives, the cheapest one is used. Equality conditions on the
+leading properties of a composite index are combined into a single key range. Every condition of
+an OR needs to be answered by a where clause, otherwise the full collection has to be scanned.
+
+Top-level conditions which are answered exactly by the chosen where clause are removed from the
+filter. Without statistics every index is assumed to be cheaper than a full scan.
+ */
+pub(crate) struct Planner<'a> {
+    collection: &'a IsarCollection,
+    object_count: u64,
+    index_stats: Option<Vec<IndexStats>>,
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(collection: &'a IsarCollection, cursors: &mut Cursors) -> Result<Self> {
+        let object_count = read_object_count(&mut cursors.info, collection.get_id())?;
+        let index_stats = collection
+            .get_indexes()
+            .iter()
+            .map(|index| index.get_stats(cursors))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Planner {
+            collection,
+            object_count,
+            index_stats: Some(index_stats),
+        })
+    }
+
+    pub fn without_stats(collection: &'a IsarCollection) -> Self {
+        Planner {
+            collection,
+            object_count: 0,
+            index_stats: None,
+        }
+    }
+
+    /// Returns the where clauses and the remaining filter.
+    pub fn plan(&self, filter: &Filter) -> (Vec<WhereClause>, Option<Filter>) {
+        let full_scan_cost = if self.index_stats.is_some() {
+            self.object_count as f64
+        } else {
+            f64::INFINITY
+        };
+        match self.plan_filter(filter, filter) {
+            Some(plan) if plan.cost < full_scan_cost => {
+                let residual = Self::remove_conditions(filter, &plan.consumed);
+                (plan.where_clauses, residual)
+            }
+            _ => {
+                let wc = IdWhereClause::new(self.collection, MIN_ID, MAX_ID, Sort::Ascending);
+                (vec![WhereClause::Id(wc)], Some(filter.clone()))
+            }
+        }
+    }
+
+    fn remove_conditions(filter: &Filter, consumed: &[usize]) -> Option<Filter> {
+        if consumed.is_empty() {
+            return Some(filter.clone());
+        }
+        if let Filter::And(and) = filter {
+            let mut remaining = and
+                .filters
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !consumed.contains(i))
+                .map(|(_, f)| f.clone())
+                .collect::<Vec<_>>();
+            match remaining.len() {
+                0 => None,
+                1 => remaining.pop(),
+                _ => Some(AndCond::filter(remaining)),
+            }
+        } else {
+            None
+        }
+    }
+
+    fn plan_filter(&self, filter: &Filter, query_filter: &Filter) -> Option<Plan> {
+        if let Some(or) = Self::expand_in(filter) {
+            return self.plan_filter(&or, query_filter);
+        }
+        let nested_plan = |filter: &Filter| {
+            self.plan_filter(filter, query_filter).map(|mut plan| {
+                plan.consumed.clear();
+                plan
+            })
+        };
+        match filter {
+            Filter::And(and) => {
+                let conditions = and.filters.iter().collect::<Vec<_>>();
+                let nested = and.filters.iter().filter_map(|f| match f {
+                    Filter::And(_) | Filter::Or(_) => nested_plan(f),
+                    _ if Self::expand_in(f).is_some() => nested_plan(f),
+                    _ => None,
+                });
+                let plans = self.plan_conditions(&conditions, query_filter);
+                let intersection = self.plan_intersection(&plans);
+                plans
+                    .into_iter()
+                    .chain(intersection)
+                    .chain(nested)
+                    .min_by(|p1, p2| p1.cost.partial_cmp(&p2.cost).unwrap())
+            }
+            Filter::Or(or) => {
+                let mut where_clauses = vec![];
+                let mut cost = 0.0;
+                for filter in &or.filters {
+                    let plan = nested_plan(filter)?;
+                    where_clauses.extend(plan.where_clauses);
+                    cost += plan.cost;
+                }
+                Some(Plan {
+                    where_clauses,
+                    cost,
+                    consumed: vec![],
+                })
+            }
+            _ => self
+                .plan_conditions(&[filter], query_filter)
+                .into_iter()
+                .min_by(|p1, p2| p1.cost.partial_cmp(&p2.cost).unwrap()),
+        }
+    }
+
+    /// Returns an OR of point conditions which can be planned like other conditions for an IN
+    /// condition. The IN condition itself stays in the filter.
+    fn expand_in(filter: &Filter) -> Option<Filter> {
+        fn sort_floats<T: PartialOrd>(values: &mut [T], is_nan: impl Fn(&T) -> bool) {
+            values.sort_unstable_by(|v1, v2| {
+                v1.partial_cmp(v2)
+                    .unwrap_or_else(|| is_nan(v2).cmp(&is_nan(v1)))
+            });
+        }
+
+        let points = match filter {
+            Filter::ByteIn(c) => c
+                .values
+                .iter()
+                .sorted()
+                .map(|v| ByteBetweenCond::filter(c.property, *v, *v))
+                .collect::<Vec<_>>(),
+            Filter::IntIn(c) => c
+                .values
+                .iter()
+                .sorted()
+                .map(|v| IntBetweenCond::filter(c.property, *v, *v))
+                .collect(),
+            Filter::LongIn(c) => c
+                .values
+                .iter()
+                .sorted()
+                .map(|v| LongBetweenCond::filter(c.property, *v, *v))
+                .collect(),
+            Filter::FloatIn(c) => {
+                let mut values = c.get_values();
+                sort_floats(&mut values, |v| v.is_nan());
+                values
+                    .into_iter()
+                    .map(|v| FloatBetweenCond::filter(c.property, v, v))
+                    .collect()
+            }
+            Filter::DoubleIn(c) => {
+                let mut values = c.get_values();
+                sort_floats(&mut values, |v| v.is_nan());
+                values
+                    .into_iter()
+                    .map(|v| DoubleBetweenCond::filter(c.property, v, v))
+                    .collect()
+            }
+            Filter::StringIn(c) => {
+                let null = if c.null { Some(None) } else { None };
+                null.into_iter()
+                    .chain(c.values.iter().sorted().map(Some))
+                    .map(|v| {
+                        let v = v.map(|v| v.as_str());
+                        StringBetweenCond::filter(c.property, v, v, c.case_sensitive)
+                    })
+                    .collect()
+            }
+            _ => return None,
+        };
+        let points = points.into_iter().collect::<Result<Vec<_>>>().ok()?;
+        Some(OrCond::filter(points))
+    }
+
+    fn plan_conditions(&self, conditions: &[&Filter], query_filter: &Filter) -> Vec<Plan> {
+        let mut plans = conditions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, condition)| self.plan_id_condition(i, condition))
+            .collect::<Vec<_>>();
+        for (i, index) in self.collection.get_indexes().iter().enumerate() {
+            if let Some(index_filter) = index.get_filter() {
+                if !query_filter.implies(index_filter) {
+                    continue;
+                }
+            }
+            if let Some(plan) = self.plan_index(i, index, conditions) {
+                plans.push(plan);
+            }
+        }
+        plans
+    }
+
+    /// Intersects the ids of the two plans which produce the cheapest intersection. This requires
+    /// statistics since the benefit depends on the selectivity of both plans.
+    fn plan_intersection(&self, plans: &[Plan]) -> Option<Plan> {
+        self.index_stats.as_ref()?;
+        let object_count = self.object_count.max(1) as f64;
+        let mut best: Option<(usize, usize, f64)> = None;
+        for (i, p1) in plans.iter().enumerate() {
+            for (j, p2) in plans.iter().enumerate().skip(i + 1) {
+                if p1.where_clauses.is_empty()
+                    || p2.where_clauses.is_empty()
+                    || p1.consumed.iter().any(|c| p2.consumed.contains(c))
+                {
+                    continue;
+                }
+                let results1 = p1.cost / INDEX_LOOKUP_COST;
+                let results2 = p2.cost / INDEX_LOOKUP_COST;
+                let cost = (results1 + results2) * ID_SCAN_COST
+                    + results1 * results2 / object_count * INDEX_LOOKUP_COST;
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((i, j, cost));
+                }
+            }
+        }
+
+        let (i, j, cost) = best?;
+        let (p1, p2) = (&plans[i], &plans[j]);
+        let groups = vec![p1.where_clauses.clone(), p2.where_clauses.clone()];
+        let wc = IntersectionWhereClause::new(self.collection, groups);
+        let mut consumed = p1.consumed.clone();
+        consumed.extend_from_slice(&p2.consumed);
+        Some(Plan {
+            where_clauses: vec![WhereClause::Intersection(wc)],
+            cost,
+            consumed,
+        })
+    }
+
+    fn plan_id_condition(&self, condition_index: usize, condition: &Filter) -> Option<Plan> {
+        if let Filter::LongBetween(cond) = condition {
+            if cond.property == self.collection.get_oid_property() {
+                let lower = cond.lower.max(MIN_ID);
+                let upper = cond.upper.min(MAX_ID);
+                if lower > upper {
+                    return Some(Plan::empty(vec![condition_index]));
+                }
+                let span = (upper as i128 - lower as i128 + 1) as f64;
+                let max_cost = if self.index_stats.is_some() {
+                    self.object_count as f64
+                } else {
+                    UNKNOWN_RANGE_COST
+                };
+                let wc = IdWhereClause::new(self.collection, lower, upper, Sort::Ascending);
+                return Some(Plan {
+                    where_clauses: vec![WhereClause::Id(wc)],
+                    cost: span.min(max_cost),
+                    consumed: vec![condition_index],
+                });
+            }
+        }
+        None
+    }
+
+    fn plan_index(
+        &self,
+        index_index: usize,
+        index: &Index,
+        conditions: &[&Filter],
+    ) -> Option<Plan> {
+        if index.is_full_text() || index.multiple() {
+            return None;
+        }
+
+        let mut lower = IndexKey::new(index);
+        let mut upper = IndexKey::new(index);
+        let mut consumed = vec![];
+        let mut exact = true;
+        let mut equal_properties = 0;
+        let mut prefix = false;
+        for ip in &index.properties {
+            let ranges = conditions
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !consumed.contains(i))
+                .filter_map(|(i, condition)| {
+                    Self::get_key_range(index, ip, condition).map(|range| (i, range))
+                })
+                .collect::<Vec<_>>();
+            let best = ranges
+                .iter()
+                .position(|(_, range)| range.is_equal())
+                .unwrap_or(0);
+            if let Some((i, range)) = ranges.into_iter().nth(best) {
+                lower.bytes.extend_from_slice(&range.lower);
+                upper.bytes.extend_from_slice(&range.upper);
+                consumed.push(i);
+                exact &= range.exact;
+                if !range.is_equal() {
+                    prefix = range.prefix;
+                    break;
+                }
+                equal_properties += 1;
+            } else {
+                break;
+            }
+        }
+        if consumed.is_empty() {
+            return None;
+        }
+
+        let full_key = equal_properties == index.properties.len();
+        if prefix || (consumed.len() < index.properties.len()) {
+            upper.add_max_suffix();
+        }
+        // Removing conditions might break the implication of the filter of a partial index.
+        if !exact || index.get_filter().is_some() {
+            consumed.clear();
+        }
+        if ByteKey::new(&lower.bytes) > ByteKey::new(&upper.bytes) {
+            return Some(Plan::empty(consumed));
+        }
+
+        let cost = if let Some(stats) = &self.index_stats {
+            let stats = stats.get(index_index).unwrap();
+            if full_key {
+                stats.estimate_equal()
+            } else {
+                stats.estimate_between(&lower.bytes, &upper.bytes)
+            }
+        } else if full_key {
+            if index.unique {
+                1.0
+            } else {
+                10.0
+            }
+        } else {
+            UNKNOWN_RANGE_COST - equal_properties as f64
+        };
+        let wc = IndexWhereClause::new(lower, upper, false, Sort::Ascending).ok()?;
+        Some(Plan {
+            where_clauses: vec![WhereClause::Index(wc)],
+            cost: cost * INDEX_LOOKUP_COST,
+            consumed,
+        })
+    }
+
+    /// Returns the key range of the index property `ip` containing all objects matching
+    /// `condition`.
+    fn get_key_range(index: &Index, ip: &IndexProperty, condition: &Filter) -> Option<KeyRange> {
+        let mut lower = IndexKey {
+            index,
+            bytes: vec![],
+            params: vec![],
+        };
+        let mut upper = lower.clone();
+        let value_index = ip.index_type == IndexType::Value;
+        let mut prefix = false;
+        let mut exact = true;
+        match condition {
+            Filter::ByteBetween(c) if c.property == ip.property && value_index => {
+                lower.add_byte(c.lower);
+                upper.add_byte(c.upper);
+            }
+            Filter::IntBetween(c) if c.property == ip.property && value_index => {
+                lower.add_int(c.lower);
+                upper.add_int(c.upper);
+            }
+            Filter::LongBetween(c) if c.property == ip.property && value_index => {
+                lower.add_long(c.lower);
+                upper.add_long(c.upper);
+            }
+            Filter::FloatBetween(c) if c.property == ip.property && value_index => {
+                // -0.0 and 0.0 are equal but have different keys
+                lower.add_float(if c.lower == 0.0 { -0.0 } else { c.lower });
+                upper.add_float(if c.upper == 0.0 { 0.0 } else { c.upper });
+            }
+            Filter::DoubleBetween(c) if c.property == ip.property && value_index => {
+                lower.add_double(if c.lower == 0.0 { -0.0 } else { c.lower });
+                upper.add_double(if c.upper == 0.0 { 0.0 } else { c.upper });
+            }
+            Filter::StringBetween(c)
+                if c.property == ip.property && ip.case_sensitive == Some(c.case_sensitive) =>
+            {
+                let (lower_str, upper_str) = (c.lower.as_deref()?, c.upper.as_deref()?);
+                if value_index && c.collation == ip.collation {
+                    lower.add_string_value(Some(lower_str), c.case_sensitive);
+                    upper.add_string_value(Some(upper_str), c.case_sensitive);
+                } else if ip.index_type == IndexType::Hash
+                    && c.collation.is_none()
+                    && lower_str == upper_str
+                {
+                    // hashes may collide
+                    lower.add_string_hash(Some(lower_str), c.case_sensitive);
+                    upper.add_string_hash(Some(upper_str), c.case_sensitive);
+                    exact = false;
+                } else {
+                    return None;
+                }
+            }
+            Filter::IsNull(c) if c.property == ip.property => {
+                if !lower.add_null_for(ip) {
+                    return None;
+                }
+                upper.bytes = lower.bytes.clone();
+            }
+            Filter::IsNotNull(c) if c.property == ip.property => match ip.property.data_type {
+                DataType::Byte => {
+                    lower.add_byte(IsarObject::NULL_BYTE + 1);
+                    upper.add_byte(u8::MAX);
+                }
+                DataType::Int => {
+                    lower.add_int(IsarObject::NULL_INT + 1);
+                    upper.add_int(i32::MAX);
+                }
+                DataType::Long => {
+                    lower.add_long(IsarObject::NULL_LONG + 1);
+                    upper.add_long(i64::MAX);
+                }
+                DataType::Float => {
+                    lower.add_float(f32::NEG_INFINITY);
+                    upper.add_float(f32::INFINITY);
+                }
+                DataType::Double => {
+                    lower.add_double(f64::NEG_INFINITY);
+                    upper.add_double(f64::INFINITY);
+                }
+                // every non-null string key starts with 1
+                DataType::String if value_index => {
+                    lower.bytes.push(1);
+                    upper.bytes.push(1);
+                    prefix = true;
+                }
+                _ => return None,
+            },
+            Filter::StringStartsWith(c)
+                if c.property == ip.property
+                    && value_index
+                    && ip.collation.is_none()
+                    && ip.case_sensitive == Some(c.case_sensitive) =>
+            {
+                let value = c.value.as_deref()?;
+                lower.add_string_value(Some(value), c.case_sensitive);
+                if lower.bytes.len() > MAX_STRING_INDEX_SIZE + 1 {
+                    return None;
+                }
+                // remove the terminator to get a prefix
+                lower.bytes.pop();
+                upper.bytes = lower.bytes.clone();
+                prefix = true;
+            }
+            _ => return None,
+        }
+        Some(KeyRange {
+            lower: lower.bytes,
+            upper: upper.bytes,
+            prefix,
+            exact,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::filter::{
+        AndCond, IntBetweenCond, IntInCond, IsNotNullCond, IsNullCond, LongBetweenCond, NotCond,
+        OrCond, StaticCond, StringInCond, StringStartsWithCond,
+    };
+    use crate::{col, ind, isar};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_planner() {
+        isar!(isar, col => col!(oid => DataType::Long, field1 => DataType::Int, field2 => DataType::Int; ind!(field1), ind!(field2)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..100 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int(oid as i32);
+            builder.write_int((oid % 2) as i32);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let stats = col.get_index_stats(&mut txn, 1).unwrap();
+        assert_eq!((stats.entries, stats.distinct_keys), (100, 2));
+        assert!(stats.histogram.is_none());
+        col.update_stats(&mut txn).unwrap();
+        assert!(col
+            .get_index_stats(&mut txn, 0)
+            .unwrap()
+            .histogram
+            .is_some());
+
+        let properties = col.get_properties();
+        let oid = properties.get(0).unwrap().1;
+        let field1 = properties.get(1).unwrap().1;
+        let field2 = properties.get(2).unwrap().1;
+
+        let mut plan = |filter: Filter| {
+            let (where_clauses, _) = txn
+                .read(|cursors| Ok(Planner::new(col, cursors)?.plan(&filter)))
+                .unwrap();
+            where_clauses
+                .iter()
+                .map(|wc| match wc {
+                    WhereClause::Id(_) => "id",
+                    WhereClause::Index(_) => "index",
+                    WhereClause::NGram(_) => "ngram",
+                    WhereClause::Intersection(_) => "intersection",
+                })
+                .collect_vec()
+        };
+
+        let selective = IntBetweenCond::filter(field1, 10, 12).unwrap();
+        let unselective = IntBetweenCond::filter(field2, 1, 1).unwrap();
+        assert_eq!(plan(selective.clone()), vec!["index"]);
+        assert_eq!(plan(unselective.clone()), vec!["id"]);
+
+        let and = AndCond::filter(vec![unselective.clone(), selective.clone()]);
+        assert_eq!(plan(and), vec!["index"]);
+
+        let half = IntBetweenCond::filter(field1, 10, 60).unwrap();
+        let and = AndCond::filter(vec![half, unselective.clone()]);
+        assert_eq!(plan(and), vec!["intersection"]);
+
+        let id = LongBetweenCond::filter(oid, 5, 6).unwrap();
+        let or = OrCond::filter(vec![selective, id]);
+        assert_eq!(plan(or), vec!["index", "id"]);
+
+        let unindexed_or = OrCond::filter(vec![unselective, StaticCond::filter(true)]);
+        assert_eq!(plan(unindexed_or), vec!["id"]);
+
+        let points = IntInCond::filter(field1, &[7, 3, 5]).unwrap();
+        assert_eq!(plan(points), vec!["index", "index", "index"]);
+
+        txn.abort();
+        isar.close();
+    }
+
+    #[test]
+    fn test_plan_without_stats() {
+        isar!(isar, col => col!(oid => DataType::Long, a => DataType::Int, b => DataType::Int, name => DataType::String; ind!(a, b), ind!(str name, IndexType::Value, Some(false))));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..20 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int((oid % 4) as i32);
+            builder.write_int(oid as i32);
+            builder.write_string(Some(format!("Name{}", oid).as_str()));
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        for oid in 20..22 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int(IsarObject::NULL_INT);
+            builder.write_int(0);
+            builder.write_string(None);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let properties = col.get_properties();
+        let a = properties.get(1).unwrap().1;
+        let b = properties.get(2).unwrap().1;
+        let name = properties.get(3).unwrap().1;
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(a, 1, 1).unwrap());
+        let query = qb.build();
+        assert!(matches!(
+            query.where_clauses.as_slice(),
+            [WhereClause::Id(_)]
+        ));
+        assert!(query.filter.is_some());
+
+        let oid = properties.get(0).unwrap().1;
+        let mut find = |filter: Filter, index_only: bool| {
+            let mut qb = col.new_query_builder();
+            qb.set_filter(filter);
+            qb.add_sort(oid, Sort::Ascending);
+            let query = qb.build();
+            let uses_index = matches!(query.where_clauses.as_slice(), [WhereClause::Index(_)]);
+            assert_eq!(uses_index && query.filter.is_none(), index_only);
+            query
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        let filter = AndCond::filter(vec![
+            IntBetweenCond::filter(a, 1, 1).unwrap(),
+            IntBetweenCond::filter(b, 5, 13).unwrap(),
+        ]);
+        assert_eq!(find(filter, true), vec![5, 9, 13]);
+
+        let filter = AndCond::filter(vec![
+            IntBetweenCond::filter(b, 0, 3).unwrap(),
+            IntBetweenCond::filter(a, 2, 2).unwrap(),
+        ]);
+        assert_eq!(find(filter, true), vec![2]);
+
+        let filter = StringStartsWithCond::filter(name, Some("name1"), false).unwrap();
+        assert_eq!(
+            find(filter, true),
+            vec![1, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19]
+        );
+
+        let filter = AndCond::filter(vec![
+            IntBetweenCond::filter(b, 2, 2).unwrap(),
+            IntBetweenCond::filter(a, 2, 2).unwrap(),
+        ]);
+        assert_eq!(find(filter, true), vec![2]);
+
+        let filter = OrCond::filter(vec![
+            IntBetweenCond::filter(a, 3, 3).unwrap(),
+            NotCond::filter(IntBetweenCond::filter(b, 0, 17).unwrap()),
+        ]);
+        assert_eq!(find(filter, false), vec![3, 7, 11, 15, 18, 19]);
+
+        let filter = StringInCond::filter(name, &[Some("NAME3"), Some("name12")], false).unwrap();
+        assert_eq!(find(filter, false), vec![3, 12]);
+
+        assert_eq!(find(IsNullCond::filter(name), true), vec![20, 21]);
+        assert_eq!(find(IsNullCond::filter(a), true), vec![20, 21]);
+        let filter = AndCond::filter(vec![
+            IsNotNullCond::filter(a),
+            IntBetweenCond::filter(b, 0, 0).unwrap(),
+        ]);
+        assert_eq!(find(filter, false), vec![0]);
+
+        txn.abort();
+        isar.close();
+    }
+}
@@ -1,10 +1,15 @@
 use crate::error::{illegal_arg, Result};
 use crate::lmdb::{MAX_ID, MIN_ID};
+use crate::object::collation::Collation;
 use crate::object::isar_object::Property;
-use crate::query::filter::Filter;
+use crate::query::filter::{AndCond, Filter, WhereClauseCond};
 use crate::query::id_where_clause::IdWhereClause;
+use crate::query::intersection_where_clause::IntersectionWhereClause;
+use crate::query::ngram_where_clause::{NGramQuery, NGramWhereClause};
+use crate::query::planner::Planner;
 use crate::query::where_clause::WhereClause;
-use crate::query::{Query, Sort};
+use crate::query::{Query, Sort, DEFAULT_SORT_MEMORY_BUDGET};
+use crate::txn::IsarTxn;
 use crate::{collection::IsarCollection, index::index_key::IndexKey};
 use itertools::Itertools;
 
@@ -13,11 +18,13 @@ use super::index_where_clause::IndexWhereClause;
 pub struct QueryBuilder<'a> {
     collection: &'a IsarCollection,
     where_clauses: Option<Vec<WhereClause>>,
+    where_clause_groups: Vec<Vec<WhereClause>>,
     filter: Option<Filter>,
-    sort: Vec<(Property, Sort)>,
+    sort: Vec<(Property, Sort, Option<Collation>)>,
     distinct: Vec<(Property, bool)>,
     offset: usize,
     limit: usize,
+    sort_memory_budget: usize,
 }
 
 impl<'a> QueryBuilder<'a> {
@@ -25,11 +32,13 @@ impl<'a> QueryBuilder<'a> {
         QueryBuilder {
             collection,
             where_clauses: None,
+            where_clause_groups: vec![],
             filter: None,
             sort: vec![],
             distinct: vec![],
             offset: 0,
             limit: usize::MAX,
+            sort_memory_budget: DEFAULT_SORT_MEMORY_BUDGET,
         }
     }
 
@@ -47,6 +56,14 @@ impl<'a> QueryBuilder<'a> {
         Ok(())
     }
 
+    /// Adds an id where clause whose bounds are bound later using [Query::bind].
+    pub fn add_param_id_where_clause(&mut self, lower_param: &str, upper_param: &str, sort: Sort) {
+        let wc = IdWhereClause::new_param(self.collection, lower_param, upper_param, sort);
+        self.where_clauses
+            .get_or_insert_with(Vec::new)
+            .push(WhereClause::Id(wc));
+    }
+
     pub fn add_index_where_clause(
         &mut self,
         lower_key: IndexKey,
@@ -59,6 +76,20 @@ impl<'a> QueryBuilder<'a> {
         if lower_key.index.get_col_id() != self.collection.get_id() {
             return illegal_arg("Invalid IndexKey for this collection");
         }
+        if !lower_key.params.is_empty() || !upper_key.params.is_empty() {
+            let wc = IndexWhereClause::with_params(
+                lower_key,
+                include_lower,
+                upper_key,
+                include_upper,
+                skip_duplicates,
+                sort,
+            )?;
+            self.where_clauses
+                .get_or_insert_with(Vec::new)
+                .push(WhereClause::Index(wc));
+            return Ok(());
+        }
         let mut wc = IndexWhereClause::new(lower_key, upper_key, skip_duplicates, sort)?;
         if self.where_clauses.is_none() {
             self.where_clauses = Some(vec![]);
@@ -72,12 +103,58 @@ impl<'a> QueryBuilder<'a> {
         Ok(())
     }
 
+    pub fn add_ngram_where_clause(&mut self, index_index: usize, query: NGramQuery) -> Result<()> {
+        let index = self.collection.get_indexes().get(index_index);
+        if let Some(index) = index {
+            let wc = NGramWhereClause::new(self.collection, index, query)?;
+            if self.where_clauses.is_none() {
+                self.where_clauses = Some(vec![]);
+            }
+            self.where_clauses
+                .as_mut()
+                .unwrap()
+                .push(WhereClause::NGram(wc));
+            Ok(())
+        } else {
+            illegal_arg("Index does not exist.")
+        }
+    }
+
+    /// Where clauses added after this call form a new group. The where clauses of a group are
+    /// unioned and the results of all groups are intersected.
+    pub fn add_where_clause_group(&mut self) {
+        if let Some(where_clauses) = self.where_clauses.take() {
+            self.where_clause_groups.push(where_clauses);
+        }
+    }
+
     pub fn set_filter(&mut self, filter: Filter) {
         self.filter = Some(filter);
     }
 
+    /// Lets the planner choose the cheapest where clauses for the filter using the collection
+    /// statistics. Conditions answered by the where clauses are removed from the filter. Does
+    /// nothing if where clauses have been added manually.
+    pub fn plan_where_clauses(&mut self, txn: &mut IsarTxn) -> Result<()> {
+        if self.where_clauses.is_some() || !self.where_clause_groups.is_empty() {
+            return Ok(());
+        }
+        if let Some(filter) = &self.filter {
+            let collection = self.collection;
+            let (where_clauses, filter) =
+                txn.read(|cursors| Ok(Planner::new(collection, cursors)?.plan(filter)))?;
+            self.where_clauses = Some(where_clauses);
+            self.filter = filter;
+        }
+        Ok(())
+    }
+
     pub fn add_sort(&mut self, property: Property, sort: Sort) {
-        self.sort.push((property, sort))
+        self.sort.push((property, sort, None))
+    }
+
+    pub fn add_collated_sort(&mut self, property: Property, sort: Sort, collation: Collation) {
+        self.sort.push((property, sort, Some(collation)))
     }
 
     pub fn add_distinct(&mut self, property: Property, case_sensitive: bool) {
@@ -92,24 +169,79 @@ impl<'a> QueryBuilder<'a> {
         self.limit = limit;
     }
 
+    /// Maximum total size in bytes of the objects sorted at once before the ids of a sorted run
+    /// are written to a temporary file. The objects are not copied: the budget limits how much of
+    /// the memory mapped database a sort keeps referenced. The objects are read again from the
+    /// database when the runs are merged. Budgets smaller than 1 MiB are raised to 1 MiB.
+    pub fn set_sort_memory_budget(&mut self, bytes: usize) {
+        self.sort_memory_budget = bytes;
+    }
+
+    /// Without manual where clauses, the filter of a sorted query is answered using indexes where
+    /// possible and the answered conditions are removed from the filter. Unsorted queries scan
+    /// the collection in id order unless [QueryBuilder::plan_where_clauses] is used.
     pub fn build(mut self) -> Query {
-        if self.where_clauses.is_none() {
-            self.add_id_where_clause(MIN_ID, MAX_ID, Sort::Ascending)
-                .unwrap();
+        if !self.where_clause_groups.is_empty() {
+            self.add_where_clause_group();
+            let mut groups = std::mem::take(&mut self.where_clause_groups);
+            let where_clauses = if groups.len() == 1 {
+                groups.pop().unwrap()
+            } else {
+                let wc = IntersectionWhereClause::new(self.collection, groups);
+                vec![WhereClause::Intersection(wc)]
+            };
+            self.where_clauses = Some(where_clauses);
         }
-        let sort_unique = self.sort.into_iter().unique_by(|(p, _)| p.offset).collect();
+
+        let sort_unique: Vec<_> = self
+            .sort
+            .into_iter()
+            .unique_by(|(p, _, _)| p.offset)
+            .collect();
         let distinct_unique = self
             .distinct
             .into_iter()
             .unique_by(|(p, _)| p.offset)
             .collect();
+
+        let (where_clauses, filter) = if let Some(where_clauses) = self.where_clauses {
+            Self::check_partial_indexes(self.collection, where_clauses, self.filter)
+        } else if let (Some(filter), false) = (&self.filter, sort_unique.is_empty()) {
+            Planner::without_stats(self.collection).plan(filter)
+        } else {
+            let wc = IdWhereClause::new(self.collection, MIN_ID, MAX_ID, Sort::Ascending);
+            (vec![WhereClause::Id(wc)], self.filter)
+        };
+
         Query::new(
-            self.where_clauses.unwrap(),
-            self.filter,
+            where_clauses,
+            filter,
             sort_unique,
             distinct_unique,
             self.offset,
             self.limit,
+            self.collection,
+            self.sort_memory_budget,
         )
     }
+
+    /// Replaces the where clauses with a full scan if they use a partial index which is not
+    /// implied by the filter.
+    fn check_partial_indexes(
+        collection: &IsarCollection,
+        where_clauses: Vec<WhereClause>,
+        filter: Option<Filter>,
+    ) -> (Vec<WhereClause>, Option<Filter>) {
+        if where_clauses.iter().all(|wc| wc.is_usable(filter.as_ref())) {
+            return (where_clauses, filter);
+        }
+        let wc_filter = WhereClauseCond::filter(where_clauses, collection.get_oid_property());
+        let filter = if let Some(filter) = filter {
+            AndCond::filter(vec![wc_filter, filter])
+        } else {
+            wc_filter
+        };
+        let full_wc = IdWhereClause::new(collection, MIN_ID, MAX_ID, Sort::Ascending);
+        (vec![WhereClause::Id(full_wc)], Some(filter))
+    }
 }
@@ -0,0 +1,282 @@
+use crate::error::{IsarError, Result};
+use crate::lmdb::cursor::Cursor;
+use crate::lmdb::IntKey;
+use crate::object::isar_object::IsarObject;
+use crate::query::filter::{Condition, FilterCursors};
+use crate::query::where_clause::WhereClause;
+use crate::query::Query;
+use crate::txn::{Cursors, IsarTxn};
+use hashbrown::HashSet;
+use std::hash::Hasher;
+use wyhash::WyHash;
+
+/*
+Every call to `next` continues the current where clause from the position of its cursor. This is
+possible because the iterator borrows the transaction exclusively so no one else moves the
+cursors in between. Where clauses that compute their ids up front (n-gram and intersection) are
+resumed from their id list.
+
+Sorted queries need all results before the first one is known so they are executed completely on
+the first call.
+ */
+pub struct QueryIter<'a, 'txn> {
+    query: &'a Query,
+    txn: &'a mut IsarTxn<'txn>,
+    state: WhereClauseState,
+    result_ids: Option<HashSet<i64>>,
+    hashes: HashSet<u64>,
+    count: usize,
+    sorted: Option<std::vec::IntoIter<IsarObject<'txn>>>,
+    done: bool,
+}
+
+struct WhereClauseState {
+    index: usize,
+    started: bool,
+    ids: Option<std::vec::IntoIter<i64>>,
+}
+
+impl WhereClauseState {
+    fn next_object<'txn>(
+        &mut self,
+        where_clause: &WhereClause,
+        cursors: &mut Cursors<'txn>,
+    ) -> Result<Option<(i64, IsarObject<'txn>)>> {
+        let first = !self.started;
+        self.started = true;
+        match where_clause {
+            WhereClause::Id(wc) => wc.iter_step(&mut cursors.data, first),
+            WhereClause::Index(wc) => {
+                if let Some(id) = wc.iter_ids_step(&mut cursors.index, first)? {
+                    let col_id = wc.get_index().get_col_id();
+                    let object = Self::load_object(&mut cursors.data, col_id, id)?;
+                    Ok(Some((id, object)))
+                } else {
+                    Ok(None)
+                }
+            }
+            WhereClause::NGram(_) | WhereClause::Intersection(_) => {
+                if first {
+                    let ids = match where_clause {
+                        WhereClause::NGram(wc) => {
+                            wc.get_candidate_ids(&mut cursors.data, &mut cursors.index)?
+                        }
+                        WhereClause::Intersection(wc) => {
+                            wc.get_ids(&mut cursors.data, &mut cursors.index)?
+                        }
+                        _ => unreachable!(),
+                    };
+                    self.ids = Some(ids.into_iter());
+                }
+                let col_id = match where_clause {
+                    WhereClause::NGram(wc) => wc.get_col_id(),
+                    WhereClause::Intersection(wc) => wc.get_col_id(),
+                    _ => unreachable!(),
+                };
+                for id in self.ids.as_mut().unwrap() {
+                    let object = Self::load_object(&mut cursors.data, col_id, id)?;
+                    if where_clause.matches(id, object) {
+                        return Ok(Some((id, object)));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn load_object<'txn>(
+        data: &mut Cursor<'txn>,
+        col_id: u16,
+        id: i64,
+    ) -> Result<IsarObject<'txn>> {
+        let (_, object) = data
+            .move_to(IntKey::new(col_id, id))?
+            .ok_or(IsarError::DbCorrupted {
+                message: "Could not find object specified in index.".to_string(),
+            })?;
+        Ok(IsarObject::from_bytes(object))
+    }
+}
+
+impl<'a, 'txn> QueryIter<'a, 'txn> {
+    pub(crate) fn new(query: &'a Query, txn: &'a mut IsarTxn<'txn>) -> Self {
+        let result_ids = if query.where_clauses_overlapping {
+            Some(HashSet::new())
+        } else {
+            None
+        };
+        QueryIter {
+            query,
+            txn,
+            state: WhereClauseState {
+                index: 0,
+                started: false,
+                ids: None,
+            },
+            result_ids,
+            hashes: HashSet::new(),
+            count: 0,
+            sorted: None,
+            done: false,
+        }
+    }
+
+    /// Returns the next object matching the where clauses and the filter.
+    fn next_raw(&mut self) -> Result<Option<IsarObject<'txn>>> {
+        let query = self.query;
+        let state = &mut self.state;
+        let result_ids = &mut self.result_ids;
+        self.txn.read(|cursors| {
+            while let Some(where_clause) = query.where_clauses.get(state.index) {
+                let (id, object) = if let Some(next) = state.next_object(where_clause, cursors)? {
+                    next
+                } else {
+                    state.index += 1;
+                    state.started = false;
+                    state.ids = None;
+                    continue;
+                };
+
+                if let Some(result_ids) = result_ids.as_mut() {
+                    if !result_ids.insert(id) {
+                        continue;
+                    }
+                }
+                if let Some(filter) = &query.filter {
+                    let mut filter_cursors =
+                        FilterCursors::new(&mut cursors.data2, &mut cursors.links);
+                    if !filter.evaluate(object, Some(&mut filter_cursors))? {
+                        continue;
+                    }
+                }
+                return Ok(Some(object));
+            }
+            Ok(None)
+        })
+    }
+
+    fn next_unsorted(&mut self) -> Result<Option<IsarObject<'txn>>> {
+        let max_count = self.query.limit.saturating_add(self.query.offset);
+        while let Some(object) = self.next_raw()? {
+            if !self.query.distinct.is_empty() {
+                let mut hasher = WyHash::default();
+                for (property, case_sensitive) in &self.query.distinct {
+                    object.hash_property(*property, *case_sensitive, &mut hasher);
+                }
+                if !self.hashes.insert(hasher.finish()) {
+                    continue;
+                }
+            }
+            self.count += 1;
+            if self.count > max_count {
+                return Ok(None);
+            } else if self.count > self.query.offset {
+                return Ok(Some(object));
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_sorted(&mut self) -> Result<Option<IsarObject<'txn>>> {
+        if self.sorted.is_none() {
+            let query = self.query;
+            let results = self.txn.read(|cursors| {
+                let mut results = vec![];
+                query.find_all_internal(cursors, false, |object| {
+                    results.push(object);
+                    Ok(true)
+                })?;
+                Ok(results)
+            })?;
+            self.sorted = Some(results.into_iter());
+        }
+        Ok(self.sorted.as_mut().unwrap().next())
+    }
+}
+
+impl<'a, 'txn> Iterator for QueryIter<'a, 'txn> {
+    type Item = Result<IsarObject<'txn>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = if self.query.sort.is_empty() || self.query.where_clause_sorted {
+            self.next_unsorted()
+        } else {
+            self.next_sorted()
+        };
+        match result {
+            Ok(Some(object)) => Some(Ok(object)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::data_type::DataType;
+    use crate::query::filter::IntBetweenCond;
+    use crate::query::Sort;
+    use crate::{col, ind, isar};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_iter() {
+        isar!(isar, col => col!(oid => DataType::Long, field => DataType::Int; ind!(field)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        for oid in 0..20 {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int((oid % 5) as i32);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let oid = col.get_oid_property();
+        let field = col.get_properties().get(1).unwrap().1;
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(field, 1, 2).unwrap());
+        qb.add_id_where_clause(0, 9, Sort::Descending).unwrap();
+        qb.add_id_where_clause(5, 15, Sort::Ascending).unwrap();
+        let query = qb.build();
+        let oids = query
+            .iter(&mut txn)
+            .map(|o| o.unwrap().read_long(oid))
+            .collect_vec();
+        assert_eq!(oids, vec![7, 6, 2, 1, 11, 12]);
+        let first = query.iter(&mut txn).next().unwrap().unwrap();
+        assert_eq!(first.read_long(oid), 7);
+
+        let mut qb = col.new_query_builder();
+        qb.add_distinct(field, false);
+        qb.set_offset(1);
+        let query = qb.build();
+        let oids = query
+            .iter(&mut txn)
+            .map(|o| o.unwrap().read_long(oid))
+            .collect_vec();
+        assert_eq!(oids, vec![1, 2, 3, 4]);
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(IntBetweenCond::filter(field, 4, 4).unwrap());
+        qb.add_sort(oid, Sort::Descending);
+        qb.add_sort(field, Sort::Ascending);
+        qb.set_limit(3);
+        let query = qb.build();
+        let oids = query
+            .iter(&mut txn)
+            .map(|o| o.unwrap().read_long(oid))
+            .collect_vec();
+        assert_eq!(oids, vec![19, 14, 9]);
+
+        txn.abort();
+        isar.close();
+    }
+}
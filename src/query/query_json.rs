@@ -0,0 +1,1432 @@
+use crate::collection::IsarCollection;
+use crate::error::{illegal_arg, IsarError, Result};
+use crate::index::index_key::{IndexKey, KeyParam};
+use crate::index::Index;
+use crate::instance::IsarInstance;
+use crate::link::Link;
+use crate::object::collation::Collation;
+use crate::object::isar_object::Property;
+use crate::query::filter::*;
+use crate::query::id_where_clause::IdWhereClause;
+use crate::query::index_where_clause::IndexWhereClause;
+use crate::query::intersection_where_clause::IntersectionWhereClause;
+use crate::query::ngram_where_clause::{NGramQuery, NGramWhereClause};
+use crate::query::where_clause::WhereClause;
+use crate::query::{Query, Sort};
+use crate::schema::collection_schema::IndexType;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/*
+The JSON form refers to properties, indexes and links by name so it stays valid as long as the
+schema does not change incompatibly. Filters are rebuilt using their public constructors and fail
+with the same errors as building them manually. Index keys are stored without the index prefix.
+
+JSON cannot represent NaN and infinity. Null floats (NaN) are stored as `null` and infinite
+floats as `"Infinity"` and `"-Infinity"`.
+
+Parameters are stored by name but bound values are not. Index keys containing parameters are
+stored as templates and a restored query has to be bound again before it matches anything.
+ */
+
+impl Query {
+    /// Serializes the query. `collection` has to be the collection the query was built for.
+    pub fn to_json(&self, isar: &IsarInstance, collection: &IsarCollection) -> Result<Value> {
+        if collection.get_id() != self.col_id {
+            return illegal_arg("Query does not belong to this collection.");
+        }
+        let where_clauses = self
+            .where_clauses
+            .iter()
+            .map(|wc| encode_where_clause(collection, wc))
+            .collect::<Result<_>>()?;
+        let filter = self
+            .filter
+            .as_ref()
+            .map(|filter| encode_filter(isar, collection, filter))
+            .transpose()?;
+        let sort = self
+            .sort
+            .iter()
+            .map(|(property, sort, collation)| {
+                Ok(SortJson {
+                    property: property_name(collection, *property)?,
+                    sort: *sort,
+                    collation: *collation,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let distinct = self
+            .distinct
+            .iter()
+            .map(|(property, case_sensitive)| {
+                Ok(DistinctJson {
+                    property: property_name(collection, *property)?,
+                    case_sensitive: *case_sensitive,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let json = QueryJson {
+            where_clauses,
+            filter,
+            sort,
+            distinct,
+            offset: self.offset,
+            limit: self.limit,
+            sort_memory_budget: self.sort_memory_budget,
+        };
+        Ok(serde_json::to_value(json).unwrap())
+    }
+
+    /// Restores a query serialized using [Query::to_json].
+    pub fn from_json(
+        isar: &IsarInstance,
+        collection: &IsarCollection,
+        json: &Value,
+    ) -> Result<Query> {
+        let json = QueryJson::deserialize(json).map_err(|_| IsarError::InvalidJson {})?;
+        let where_clauses = json
+            .where_clauses
+            .into_iter()
+            .map(|wc| decode_where_clause(collection, wc))
+            .collect::<Result<_>>()?;
+        let filter = json
+            .filter
+            .map(|filter| decode_filter(isar, collection, filter))
+            .transpose()?;
+        let sort = json
+            .sort
+            .into_iter()
+            .map(|s| Ok((find_property(collection, &s.property)?, s.sort, s.collation)))
+            .collect::<Result<_>>()?;
+        let distinct = json
+            .distinct
+            .into_iter()
+            .map(|d| Ok((find_property(collection, &d.property)?, d.case_sensitive)))
+            .collect::<Result<_>>()?;
+        Ok(Query::new(
+            where_clauses,
+            filter,
+            sort,
+            distinct,
+            json.offset,
+            json.limit,
+            collection,
+            json.sort_memory_budget,
+        ))
+    }
+}
+
+impl Filter {
+    /// Serializes the filter. `collection` has to be the collection the filter was built for.
+    pub fn to_json(&self, isar: &IsarInstance, collection: &IsarCollection) -> Result<Value> {
+        let json = encode_filter(isar, collection, self)?;
+        Ok(serde_json::to_value(json).unwrap())
+    }
+
+    /// Restores a filter serialized using [Filter::to_json].
+    pub fn from_json(
+        isar: &IsarInstance,
+        collection: &IsarCollection,
+        json: &Value,
+    ) -> Result<Filter> {
+        let json = FilterJson::deserialize(json).map_err(|_| IsarError::InvalidJson {})?;
+        decode_filter(isar, collection, json)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueryJson {
+    #[serde(rename = "whereClauses")]
+    where_clauses: Vec<WhereClauseJson>,
+    filter: Option<FilterJson>,
+    sort: Vec<SortJson>,
+    distinct: Vec<DistinctJson>,
+    offset: usize,
+    limit: usize,
+    #[serde(rename = "sortMemoryBudget")]
+    sort_memory_budget: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SortJson {
+    property: String,
+    sort: Sort,
+    collation: Option<Collation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DistinctJson {
+    property: String,
+    #[serde(rename = "caseSensitive")]
+    case_sensitive: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum WhereClauseJson {
+    Id {
+        lower: i64,
+        upper: i64,
+        sort: Sort,
+        #[serde(
+            default,
+            rename = "lowerParam",
+            skip_serializing_if = "Option::is_none"
+        )]
+        lower_param: Option<String>,
+        #[serde(
+            default,
+            rename = "upperParam",
+            skip_serializing_if = "Option::is_none"
+        )]
+        upper_param: Option<String>,
+    },
+    Index {
+        index: Vec<IndexPropertyJson>,
+        lower: Vec<u8>,
+        upper: Vec<u8>,
+        #[serde(rename = "skipDuplicates")]
+        skip_duplicates: bool,
+        sort: Sort,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        params: Option<KeyParamsJson>,
+    },
+    #[serde(rename = "ngram")]
+    NGram {
+        index: Vec<IndexPropertyJson>,
+        query: NGramQuery,
+    },
+    Intersection {
+        groups: Vec<Vec<WhereClauseJson>>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyParamsJson {
+    lower: Vec<KeyParamJson>,
+    #[serde(rename = "includeLower")]
+    include_lower: bool,
+    upper: Vec<KeyParamJson>,
+    #[serde(rename = "includeUpper")]
+    include_upper: bool,
+}
+
+/// The offset is relative to the key without the index prefix.
+#[derive(Serialize, Deserialize)]
+struct KeyParamJson {
+    offset: usize,
+    position: usize,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexPropertyJson {
+    name: String,
+    #[serde(rename = "indexType")]
+    index_type: IndexType,
+    #[serde(rename = "caseSensitive")]
+    case_sensitive: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum FilterJson {
+    ByteBetween {
+        property: String,
+        lower: u8,
+        upper: u8,
+    },
+    IntBetween {
+        property: String,
+        lower: i32,
+        upper: i32,
+    },
+    LongBetween {
+        property: String,
+        lower: i64,
+        upper: i64,
+    },
+    FloatBetween {
+        property: String,
+        lower: JsonFloat,
+        upper: JsonFloat,
+    },
+    DoubleBetween {
+        property: String,
+        lower: JsonFloat,
+        upper: JsonFloat,
+    },
+
+    ByteListContains {
+        property: String,
+        value: u8,
+    },
+    IntListContains {
+        property: String,
+        value: i32,
+    },
+    LongListContains {
+        property: String,
+        value: i64,
+    },
+    ByteListBetween {
+        property: String,
+        lower: u8,
+        upper: u8,
+        all: bool,
+    },
+    IntListBetween {
+        property: String,
+        lower: i32,
+        upper: i32,
+        all: bool,
+    },
+    LongListBetween {
+        property: String,
+        lower: i64,
+        upper: i64,
+        all: bool,
+    },
+    FloatListBetween {
+        property: String,
+        lower: JsonFloat,
+        upper: JsonFloat,
+        all: bool,
+    },
+    DoubleListBetween {
+        property: String,
+        lower: JsonFloat,
+        upper: JsonFloat,
+        all: bool,
+    },
+    ByteListContainsValues {
+        property: String,
+        values: Vec<u8>,
+        all: bool,
+    },
+    IntListContainsValues {
+        property: String,
+        values: Vec<i32>,
+        all: bool,
+    },
+    LongListContainsValues {
+        property: String,
+        values: Vec<i64>,
+        all: bool,
+    },
+    FloatListContainsValues {
+        property: String,
+        values: Vec<JsonFloat>,
+        all: bool,
+    },
+    DoubleListContainsValues {
+        property: String,
+        values: Vec<JsonFloat>,
+        all: bool,
+    },
+    ListLength {
+        property: String,
+        lower: usize,
+        upper: usize,
+    },
+
+    ByteIn {
+        property: String,
+        values: Vec<u8>,
+    },
+    IntIn {
+        property: String,
+        values: Vec<i32>,
+    },
+    LongIn {
+        property: String,
+        values: Vec<i64>,
+    },
+    FloatIn {
+        property: String,
+        values: Vec<JsonFloat>,
+    },
+    DoubleIn {
+        property: String,
+        values: Vec<JsonFloat>,
+    },
+    StringIn {
+        property: String,
+        values: Vec<Option<String>>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+
+    StringBetween {
+        property: String,
+        lower: Option<String>,
+        upper: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+        collation: Option<Collation>,
+    },
+    StringStartsWith {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringEndsWith {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringMatches {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringContains {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringFuzzy {
+        property: String,
+        value: String,
+        #[serde(rename = "maxDistance")]
+        max_distance: usize,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringRegex {
+        property: String,
+        pattern: String,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+
+    StringListContains {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringListRegex {
+        property: String,
+        pattern: String,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    StringListBetween {
+        property: String,
+        lower: Option<String>,
+        upper: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+        all: bool,
+    },
+    StringListContainsValues {
+        property: String,
+        values: Vec<Option<String>>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+        all: bool,
+    },
+
+    IsNull {
+        property: String,
+    },
+    IsNotNull {
+        property: String,
+    },
+
+    PropertyCompare {
+        property: String,
+        comparison: Comparison,
+        #[serde(rename = "otherProperty")]
+        other_property: String,
+    },
+    PropertyOffsetBetween {
+        property: String,
+        #[serde(rename = "otherProperty")]
+        other_property: String,
+        lower: JsonFloat,
+        upper: JsonFloat,
+    },
+
+    Param {
+        name: String,
+        property: String,
+        op: ParamOp,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+
+    And {
+        filters: Vec<FilterJson>,
+    },
+    Or {
+        filters: Vec<FilterJson>,
+    },
+    Not {
+        filter: Box<FilterJson>,
+    },
+    Static {
+        value: bool,
+    },
+    /// `collection` is the collection defining the link.
+    Link {
+        collection: String,
+        link: String,
+        backlink: bool,
+        filter: Box<FilterJson>,
+    },
+    WhereClause {
+        #[serde(rename = "whereClauses")]
+        where_clauses: Vec<WhereClauseJson>,
+    },
+}
+
+#[derive(Copy, Clone)]
+struct JsonFloat(f64);
+
+impl From<f32> for JsonFloat {
+    fn from(value: f32) -> Self {
+        JsonFloat(value as f64)
+    }
+}
+
+impl From<f64> for JsonFloat {
+    fn from(value: f64) -> Self {
+        JsonFloat(value)
+    }
+}
+
+impl Serialize for JsonFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if self.0.is_nan() {
+            serializer.serialize_none()
+        } else if self.0 == f64::INFINITY {
+            serializer.serialize_str("Infinity")
+        } else if self.0 == f64::NEG_INFINITY {
+            serializer.serialize_str("-Infinity")
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonFloat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Special(Option<String>),
+        }
+        let value = match Repr::deserialize(deserializer)? {
+            Repr::Number(value) => value,
+            Repr::Special(None) => f64::NAN,
+            Repr::Special(Some(s)) if s == "Infinity" => f64::INFINITY,
+            Repr::Special(Some(s)) if s == "-Infinity" => f64::NEG_INFINITY,
+            Repr::Special(Some(_)) => return Err(serde::de::Error::custom("invalid float")),
+        };
+        Ok(JsonFloat(value))
+    }
+}
+
+fn property_name(collection: &IsarCollection, property: Property) -> Result<String> {
+    let name = collection
+        .get_properties()
+        .iter()
+        .find(|(_, p)| *p == property)
+        .map(|(name, _)| name.clone());
+    if let Some(name) = name {
+        Ok(name)
+    } else {
+        illegal_arg("Property does not belong to this collection.")
+    }
+}
+
+fn find_property(collection: &IsarCollection, name: &str) -> Result<Property> {
+    let property = collection
+        .get_properties()
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, p)| *p);
+    if let Some(property) = property {
+        Ok(property)
+    } else {
+        illegal_arg(&format!("Unknown property `{}`.", name))
+    }
+}
+
+fn get_collection(isar: &IsarInstance, col_id: u16) -> Result<&IsarCollection> {
+    let collection = isar.get_collections().iter().find(|c| c.get_id() == col_id);
+    if let Some(collection) = collection {
+        Ok(collection)
+    } else {
+        illegal_arg("Collection does not exist.")
+    }
+}
+
+/// Returns the collection defining `link`, the name of the link and whether `link` is its
+/// backlink.
+fn find_link(isar: &IsarInstance, link: Link) -> Result<(&IsarCollection, String, bool)> {
+    for collection in isar.get_collections() {
+        for (name, l) in collection.get_links() {
+            if *l == link || l.to_backlink() == link {
+                return Ok((collection, name.clone(), *l != link));
+            }
+        }
+    }
+    illegal_arg("Link does not exist.")
+}
+
+fn encode_index(collection: &IsarCollection, index: &Index) -> Result<Vec<IndexPropertyJson>> {
+    index
+        .properties
+        .iter()
+        .map(|ip| {
+            Ok(IndexPropertyJson {
+                name: property_name(collection, ip.property)?,
+                index_type: ip.index_type,
+                case_sensitive: ip.case_sensitive,
+            })
+        })
+        .collect()
+}
+
+fn find_index<'a>(
+    collection: &'a IsarCollection,
+    properties: &[IndexPropertyJson],
+) -> Result<&'a Index> {
+    let index = collection.get_indexes().iter().find(|index| {
+        index.properties.len() == properties.len()
+            && index.properties.iter().zip(properties).all(|(ip, json)| {
+                ip.index_type == json.index_type
+                    && ip.case_sensitive == json.case_sensitive
+                    && property_name(collection, ip.property).ok().as_ref() == Some(&json.name)
+            })
+    });
+    if let Some(index) = index {
+        Ok(index)
+    } else {
+        illegal_arg("Index does not exist.")
+    }
+}
+
+fn encode_where_clause(collection: &IsarCollection, wc: &WhereClause) -> Result<WhereClauseJson> {
+    let json = match wc {
+        WhereClause::Id(wc) => WhereClauseJson::Id {
+            lower: wc.lower,
+            upper: wc.upper,
+            sort: wc.sort,
+            lower_param: wc.lower_param.clone(),
+            upper_param: wc.upper_param.clone(),
+        },
+        WhereClause::Index(wc) => {
+            let index = wc.get_index();
+            let prefix_len = index.get_prefix().len();
+            let encode_params = |params: &[KeyParam]| {
+                params
+                    .iter()
+                    .map(|param| KeyParamJson {
+                        offset: param.offset - prefix_len,
+                        position: param.position,
+                        name: param.name.clone(),
+                    })
+                    .collect()
+            };
+            let (lower, upper, params) = if let Some(params) = &wc.params {
+                let params_json = KeyParamsJson {
+                    lower: encode_params(&params.lower_params),
+                    include_lower: params.include_lower,
+                    upper: encode_params(&params.upper_params),
+                    include_upper: params.include_upper,
+                };
+                (&params.lower, &params.upper, Some(params_json))
+            } else {
+                (&wc.lower_key, &wc.upper_key, None)
+            };
+            WhereClauseJson::Index {
+                index: encode_index(collection, index)?,
+                lower: lower[prefix_len..].to_vec(),
+                upper: upper[prefix_len..].to_vec(),
+                skip_duplicates: wc.skip_duplicates,
+                sort: wc.sort,
+                params,
+            }
+        }
+        WhereClause::NGram(wc) => {
+            let index = collection
+                .get_indexes()
+                .iter()
+                .find(|index| index.id == wc.index_id);
+            let index = if let Some(index) = index {
+                index
+            } else {
+                return illegal_arg("Index does not exist.");
+            };
+            WhereClauseJson::NGram {
+                index: encode_index(collection, index)?,
+                query: wc.query.clone(),
+            }
+        }
+        WhereClause::Intersection(wc) => {
+            let groups = wc
+                .groups
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|wc| encode_where_clause(collection, wc))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .collect::<Result<_>>()?;
+            WhereClauseJson::Intersection { groups }
+        }
+    };
+    Ok(json)
+}
+
+fn decode_where_clause(collection: &IsarCollection, json: WhereClauseJson) -> Result<WhereClause> {
+    let wc = match json {
+        WhereClauseJson::Id {
+            lower,
+            upper,
+            sort,
+            lower_param,
+            upper_param,
+        } => {
+            let wc = match (lower_param, upper_param) {
+                (None, None) => IdWhereClause::new(collection, lower, upper, sort),
+                (Some(lower_param), Some(upper_param)) => {
+                    IdWhereClause::new_param(collection, &lower_param, &upper_param, sort)
+                }
+                _ => return Err(IsarError::InvalidJson {}),
+            };
+            WhereClause::Id(wc)
+        }
+        WhereClauseJson::Index {
+            index,
+            lower,
+            upper,
+            skip_duplicates,
+            sort,
+            params,
+        } => {
+            let index = find_index(collection, &index)?;
+            let wc = if let Some(params) = params {
+                let lower = decode_key(index, &lower, params.lower)?;
+                let upper = decode_key(index, &upper, params.upper)?;
+                IndexWhereClause::with_params(
+                    lower,
+                    params.include_lower,
+                    upper,
+                    params.include_upper,
+                    skip_duplicates,
+                    sort,
+                )?
+            } else {
+                let lower = decode_key(index, &lower, vec![])?;
+                let upper = decode_key(index, &upper, vec![])?;
+                IndexWhereClause::new(lower, upper, skip_duplicates, sort)?
+            };
+            WhereClause::Index(wc)
+        }
+        WhereClauseJson::NGram { index, query } => {
+            let index = find_index(collection, &index)?;
+            WhereClause::NGram(NGramWhereClause::new(collection, index, query)?)
+        }
+        WhereClauseJson::Intersection { groups } => {
+            let groups = groups
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|wc| decode_where_clause(collection, wc))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .collect::<Result<_>>()?;
+            WhereClause::Intersection(IntersectionWhereClause::new(collection, groups))
+        }
+    };
+    Ok(wc)
+}
+
+fn decode_key<'a>(
+    index: &'a Index,
+    bytes: &[u8],
+    params: Vec<KeyParamJson>,
+) -> Result<IndexKey<'a>> {
+    let mut key = IndexKey::new(index);
+    let mut last = 0;
+    for param in params {
+        if param.offset < last || param.offset > bytes.len() {
+            return Err(IsarError::InvalidJson {});
+        }
+        key.bytes.extend_from_slice(&bytes[last..param.offset]);
+        key.add_param(param.position, &param.name)?;
+        last = param.offset;
+    }
+    key.bytes.extend_from_slice(&bytes[last..]);
+    Ok(key)
+}
+
+/// Set values are sorted to keep the JSON of equal filters equal.
+fn sorted<T: Ord>(values: impl Iterator<Item = T>) -> Vec<T> {
+    let mut values: Vec<T> = values.collect();
+    values.sort_unstable();
+    values
+}
+
+fn sorted_floats<T: Into<f64>>(values: Vec<T>) -> Vec<JsonFloat> {
+    let mut values: Vec<f64> = values.into_iter().map(|v| v.into()).collect();
+    values.sort_by(|a, b| {
+        a.partial_cmp(b)
+            .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+    });
+    values.into_iter().map(JsonFloat).collect()
+}
+
+fn string_values(cond: &StringInCond) -> Vec<Option<String>> {
+    let mut values = sorted(cond.values.iter().cloned().map(Some));
+    if cond.null {
+        values.insert(0, None);
+    }
+    values
+}
+
+fn str_values(values: &[Option<String>]) -> Vec<Option<&str>> {
+    values.iter().map(|v| v.as_deref()).collect()
+}
+
+fn encode_filters(
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    filters: &[Filter],
+) -> Result<Vec<FilterJson>> {
+    filters
+        .iter()
+        .map(|filter| encode_filter(isar, collection, filter))
+        .collect()
+}
+
+fn encode_filter(
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    filter: &Filter,
+) -> Result<FilterJson> {
+    let name = |property: Property| property_name(collection, property);
+    let json = match filter {
+        Filter::ByteBetween(c) => FilterJson::ByteBetween {
+            property: name(c.property)?,
+            lower: c.lower,
+            upper: c.upper,
+        },
+        Filter::IntBetween(c) => FilterJson::IntBetween {
+            property: name(c.property)?,
+            lower: c.lower,
+            upper: c.upper,
+        },
+        Filter::LongBetween(c) => FilterJson::LongBetween {
+            property: name(c.property)?,
+            lower: c.lower,
+            upper: c.upper,
+        },
+        Filter::FloatBetween(c) => FilterJson::FloatBetween {
+            property: name(c.property)?,
+            lower: c.lower.into(),
+            upper: c.upper.into(),
+        },
+        Filter::DoubleBetween(c) => FilterJson::DoubleBetween {
+            property: name(c.property)?,
+            lower: c.lower.into(),
+            upper: c.upper.into(),
+        },
+
+        Filter::ByteListContains(c) => FilterJson::ByteListContains {
+            property: name(c.property)?,
+            value: c.value,
+        },
+        Filter::IntListContains(c) => FilterJson::IntListContains {
+            property: name(c.property)?,
+            value: c.value,
+        },
+        Filter::LongListContains(c) => FilterJson::LongListContains {
+            property: name(c.property)?,
+            value: c.value,
+        },
+        Filter::ByteListBetween(c) => FilterJson::ByteListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower,
+            upper: c.element.upper,
+            all: c.all,
+        },
+        Filter::IntListBetween(c) => FilterJson::IntListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower,
+            upper: c.element.upper,
+            all: c.all,
+        },
+        Filter::LongListBetween(c) => FilterJson::LongListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower,
+            upper: c.element.upper,
+            all: c.all,
+        },
+        Filter::FloatListBetween(c) => FilterJson::FloatListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower.into(),
+            upper: c.element.upper.into(),
+            all: c.all,
+        },
+        Filter::DoubleListBetween(c) => FilterJson::DoubleListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower.into(),
+            upper: c.element.upper.into(),
+            all: c.all,
+        },
+        Filter::ByteListContainsValues(c) => FilterJson::ByteListContainsValues {
+            property: name(c.property)?,
+            values: sorted(c.values.values.iter().copied()),
+            all: c.all,
+        },
+        Filter::IntListContainsValues(c) => FilterJson::IntListContainsValues {
+            property: name(c.property)?,
+            values: sorted(c.values.values.iter().copied()),
+            all: c.all,
+        },
+        Filter::LongListContainsValues(c) => FilterJson::LongListContainsValues {
+            property: name(c.property)?,
+            values: sorted(c.values.values.iter().copied()),
+            all: c.all,
+        },
+        Filter::FloatListContainsValues(c) => FilterJson::FloatListContainsValues {
+            property: name(c.property)?,
+            values: sorted_floats(c.values.get_values()),
+            all: c.all,
+        },
+        Filter::DoubleListContainsValues(c) => FilterJson::DoubleListContainsValues {
+            property: name(c.property)?,
+            values: sorted_floats(c.values.get_values()),
+            all: c.all,
+        },
+        Filter::ListLength(c) => FilterJson::ListLength {
+            property: name(c.property)?,
+            lower: c.lower,
+            upper: c.upper,
+        },
+
+        Filter::ByteIn(c) => FilterJson::ByteIn {
+            property: name(c.property)?,
+            values: sorted(c.values.iter().copied()),
+        },
+        Filter::IntIn(c) => FilterJson::IntIn {
+            property: name(c.property)?,
+            values: sorted(c.values.iter().copied()),
+        },
+        Filter::LongIn(c) => FilterJson::LongIn {
+            property: name(c.property)?,
+            values: sorted(c.values.iter().copied()),
+        },
+        Filter::FloatIn(c) => FilterJson::FloatIn {
+            property: name(c.property)?,
+            values: sorted_floats(c.get_values()),
+        },
+        Filter::DoubleIn(c) => FilterJson::DoubleIn {
+            property: name(c.property)?,
+            values: sorted_floats(c.get_values()),
+        },
+        Filter::StringIn(c) => FilterJson::StringIn {
+            property: name(c.property)?,
+            values: string_values(c),
+            case_sensitive: c.case_sensitive,
+        },
+
+        Filter::StringBetween(c) => FilterJson::StringBetween {
+            property: name(c.property)?,
+            lower: c.lower.clone(),
+            upper: c.upper.clone(),
+            case_sensitive: c.case_sensitive,
+            collation: c.collation,
+        },
+        Filter::StringStartsWith(c) => FilterJson::StringStartsWith {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringEndsWith(c) => FilterJson::StringEndsWith {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringMatches(c) => FilterJson::StringMatches {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringContains(c) => FilterJson::StringContains {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringFuzzy(c) => FilterJson::StringFuzzy {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            max_distance: c.max_distance,
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringRegex(c) => FilterJson::StringRegex {
+            property: name(c.property)?,
+            pattern: c.regex.as_str().to_string(),
+            case_sensitive: c.case_sensitive,
+        },
+
+        Filter::StringListContains(c) => FilterJson::StringListContains {
+            property: name(c.property)?,
+            value: c.value.clone(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringListRegex(c) => FilterJson::StringListRegex {
+            property: name(c.property)?,
+            pattern: c.regex.as_str().to_string(),
+            case_sensitive: c.case_sensitive,
+        },
+        Filter::StringListBetween(c) => FilterJson::StringListBetween {
+            property: name(c.property)?,
+            lower: c.element.lower.clone(),
+            upper: c.element.upper.clone(),
+            case_sensitive: c.element.case_sensitive,
+            all: c.all,
+        },
+        Filter::StringListContainsValues(c) => FilterJson::StringListContainsValues {
+            property: name(c.property)?,
+            values: string_values(&c.values),
+            case_sensitive: c.values.case_sensitive,
+            all: c.all,
+        },
+
+        Filter::IsNull(c) => FilterJson::IsNull {
+            property: name(c.property)?,
+        },
+        Filter::IsNotNull(c) => FilterJson::IsNotNull {
+            property: name(c.property)?,
+        },
+
+        Filter::PropertyCompare(c) => FilterJson::PropertyCompare {
+            property: name(c.property)?,
+            comparison: c.comparison,
+            other_property: name(c.other_property)?,
+        },
+        Filter::PropertyOffsetBetween(c) => FilterJson::PropertyOffsetBetween {
+            property: name(c.property)?,
+            other_property: name(c.other_property)?,
+            lower: c.lower.into(),
+            upper: c.upper.into(),
+        },
+
+        Filter::Param(c) => FilterJson::Param {
+            name: c.name.clone(),
+            property: name(c.property)?,
+            op: c.op,
+            case_sensitive: c.case_sensitive,
+        },
+
+        Filter::And(c) => FilterJson::And {
+            filters: encode_filters(isar, collection, &c.filters)?,
+        },
+        Filter::Or(c) => FilterJson::Or {
+            filters: encode_filters(isar, collection, &c.filters)?,
+        },
+        Filter::Not(c) => FilterJson::Not {
+            filter: Box::new(encode_filter(isar, collection, &c.filter)?),
+        },
+        Filter::Static(c) => FilterJson::Static { value: c.value },
+        Filter::Link(c) => {
+            let (link_collection, link, backlink) = find_link(isar, c.link)?;
+            let target_collection = get_collection(isar, c.link.get_target_col_id())?;
+            FilterJson::Link {
+                collection: link_collection.get_name().to_string(),
+                link,
+                backlink,
+                filter: Box::new(encode_filter(isar, target_collection, &c.filter)?),
+            }
+        }
+        Filter::WhereClause(c) => FilterJson::WhereClause {
+            where_clauses: c
+                .where_clauses
+                .iter()
+                .map(|wc| encode_where_clause(collection, wc))
+                .collect::<Result<_>>()?,
+        },
+    };
+    Ok(json)
+}
+
+fn decode_filters(
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    filters: Vec<FilterJson>,
+) -> Result<Vec<Filter>> {
+    filters
+        .into_iter()
+        .map(|filter| decode_filter(isar, collection, filter))
+        .collect()
+}
+
+fn decode_filter(
+    isar: &IsarInstance,
+    collection: &IsarCollection,
+    json: FilterJson,
+) -> Result<Filter> {
+    let p = |name: &str| find_property(collection, name);
+    let f32s = |values: Vec<JsonFloat>| values.iter().map(|v| v.0 as f32).collect::<Vec<_>>();
+    let f64s = |values: Vec<JsonFloat>| values.iter().map(|v| v.0).collect::<Vec<_>>();
+    let filter = match json {
+        FilterJson::ByteBetween {
+            property,
+            lower,
+            upper,
+        } => ByteBetweenCond::filter(p(&property)?, lower, upper)?,
+        FilterJson::IntBetween {
+            property,
+            lower,
+            upper,
+        } => IntBetweenCond::filter(p(&property)?, lower, upper)?,
+        FilterJson::LongBetween {
+            property,
+            lower,
+            upper,
+        } => LongBetweenCond::filter(p(&property)?, lower, upper)?,
+        FilterJson::FloatBetween {
+            property,
+            lower,
+            upper,
+        } => FloatBetweenCond::filter(p(&property)?, lower.0 as f32, upper.0 as f32)?,
+        FilterJson::DoubleBetween {
+            property,
+            lower,
+            upper,
+        } => DoubleBetweenCond::filter(p(&property)?, lower.0, upper.0)?,
+
+        FilterJson::ByteListContains { property, value } => {
+            ByteListContainsCond::filter(p(&property)?, value)?
+        }
+        FilterJson::IntListContains { property, value } => {
+            IntListContainsCond::filter(p(&property)?, value)?
+        }
+        FilterJson::LongListContains { property, value } => {
+            LongListContainsCond::filter(p(&property)?, value)?
+        }
+        FilterJson::ByteListBetween {
+            property,
+            lower,
+            upper,
+            all,
+        } => ByteListBetweenCond::filter(p(&property)?, lower, upper, all)?,
+        FilterJson::IntListBetween {
+            property,
+            lower,
+            upper,
+            all,
+        } => IntListBetweenCond::filter(p(&property)?, lower, upper, all)?,
+        FilterJson::LongListBetween {
+            property,
+            lower,
+            upper,
+            all,
+        } => LongListBetweenCond::filter(p(&property)?, lower, upper, all)?,
+        FilterJson::FloatListBetween {
+            property,
+            lower,
+            upper,
+            all,
+        } => FloatListBetweenCond::filter(p(&property)?, lower.0 as f32, upper.0 as f32, all)?,
+        FilterJson::DoubleListBetween {
+            property,
+            lower,
+            upper,
+            all,
+        } => DoubleListBetweenCond::filter(p(&property)?, lower.0, upper.0, all)?,
+        FilterJson::ByteListContainsValues {
+            property,
+            values,
+            all,
+        } => ByteListContainsValuesCond::filter(p(&property)?, &values, all)?,
+        FilterJson::IntListContainsValues {
+            property,
+            values,
+            all,
+        } => IntListContainsValuesCond::filter(p(&property)?, &values, all)?,
+        FilterJson::LongListContainsValues {
+            property,
+            values,
+            all,
+        } => LongListContainsValuesCond::filter(p(&property)?, &values, all)?,
+        FilterJson::FloatListContainsValues {
+            property,
+            values,
+            all,
+        } => FloatListContainsValuesCond::filter(p(&property)?, &f32s(values), all)?,
+        FilterJson::DoubleListContainsValues {
+            property,
+            values,
+            all,
+        } => DoubleListContainsValuesCond::filter(p(&property)?, &f64s(values), all)?,
+        FilterJson::ListLength {
+            property,
+            lower,
+            upper,
+        } => ListLengthCond::filter(p(&property)?, lower, upper)?,
+
+        FilterJson::ByteIn { property, values } => ByteInCond::filter(p(&property)?, &values)?,
+        FilterJson::IntIn { property, values } => IntInCond::filter(p(&property)?, &values)?,
+        FilterJson::LongIn { property, values } => LongInCond::filter(p(&property)?, &values)?,
+        FilterJson::FloatIn { property, values } => {
+            FloatInCond::filter(p(&property)?, &f32s(values))?
+        }
+        FilterJson::DoubleIn { property, values } => {
+            DoubleInCond::filter(p(&property)?, &f64s(values))?
+        }
+        FilterJson::StringIn {
+            property,
+            values,
+            case_sensitive,
+        } => StringInCond::filter(p(&property)?, &str_values(&values), case_sensitive)?,
+
+        FilterJson::StringBetween {
+            property,
+            lower,
+            upper,
+            case_sensitive,
+            collation,
+        } => StringBetweenCond::collated_filter(
+            p(&property)?,
+            lower.as_deref(),
+            upper.as_deref(),
+            case_sensitive,
+            collation,
+        )?,
+        FilterJson::StringStartsWith {
+            property,
+            value,
+            case_sensitive,
+        } => StringStartsWithCond::filter(p(&property)?, value.as_deref(), case_sensitive)?,
+        FilterJson::StringEndsWith {
+            property,
+            value,
+            case_sensitive,
+        } => StringEndsWithCond::filter(p(&property)?, value.as_deref(), case_sensitive)?,
+        FilterJson::StringMatches {
+            property,
+            value,
+            case_sensitive,
+        } => StringMatchesCond::filter(p(&property)?, value.as_deref(), case_sensitive)?,
+        FilterJson::StringContains {
+            property,
+            value,
+            case_sensitive,
+        } => StringContainsCond::filter(p(&property)?, value.as_deref(), case_sensitive)?,
+        FilterJson::StringFuzzy {
+            property,
+            value,
+            max_distance,
+            case_sensitive,
+        } => StringFuzzyCond::filter(p(&property)?, &value, max_distance, case_sensitive)?,
+        FilterJson::StringRegex {
+            property,
+            pattern,
+            case_sensitive,
+        } => StringRegexCond::filter(p(&property)?, &pattern, case_sensitive)?,
+
+        FilterJson::StringListContains {
+            property,
+            value,
+            case_sensitive,
+        } => StringListContainsCond::filter(p(&property)?, value.as_deref(), case_sensitive)?,
+        FilterJson::StringListRegex {
+            property,
+            pattern,
+            case_sensitive,
+        } => StringListRegexCond::filter(p(&property)?, &pattern, case_sensitive)?,
+        FilterJson::StringListBetween {
+            property,
+            lower,
+            upper,
+            case_sensitive,
+            all,
+        } => StringListBetweenCond::filter(
+            p(&property)?,
+            lower.as_deref(),
+            upper.as_deref(),
+            case_sensitive,
+            all,
+        )?,
+        FilterJson::StringListContainsValues {
+            property,
+            values,
+            case_sensitive,
+            all,
+        } => StringListContainsValuesCond::filter(
+            p(&property)?,
+            &str_values(&values),
+            case_sensitive,
+            all,
+        )?,
+
+        FilterJson::IsNull { property } => IsNullCond::filter(p(&property)?),
+        FilterJson::IsNotNull { property } => IsNotNullCond::filter(p(&property)?),
+
+        FilterJson::PropertyCompare {
+            property,
+            comparison,
+            other_property,
+        } => PropertyCompareCond::filter(p(&property)?, comparison, p(&other_property)?)?,
+        FilterJson::PropertyOffsetBetween {
+            property,
+            other_property,
+            lower,
+            upper,
+        } => {
+            PropertyOffsetBetweenCond::filter(p(&property)?, p(&other_property)?, lower.0, upper.0)?
+        }
+
+        FilterJson::Param {
+            name,
+            property,
+            op,
+            case_sensitive,
+        } => ParamCond::filter(&name, p(&property)?, op, case_sensitive)?,
+
+        FilterJson::And { filters } => AndCond::filter(decode_filters(isar, collection, filters)?),
+        FilterJson::Or { filters } => OrCond::filter(decode_filters(isar, collection, filters)?),
+        FilterJson::Not { filter } => NotCond::filter(decode_filter(isar, collection, *filter)?),
+        FilterJson::Static { value } => StaticCond::filter(value),
+        FilterJson::Link {
+            collection: link_collection,
+            link,
+            backlink,
+            filter,
+        } => {
+            let link_collection = if let Some(c) = isar.get_collection_by_name(&link_collection) {
+                c
+            } else {
+                return illegal_arg("Collection does not exist.");
+            };
+            let link_index = link_collection
+                .get_links()
+                .iter()
+                .position(|(name, _)| *name == link);
+            let link_index = if let Some(link_index) = link_index {
+                link_index
+            } else {
+                return illegal_arg("Link does not exist.");
+            };
+            let target_col_id = link_collection
+                .get_link_backlink(link_index, backlink)?
+                .get_target_col_id();
+            let target_collection = get_collection(isar, target_col_id)?;
+            let filter = decode_filter(isar, target_collection, *filter)?;
+            LinkCond::filter(link_collection, collection, link_index, backlink, filter)?
+        }
+        FilterJson::WhereClause { where_clauses } => {
+            let where_clauses = where_clauses
+                .into_iter()
+                .map(|wc| decode_where_clause(collection, wc))
+                .collect::<Result<_>>()?;
+            WhereClauseCond::filter(where_clauses, collection.get_oid_property())
+        }
+    };
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::data_type::DataType;
+    use crate::object::isar_object::IsarObject;
+    use crate::{col, ind, isar};
+    use itertools::Itertools;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_json() {
+        isar!(isar, col => col!(oid => DataType::Long, a => DataType::Int, f => DataType::Double, s => DataType::String; ind!(a)));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        let data = vec![
+            (1, 1, 1.5, Some("alice")),
+            (2, 2, IsarObject::NULL_DOUBLE, Some("bob")),
+            (3, 3, 3.5, None),
+            (4, 4, f64::NEG_INFINITY, Some("carl")),
+            (5, 5, 5.5, Some("Bea")),
+        ];
+        for (oid, a, f, s) in data {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int(a);
+            builder.write_double(f);
+            builder.write_string(s);
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+        let a = find_property(col, "a").unwrap();
+        let f = find_property(col, "f").unwrap();
+        let s = find_property(col, "s").unwrap();
+
+        let mut oids = |query: &Query| {
+            query
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+
+        let mut qb = col.new_query_builder();
+        qb.set_filter(AndCond::filter(vec![
+            IntBetweenCond::filter(a, 2, 5).unwrap(),
+            NotCond::filter(StringRegexCond::filter(s, "^b", false).unwrap()),
+            DoubleBetweenCond::filter(f, f64::NAN, f64::INFINITY).unwrap(),
+            StringInCond::filter(s, &[Some("dan"), None, Some("carl")], true).unwrap(),
+        ]));
+        qb.add_sort(a, Sort::Descending);
+        qb.set_limit(10);
+        let query = qb.build();
+        assert_eq!(oids(&query), vec![4, 3]);
+
+        let json = query.to_json(&isar, col).unwrap();
+        assert_eq!(json["whereClauses"][0]["type"], "index");
+        assert_eq!(json["whereClauses"][0]["index"][0]["name"], "a");
+        assert_eq!(json["sort"][0]["sort"], "descending");
+        let restored = Query::from_json(&isar, col, &json).unwrap();
+        assert_eq!(oids(&restored), vec![4, 3]);
+        assert_eq!(restored.to_json(&isar, col).unwrap(), json);
+
+        let filter = json!({
+            "type": "or",
+            "filters": [
+                {"type": "isNull", "property": "s"},
+                {"type": "doubleBetween", "property": "f", "lower": null, "upper": null},
+                {"type": "doubleBetween", "property": "f", "lower": "-Infinity", "upper": 0},
+            ]
+        });
+        let mut qb = col.new_query_builder();
+        qb.set_filter(Filter::from_json(&isar, col, &filter).unwrap());
+        assert_eq!(oids(&qb.build()), vec![2, 3, 4]);
+
+        let invalid_property = json!({"type": "isNull", "property": "x"});
+        assert!(matches!(
+            Filter::from_json(&isar, col, &invalid_property),
+            Err(IsarError::IllegalArg { .. })
+        ));
+        let invalid_type = json!({"type": "unknown"});
+        assert!(matches!(
+            Filter::from_json(&isar, col, &invalid_type),
+            Err(IsarError::InvalidJson {})
+        ));
+
+        txn.abort();
+        isar.close();
+    }
+}
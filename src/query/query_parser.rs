@@ -0,0 +1,687 @@
+use crate::collection::IsarCollection;
+use crate::error::{illegal_arg, IsarError, Result};
+use crate::object::data_type::DataType;
+use crate::object::isar_object::{IsarObject, Property};
+use crate::query::filter::*;
+use crate::query::{Query, Sort};
+
+/*
+Keywords are case-insensitive, strings use double quotes and string conditions are case-sensitive.
+Comparisons using `<`, `<=`, `>` and `>=` never match null values.
+
+query     := [filter] [SORT BY property [ASC | DESC] {, property [ASC | DESC]}]
+             [DISTINCT BY property {, property}] [OFFSET integer] [LIMIT integer]
+filter    := and {OR and}
+and       := unary {AND unary}
+unary     := NOT unary | ( filter ) | condition
+condition := property (= | != | < | <= | > | >=) value
+           | property BETWEEN value AND value
+           | property IN ( value {, value} )
+           | property IS [NOT] NULL
+           | property (STARTSWITH | ENDSWITH | CONTAINS | MATCHES | REGEX) value
+value     := number | string | TRUE | FALSE | NULL
+
+NOT and parentheses may be nested at most MAX_NESTING_DEPTH times.
+ */
+
+const MAX_NESTING_DEPTH: usize = 128;
+
+/// Parses `query` and resolves the property names using `collection`. Errors contain the byte
+/// offset within `query` at which they occurred.
+pub fn parse_query(collection: &IsarCollection, query: &str) -> Result<Query> {
+    let mut parser = Parser {
+        collection,
+        tokens: tokenize(query)?,
+        pos: 0,
+        depth: 0,
+    };
+    let mut qb = collection.new_query_builder();
+    if !parser.is_clause_end() {
+        qb.set_filter(parser.parse_or()?);
+    }
+    if parser.eat_keyword("SORT") {
+        parser.expect_keyword("BY")?;
+        loop {
+            let property = parser.parse_property()?;
+            let sort = if parser.eat_keyword("DESC") {
+                Sort::Descending
+            } else {
+                parser.eat_keyword("ASC");
+                Sort::Ascending
+            };
+            qb.add_sort(property, sort);
+            if !parser.eat_symbol(",") {
+                break;
+            }
+        }
+    }
+    if parser.eat_keyword("DISTINCT") {
+        parser.expect_keyword("BY")?;
+        loop {
+            qb.add_distinct(parser.parse_property()?, true);
+            if !parser.eat_symbol(",") {
+                break;
+            }
+        }
+    }
+    if parser.eat_keyword("OFFSET") {
+        qb.set_offset(parser.parse_usize()?);
+    }
+    if parser.eat_keyword("LIMIT") {
+        qb.set_limit(parser.parse_usize()?);
+    }
+    if parser.peek() != &Token::End {
+        return parser.unexpected();
+    }
+    Ok(qb.build())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(ident) => format!("`{}`", ident),
+            Token::Number(number) => format!("`{}`", number),
+            Token::Str(str) => format!("`{:?}`", str),
+            Token::Symbol(symbol) => format!("`{}`", symbol),
+            Token::End => "end of query".to_string(),
+        }
+    }
+}
+
+fn parse_error<T>(message: &str, position: usize) -> Result<T> {
+    Err(IsarError::QueryParseError {
+        message: message.to_string(),
+        position,
+    })
+}
+
+fn tokenize(query: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = vec![];
+    let mut chars = query.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Symbol("("),
+            ')' => Token::Symbol(")"),
+            ',' => Token::Symbol(","),
+            '=' => Token::Symbol("="),
+            '!' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Symbol("!="),
+            '<' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Symbol("<="),
+            '<' => Token::Symbol("<"),
+            '>' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Symbol(">="),
+            '>' => Token::Symbol(">"),
+            '"' => {
+                let mut str = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((escape_position, '\\')) => match chars.next() {
+                            Some((_, '"')) => str.push('"'),
+                            Some((_, '\\')) => str.push('\\'),
+                            Some((_, 'n')) => str.push('\n'),
+                            Some((_, 't')) => str.push('\t'),
+                            _ => return parse_error("Invalid escape sequence.", escape_position),
+                        },
+                        Some((_, c)) => str.push(c),
+                        None => return parse_error("Unterminated string.", position),
+                    }
+                }
+                Token::Str(str)
+            }
+            _ if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| {
+                    c.is_ascii_alphanumeric()
+                        || *c == '.'
+                        || ((*c == '-' || *c == '+') && number.ends_with(&['e', 'E'][..]))
+                }) {
+                    number.push(c);
+                }
+                Token::Number(number)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            _ => return parse_error(&format!("Unexpected character `{}`.", c), position),
+        };
+        tokens.push((token, position));
+    }
+    tokens.push((Token::End, query.len()));
+    Ok(tokens)
+}
+
+enum Literal {
+    Number(String),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+struct Value {
+    literal: Literal,
+    position: usize,
+}
+
+struct Parser<'a> {
+    collection: &'a IsarCollection,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.pos].clone();
+        if token.0 != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn unexpected<T>(&self) -> Result<T> {
+        let message = format!("Unexpected {}.", self.peek().describe());
+        parse_error(&message, self.position())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(ident) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            parse_error(&format!("Expected `{}`.", keyword), self.position())
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Token::Symbol(s) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            parse_error(&format!("Expected `{}`.", symbol), self.position())
+        }
+    }
+
+    fn is_clause_end(&self) -> bool {
+        self.peek() == &Token::End
+            || ["SORT", "DISTINCT", "OFFSET", "LIMIT"]
+                .iter()
+                .any(|keyword| self.is_keyword(keyword))
+    }
+
+    fn parse_property(&mut self) -> Result<Property> {
+        let position = self.position();
+        if let (Token::Ident(name), _) = self.advance() {
+            let property = self
+                .collection
+                .get_properties()
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, p)| *p);
+            if let Some(property) = property {
+                Ok(property)
+            } else {
+                parse_error(&format!("Unknown property `{}`.", name), position)
+            }
+        } else {
+            parse_error("Expected a property.", position)
+        }
+    }
+
+    fn parse_usize(&mut self) -> Result<usize> {
+        let (token, position) = self.advance();
+        match token {
+            Token::Number(number) => number
+                .parse()
+                .or_else(|_| parse_error("Expected a non-negative integer.", position)),
+            _ => parse_error("Expected a non-negative integer.", position),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        let (token, position) = self.advance();
+        let literal = match token {
+            Token::Number(number) => Literal::Number(number),
+            Token::Str(str) => Literal::Str(str),
+            Token::Ident(ident) if ident.eq_ignore_ascii_case("TRUE") => Literal::Bool(true),
+            Token::Ident(ident) if ident.eq_ignore_ascii_case("FALSE") => Literal::Bool(false),
+            Token::Ident(ident) if ident.eq_ignore_ascii_case("NULL") => Literal::Null,
+            _ => return parse_error("Expected a value.", position),
+        };
+        Ok(Value { literal, position })
+    }
+
+    fn parse_or(&mut self) -> Result<Filter> {
+        let mut filters = vec![self.parse_and()?];
+        while self.eat_keyword("OR") {
+            filters.push(self.parse_and()?);
+        }
+        if filters.len() == 1 {
+            Ok(filters.pop().unwrap())
+        } else {
+            Ok(OrCond::filter(filters))
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<Filter> {
+        let mut filters = vec![self.parse_unary()?];
+        while self.eat_keyword("AND") {
+            filters.push(self.parse_unary()?);
+        }
+        if filters.len() == 1 {
+            Ok(filters.pop().unwrap())
+        } else {
+            Ok(AndCond::filter(filters))
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter> {
+        let position = self.position();
+        if self.eat_keyword("NOT") {
+            self.enter(position)?;
+            let filter = NotCond::filter(self.parse_unary()?);
+            self.depth -= 1;
+            Ok(filter)
+        } else if self.eat_symbol("(") {
+            self.enter(position)?;
+            let filter = self.parse_or()?;
+            self.expect_symbol(")")?;
+            self.depth -= 1;
+            Ok(filter)
+        } else {
+            self.parse_condition()
+        }
+    }
+
+    fn enter(&mut self, position: usize) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            parse_error("Filter is nested too deeply.", position)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn parse_condition(&mut self) -> Result<Filter> {
+        let position = self.position();
+        let property = self.parse_property()?;
+        let (operator, operator_position) = self.advance();
+        let filter = match operator {
+            Token::Symbol(symbol) if symbol != "(" && symbol != ")" && symbol != "," => {
+                let value = self.parse_value()?;
+                Self::comparison(property, symbol, &value)
+            }
+            Token::Ident(keyword) => match keyword.to_uppercase().as_str() {
+                "BETWEEN" => {
+                    let lower = self.parse_value()?;
+                    self.expect_keyword("AND")?;
+                    let upper = self.parse_value()?;
+                    Self::between(property, &lower, &upper)
+                }
+                "IN" => {
+                    self.expect_symbol("(")?;
+                    let mut values = vec![self.parse_value()?];
+                    while self.eat_symbol(",") {
+                        values.push(self.parse_value()?);
+                    }
+                    self.expect_symbol(")")?;
+                    Self::is_in(property, &values)
+                }
+                "IS" => {
+                    let not = self.eat_keyword("NOT");
+                    self.expect_keyword("NULL")?;
+                    if not {
+                        Ok(IsNotNullCond::filter(property))
+                    } else {
+                        Ok(IsNullCond::filter(property))
+                    }
+                }
+                "STARTSWITH" | "ENDSWITH" | "CONTAINS" | "MATCHES" | "REGEX" => {
+                    let value = self.parse_value()?;
+                    Self::keyword_condition(property, &keyword.to_uppercase(), &value)
+                }
+                _ => parse_error("Expected an operator.", operator_position),
+            },
+            _ => parse_error("Expected an operator.", operator_position),
+        };
+        // errors of the filter constructors refer to the whole condition
+        filter.map_err(|e| match e {
+            IsarError::IllegalArg { message } => IsarError::QueryParseError { message, position },
+            e => e,
+        })
+    }
+
+    fn comparison(property: Property, operator: &str, value: &Value) -> Result<Filter> {
+        if let Literal::Null = value.literal {
+            return match operator {
+                "=" => Ok(IsNullCond::filter(property)),
+                "!=" => Ok(IsNotNullCond::filter(property)),
+                _ => parse_error(
+                    "NULL can only be compared using `=` and `!=`.",
+                    value.position,
+                ),
+            };
+        }
+        let equal = Self::between(property, value, value)?;
+        match operator {
+            "=" => Ok(equal),
+            "!=" => Ok(NotCond::filter(equal)),
+            "<=" => Self::at_most(property, value),
+            ">=" => Self::at_least(property, value),
+            "<" => Ok(AndCond::filter(vec![
+                Self::at_most(property, value)?,
+                NotCond::filter(equal),
+            ])),
+            _ => Ok(AndCond::filter(vec![
+                Self::at_least(property, value)?,
+                NotCond::filter(equal),
+            ])),
+        }
+    }
+
+    fn between(property: Property, lower: &Value, upper: &Value) -> Result<Filter> {
+        match property.data_type {
+            DataType::Byte => ByteBetweenCond::filter(property, lower.byte()?, upper.byte()?),
+            DataType::Int => IntBetweenCond::filter(property, lower.int()?, upper.int()?),
+            DataType::Long => LongBetweenCond::filter(property, lower.long()?, upper.long()?),
+            DataType::Float => FloatBetweenCond::filter(property, lower.float()?, upper.float()?),
+            DataType::Double => {
+                DoubleBetweenCond::filter(property, lower.double()?, upper.double()?)
+            }
+            DataType::String => {
+                let (lower, upper) = (lower.string()?, upper.string()?);
+                StringBetweenCond::filter(property, lower.as_deref(), upper.as_deref(), true)
+            }
+            _ => illegal_arg("Property does not support this filter."),
+        }
+    }
+
+    fn at_most(property: Property, value: &Value) -> Result<Filter> {
+        match property.data_type {
+            DataType::Byte => {
+                ByteBetweenCond::filter(property, IsarObject::NULL_BYTE + 1, value.byte()?)
+            }
+            DataType::Int => {
+                IntBetweenCond::filter(property, IsarObject::NULL_INT + 1, value.int()?)
+            }
+            DataType::Long => {
+                LongBetweenCond::filter(property, IsarObject::NULL_LONG + 1, value.long()?)
+            }
+            DataType::Float => {
+                FloatBetweenCond::filter(property, f32::NEG_INFINITY, value.float()?)
+            }
+            DataType::Double => {
+                DoubleBetweenCond::filter(property, f64::NEG_INFINITY, value.double()?)
+            }
+            DataType::String => {
+                StringBetweenCond::filter(property, Some(""), value.string()?.as_deref(), true)
+            }
+            _ => illegal_arg("Property does not support this filter."),
+        }
+    }
+
+    fn at_least(property: Property, value: &Value) -> Result<Filter> {
+        match property.data_type {
+            DataType::Byte => ByteBetweenCond::filter(property, value.byte()?, u8::MAX),
+            DataType::Int => IntBetweenCond::filter(property, value.int()?, i32::MAX),
+            DataType::Long => LongBetweenCond::filter(property, value.long()?, i64::MAX),
+            DataType::Float => FloatBetweenCond::filter(property, value.float()?, f32::INFINITY),
+            DataType::Double => DoubleBetweenCond::filter(property, value.double()?, f64::INFINITY),
+            // strings have no upper bound
+            DataType::String => {
+                let less = AndCond::filter(vec![
+                    Self::at_most(property, value)?,
+                    NotCond::filter(Self::between(property, value, value)?),
+                ]);
+                Ok(AndCond::filter(vec![
+                    IsNotNullCond::filter(property),
+                    NotCond::filter(less),
+                ]))
+            }
+            _ => illegal_arg("Property does not support this filter."),
+        }
+    }
+
+    fn is_in(property: Property, values: &[Value]) -> Result<Filter> {
+        match property.data_type {
+            DataType::Byte => {
+                let values = values
+                    .iter()
+                    .map(|v| v.byte())
+                    .collect::<Result<Vec<_>>>()?;
+                ByteInCond::filter(property, &values)
+            }
+            DataType::Int => {
+                let values = values.iter().map(|v| v.int()).collect::<Result<Vec<_>>>()?;
+                IntInCond::filter(property, &values)
+            }
+            DataType::Long => {
+                let values = values
+                    .iter()
+                    .map(|v| v.long())
+                    .collect::<Result<Vec<_>>>()?;
+                LongInCond::filter(property, &values)
+            }
+            DataType::Float => {
+                let values = values
+                    .iter()
+                    .map(|v| v.float())
+                    .collect::<Result<Vec<_>>>()?;
+                FloatInCond::filter(property, &values)
+            }
+            DataType::Double => {
+                let values = values
+                    .iter()
+                    .map(|v| v.double())
+                    .collect::<Result<Vec<_>>>()?;
+                DoubleInCond::filter(property, &values)
+            }
+            DataType::String => {
+                let values = values
+                    .iter()
+                    .map(|v| v.string())
+                    .collect::<Result<Vec<_>>>()?;
+                let values = values.iter().map(|v| v.as_deref()).collect::<Vec<_>>();
+                StringInCond::filter(property, &values, true)
+            }
+            _ => illegal_arg("Property does not support this filter."),
+        }
+    }
+
+    fn keyword_condition(property: Property, keyword: &str, value: &Value) -> Result<Filter> {
+        match (keyword, property.data_type) {
+            ("CONTAINS", DataType::ByteList) => {
+                ByteListContainsValuesCond::filter(property, &[value.byte()?], false)
+            }
+            ("CONTAINS", DataType::IntList) => {
+                IntListContainsValuesCond::filter(property, &[value.int()?], false)
+            }
+            ("CONTAINS", DataType::LongList) => {
+                LongListContainsValuesCond::filter(property, &[value.long()?], false)
+            }
+            ("CONTAINS", DataType::FloatList) => {
+                FloatListContainsValuesCond::filter(property, &[value.float()?], false)
+            }
+            ("CONTAINS", DataType::DoubleList) => {
+                DoubleListContainsValuesCond::filter(property, &[value.double()?], false)
+            }
+            ("CONTAINS", DataType::StringList) => {
+                let value = value.string()?;
+                StringListContainsValuesCond::filter(property, &[value.as_deref()], true, false)
+            }
+            ("CONTAINS", _) => {
+                StringContainsCond::filter(property, value.string()?.as_deref(), true)
+            }
+            ("STARTSWITH", _) => {
+                StringStartsWithCond::filter(property, value.string()?.as_deref(), true)
+            }
+            ("ENDSWITH", _) => {
+                StringEndsWithCond::filter(property, value.string()?.as_deref(), true)
+            }
+            ("MATCHES", _) => StringMatchesCond::filter(property, value.string()?.as_deref(), true),
+            (_, data_type) => {
+                let pattern = if let Some(pattern) = value.string()? {
+                    pattern
+                } else {
+                    return parse_error("Expected a pattern.", value.position);
+                };
+                if data_type == DataType::StringList {
+                    StringListRegexCond::filter(property, &pattern, true)
+                } else {
+                    StringRegexCond::filter(property, &pattern, true)
+                }
+            }
+        }
+    }
+}
+
+impl Value {
+    fn number<T: std::str::FromStr>(&self, null: T, message: &str) -> Result<T> {
+        match &self.literal {
+            Literal::Number(number) => number
+                .parse()
+                .or_else(|_| parse_error(message, self.position)),
+            Literal::Null => Ok(null),
+            _ => parse_error(message, self.position),
+        }
+    }
+
+    fn byte(&self) -> Result<u8> {
+        match self.literal {
+            Literal::Bool(true) => Ok(IsarObject::TRUE_BYTE),
+            Literal::Bool(false) => Ok(IsarObject::FALSE_BYTE),
+            _ => self.number(IsarObject::NULL_BYTE, "Expected a byte or boolean."),
+        }
+    }
+
+    fn int(&self) -> Result<i32> {
+        self.number(IsarObject::NULL_INT, "Expected a 32 bit integer.")
+    }
+
+    fn long(&self) -> Result<i64> {
+        self.number(IsarObject::NULL_LONG, "Expected a 64 bit integer.")
+    }
+
+    fn float(&self) -> Result<f32> {
+        self.number(IsarObject::NULL_FLOAT, "Expected a number.")
+    }
+
+    fn double(&self) -> Result<f64> {
+        self.number(IsarObject::NULL_DOUBLE, "Expected a number.")
+    }
+
+    fn string(&self) -> Result<Option<String>> {
+        match &self.literal {
+            Literal::Str(str) => Ok(Some(str.clone())),
+            Literal::Null => Ok(None),
+            _ => parse_error("Expected a string.", self.position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{col, isar};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_parse_query() {
+        isar!(isar, col => col!(oid => DataType::Long, age => DataType::Int, name => DataType::String, tags => DataType::StringList));
+        let mut txn = isar.begin_txn(true, false).unwrap();
+        let data = vec![
+            (1, 25, Some("alice"), Some(vec![Some("a"), Some("b")])),
+            (2, 35, Some("bob"), Some(vec![Some("b")])),
+            (3, 40, Some("anna"), Some(vec![])),
+            (4, IsarObject::NULL_INT, None, None),
+        ];
+        for (oid, age, name, tags) in data {
+            let mut builder = col.new_object_builder(None);
+            builder.write_long(oid);
+            builder.write_int(age);
+            builder.write_string(name);
+            builder.write_string_list(tags.as_deref());
+            col.put(&mut txn, builder.finish()).unwrap();
+        }
+
+        let mut oids = |query: &str| {
+            parse_query(col, query)
+                .unwrap()
+                .find_all_vec(&mut txn)
+                .unwrap()
+                .iter()
+                .map(|o| o.read_long(col.get_oid_property()))
+                .collect_vec()
+        };
+        assert_eq!(oids(""), vec![1, 2, 3, 4]);
+        assert_eq!(oids("age > 30 and name STARTSWITH \"a\""), vec![3]);
+        assert_eq!(oids("age >= 25 SORT BY age DESC LIMIT 2"), vec![3, 2]);
+        assert_eq!(oids("age < 40 OFFSET 1"), vec![2]);
+        assert_eq!(
+            oids("NOT (age BETWEEN 30 AND 40) OR tags CONTAINS \"b\""),
+            vec![1, 2, 4]
+        );
+        assert_eq!(oids("name IN (\"bob\", NULL)"), vec![2, 4]);
+        assert_eq!(oids("age IS NULL"), vec![4]);
+        assert_eq!(oids("name != NULL"), vec![1, 2, 3]);
+        assert_eq!(oids("name > \"alice\""), vec![2, 3]);
+
+        let error = |query: &str| match parse_query(col, query) {
+            Err(IsarError::QueryParseError { position, .. }) => position,
+            _ => panic!(),
+        };
+        assert_eq!(error("agee > 3"), 0);
+        assert_eq!(error("age > \"x\""), 6);
+        assert_eq!(error("name = \"abc"), 7);
+        assert_eq!(error("name > 3"), 7);
+        assert_eq!(error("age > 3 LIMIT"), 13);
+        assert_eq!(error("age = 1 age = 2"), 8);
+        assert_eq!(error("tags > \"a\""), 0);
+        let nested = format!("{}age = 1{}", "(".repeat(129), ")".repeat(129));
+        assert_eq!(error(&nested), 128);
+        assert_eq!(error(&"NOT ".repeat(200)), 512);
+        let nested = format!("{}age = 1{}", "(".repeat(128), ")".repeat(128));
+        assert!(parse_query(col, &nested).is_ok());
+
+        txn.abort();
+        isar.close();
+    }
+}
@@ -1,8 +1,11 @@
 use crate::error::Result;
 use crate::object::isar_object::IsarObject;
-use crate::query::filter::FilterCursors;
+use crate::object::property_value::PropertyValue;
+use crate::query::filter::{Filter, FilterCursors};
 use crate::query::id_where_clause::IdWhereClause;
 use crate::query::index_where_clause::IndexWhereClause;
+use crate::query::intersection_where_clause::IntersectionWhereClause;
+use crate::query::ngram_where_clause::NGramWhereClause;
 use crate::txn::Cursors;
 use hashbrown::HashSet;
 
@@ -10,6 +13,8 @@ use hashbrown::HashSet;
 pub(crate) enum WhereClause {
     Id(IdWhereClause),
     Index(IndexWhereClause),
+    NGram(NGramWhereClause),
+    Intersection(IntersectionWhereClause),
 }
 
 impl WhereClause {
@@ -17,6 +22,61 @@ impl WhereClause {
         match self {
             WhereClause::Id(wc) => wc.id_matches(id),
             WhereClause::Index(wc) => wc.object_matches(object),
+            WhereClause::NGram(wc) => wc.object_matches(object),
+            WhereClause::Intersection(wc) => wc.object_matches(id, object),
+        }
+    }
+
+    pub fn is_usable(&self, filter: Option<&Filter>) -> bool {
+        match self {
+            WhereClause::Index(wc) => wc.is_usable(filter),
+            WhereClause::Intersection(wc) => wc
+                .get_groups()
+                .iter()
+                .flatten()
+                .all(|wc| wc.is_usable(filter)),
+            _ => true,
+        }
+    }
+
+    /// Returns whether the where clause contains the parameter `name` and fails if `value` cannot
+    /// be bound to it.
+    pub fn check_param(&self, name: &str, value: &PropertyValue) -> Result<bool> {
+        match self {
+            WhereClause::Id(wc) => wc.check_param(name, value),
+            WhereClause::Index(wc) => wc.check_param(name, value),
+            WhereClause::NGram(_) => Ok(false),
+            WhereClause::Intersection(wc) => {
+                let mut found = false;
+                for wc in wc.groups.iter().flatten() {
+                    found |= wc.check_param(name, value)?;
+                }
+                Ok(found)
+            }
+        }
+    }
+
+    /// Binds `value` to the parameter `name`. The value has to be checked using `check_param()`.
+    pub fn bind_param(&mut self, name: &str, value: &PropertyValue) -> Result<()> {
+        match self {
+            WhereClause::Id(wc) => wc.bind_param(name, value),
+            WhereClause::Index(wc) => wc.bind_param(name, value)?,
+            WhereClause::NGram(_) => {}
+            WhereClause::Intersection(wc) => {
+                for wc in wc.groups.iter_mut().flatten() {
+                    wc.bind_param(name, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_multi_entry(&self) -> bool {
+        match self {
+            WhereClause::Id(_) => false,
+            WhereClause::Index(wc) => wc.is_multi_entry(),
+            WhereClause::NGram(_) => false,
+            WhereClause::Intersection(_) => false,
         }
     }
 
@@ -40,6 +100,16 @@ impl WhereClause {
                 result_ids,
                 |_, _, o| callback(&mut filter_cursors, o),
             ),
+            WhereClause::NGram(wc) => {
+                wc.iter(&mut cursors.data, &mut cursors.index, result_ids, |_, o| {
+                    callback(&mut filter_cursors, o)
+                })
+            }
+            WhereClause::Intersection(wc) => {
+                wc.iter(&mut cursors.data, &mut cursors.index, result_ids, |_, o| {
+                    callback(&mut filter_cursors, o)
+                })
+            }
         }
     }
 }
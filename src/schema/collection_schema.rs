@@ -1,10 +1,16 @@
 use crate::collection::IsarCollection;
-use crate::error::{schema_error, Result};
+use crate::error::{schema_error, IsarError, Result};
+use crate::index::analyzer::{Analyzer, ENGLISH_STOP_WORDS};
 use crate::index::{Index, IndexProperty};
 use crate::link::Link;
+use crate::object::collation::Collation;
 use crate::object::data_type::DataType;
 use crate::object::isar_object::Property;
 use crate::object::object_info::ObjectInfo;
+use crate::query::filter::{
+    AndCond, ByteBetweenCond, DoubleBetweenCond, Filter, FloatBetweenCond, IntBetweenCond,
+    LongBetweenCond, NotCond, OrCond, StaticCond, StringBetweenCond,
+};
 use enum_ordinalize::Ordinalize;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -34,6 +40,58 @@ pub enum IndexType {
     Value,
     Hash,
     Words,
+    FullText,
+    NGram,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum StemmerLanguage {
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Russian,
+    Spanish,
+    Swedish,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct AnalyzerSchema {
+    #[serde(default)]
+    pub(crate) normalize: bool,
+    #[serde(default, rename = "foldDiacritics")]
+    pub(crate) fold_diacritics: bool,
+    #[serde(default)]
+    pub(crate) stemmer: Option<StemmerLanguage>,
+    #[serde(default, rename = "stopWords")]
+    pub(crate) stop_words: Vec<String>,
+}
+
+impl AnalyzerSchema {
+    pub fn new(
+        normalize: bool,
+        fold_diacritics: bool,
+        stemmer: Option<StemmerLanguage>,
+        stop_words: Vec<String>,
+    ) -> AnalyzerSchema {
+        AnalyzerSchema {
+            normalize,
+            fold_diacritics,
+            stemmer,
+            stop_words,
+        }
+    }
+
+    pub fn english() -> AnalyzerSchema {
+        let stop_words = ENGLISH_STOP_WORDS.iter().map(|w| w.to_string()).collect();
+        AnalyzerSchema::new(true, true, Some(StemmerLanguage::English), stop_words)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
@@ -44,6 +102,10 @@ pub struct IndexPropertySchema {
     pub(crate) index_type: IndexType,
     #[serde(rename = "caseSensitive")]
     pub(crate) case_sensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) analyzer: Option<AnalyzerSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) collation: Option<Collation>,
 }
 
 impl IndexPropertySchema {
@@ -56,16 +118,190 @@ impl IndexPropertySchema {
             name: name.to_string(),
             index_type,
             case_sensitive,
+            analyzer: None,
+            collation: None,
+        }
+    }
+
+    pub fn collated(name: &str, case_sensitive: bool, collation: Collation) -> IndexPropertySchema {
+        IndexPropertySchema {
+            name: name.to_string(),
+            index_type: IndexType::Value,
+            case_sensitive: Some(case_sensitive),
+            analyzer: None,
+            collation: Some(collation),
+        }
+    }
+
+    pub fn full_text(
+        name: &str,
+        case_sensitive: bool,
+        analyzer: AnalyzerSchema,
+    ) -> IndexPropertySchema {
+        IndexPropertySchema {
+            name: name.to_string(),
+            index_type: IndexType::FullText,
+            case_sensitive: Some(case_sensitive),
+            analyzer: Some(analyzer),
+            collation: None,
         }
     }
 }
 
+/// Predicate of a partial index. Only objects matching it are indexed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IndexFilterSchema {
+    IntBetween {
+        property: String,
+        lower: i64,
+        upper: i64,
+    },
+    DoubleBetween {
+        property: String,
+        lower: f64,
+        upper: f64,
+    },
+    StringEqual {
+        property: String,
+        value: Option<String>,
+        #[serde(rename = "caseSensitive")]
+        case_sensitive: bool,
+    },
+    And {
+        filters: Vec<IndexFilterSchema>,
+    },
+    Or {
+        filters: Vec<IndexFilterSchema>,
+    },
+    Not {
+        filter: Box<IndexFilterSchema>,
+    },
+}
+
+impl IndexFilterSchema {
+    fn verify(&self, properties: &[PropertySchema]) -> Result<()> {
+        let find_property = |name: &str| -> Result<DataType> {
+            properties
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| p.data_type)
+                .ok_or(IsarError::SchemaError {
+                    message: "Index filter property does not exist".to_string(),
+                })
+        };
+        match self {
+            IndexFilterSchema::IntBetween { property, .. } => {
+                let data_type = find_property(property)?;
+                if data_type != DataType::Byte
+                    && data_type != DataType::Int
+                    && data_type != DataType::Long
+                {
+                    schema_error("Illegal index filter data type")?;
+                }
+            }
+            IndexFilterSchema::DoubleBetween { property, .. } => {
+                let data_type = find_property(property)?;
+                if data_type != DataType::Float && data_type != DataType::Double {
+                    schema_error("Illegal index filter data type")?;
+                }
+            }
+            IndexFilterSchema::StringEqual { property, .. } => {
+                if find_property(property)? != DataType::String {
+                    schema_error("Illegal index filter data type")?;
+                }
+            }
+            IndexFilterSchema::And { filters } | IndexFilterSchema::Or { filters } => {
+                for filter in filters {
+                    filter.verify(properties)?;
+                }
+            }
+            IndexFilterSchema::Not { filter } => filter.verify(properties)?,
+        }
+        Ok(())
+    }
+
+    fn to_filter(&self, properties: &[(String, Property)]) -> Result<Filter> {
+        let find_property = |name: &str| {
+            let (_, property) = properties.iter().find(|(n, _)| n == name).unwrap();
+            *property
+        };
+        let filter = match self {
+            IndexFilterSchema::IntBetween {
+                property,
+                lower,
+                upper,
+            } => {
+                let property = find_property(property);
+                match property.data_type {
+                    DataType::Byte if *lower > u8::MAX as i64 || *upper < 0 => {
+                        StaticCond::filter(false)
+                    }
+                    DataType::Byte => ByteBetweenCond::filter(
+                        property,
+                        (*lower).max(0) as u8,
+                        (*upper).min(u8::MAX as i64) as u8,
+                    )?,
+                    DataType::Int if *lower > i32::MAX as i64 || *upper < i32::MIN as i64 => {
+                        StaticCond::filter(false)
+                    }
+                    DataType::Int => IntBetweenCond::filter(
+                        property,
+                        (*lower).max(i32::MIN as i64) as i32,
+                        (*upper).min(i32::MAX as i64) as i32,
+                    )?,
+                    _ => LongBetweenCond::filter(property, *lower, *upper)?,
+                }
+            }
+            IndexFilterSchema::DoubleBetween {
+                property,
+                lower,
+                upper,
+            } => {
+                let property = find_property(property);
+                if property.data_type == DataType::Float {
+                    FloatBetweenCond::filter(property, *lower as f32, *upper as f32)?
+                } else {
+                    DoubleBetweenCond::filter(property, *lower, *upper)?
+                }
+            }
+            IndexFilterSchema::StringEqual {
+                property,
+                value,
+                case_sensitive,
+            } => {
+                let property = find_property(property);
+                let value = value.as_deref();
+                StringBetweenCond::filter(property, value, value, *case_sensitive)?
+            }
+            IndexFilterSchema::And { filters } => {
+                let filters = filters
+                    .iter()
+                    .map(|f| f.to_filter(properties))
+                    .collect::<Result<Vec<_>>>()?;
+                AndCond::filter(filters)
+            }
+            IndexFilterSchema::Or { filters } => {
+                let filters = filters
+                    .iter()
+                    .map(|f| f.to_filter(properties))
+                    .collect::<Result<Vec<_>>>()?;
+                OrCond::filter(filters)
+            }
+            IndexFilterSchema::Not { filter } => NotCond::filter(filter.to_filter(properties)?),
+        };
+        Ok(filter)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct IndexSchema {
     pub(crate) id: Option<u16>,
     pub(crate) properties: Vec<IndexPropertySchema>,
     pub(crate) unique: bool,
     pub(crate) replace: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) filter: Option<IndexFilterSchema>,
 }
 
 impl IndexSchema {
@@ -75,6 +311,22 @@ impl IndexSchema {
             properties,
             unique,
             replace,
+            filter: None,
+        }
+    }
+
+    pub fn partial(
+        properties: Vec<IndexPropertySchema>,
+        unique: bool,
+        replace: bool,
+        filter: IndexFilterSchema,
+    ) -> IndexSchema {
+        IndexSchema {
+            id: None,
+            properties,
+            unique,
+            replace,
+            filter: Some(filter),
         }
     }
 }
@@ -178,23 +430,56 @@ impl CollectionSchema {
                 }
                 let property = property.unwrap();
 
-                if property.data_type.is_dynamic() && property.data_type != DataType::String {
+                if property.data_type == DataType::ByteList {
                     schema_error("Illegal index data type")?;
                 }
 
-                if property.data_type != DataType::String
-                    && index_property.index_type != IndexType::Value
-                {
+                let is_string = property.data_type == DataType::String
+                    || property.data_type == DataType::StringList;
+                if !is_string && index_property.index_type != IndexType::Value {
                     schema_error("Non string indexes must use IndexType::Value")?;
                 }
-                if (property.data_type == DataType::String)
-                    != index_property.case_sensitive.is_some()
-                {
+                if is_string != index_property.case_sensitive.is_some() {
                     schema_error("Only String indexes must have case sensitivity.")?;
                 }
 
+                if property.data_type.is_list() {
+                    if index.properties.len() != 1 {
+                        schema_error("List indexes must not be composite.")?;
+                    }
+                    if index_property.index_type != IndexType::Value
+                        && index_property.index_type != IndexType::Hash
+                    {
+                        schema_error("List indexes must use IndexType::Value or IndexType::Hash")?;
+                    }
+                }
+
+                if index_property.index_type == IndexType::FullText
+                    || index_property.index_type == IndexType::NGram
+                {
+                    if index.properties.len() != 1 {
+                        schema_error("Full-text and n-gram indexes must not be composite.")?;
+                    }
+                    if index.unique {
+                        schema_error("Full-text and n-gram indexes must not be unique.")?;
+                    }
+                    if index.filter.is_some() {
+                        schema_error("Full-text and n-gram indexes must not be partial.")?;
+                    }
+                }
+                if index_property.collation.is_some()
+                    && (!is_string || index_property.index_type != IndexType::Value)
+                {
+                    schema_error("Only string value indexes may have a collation.")?;
+                }
+                if index_property.index_type != IndexType::FullText
+                    && index_property.analyzer.is_some()
+                {
+                    schema_error("Only full-text indexes may have an analyzer.")?;
+                }
+
                 match index_property.index_type {
-                    IndexType::Value | IndexType::Words => {
+                    IndexType::Value | IndexType::Words | IndexType::FullText => {
                         if i != index.properties.len() - 1 {
                             schema_error(
                                 "Value and word string indexes must only be at the end of a composite index.",
@@ -206,6 +491,12 @@ impl CollectionSchema {
             }
         }
 
+        for index in &self.indexes {
+            if let Some(filter) = &index.filter {
+                filter.verify(&self.properties)?;
+            }
+        }
+
         for link in &self.links {
             if link.name.is_empty() {
                 schema_error("Empty link names are not allowed")?;
@@ -251,6 +542,10 @@ impl CollectionSchema {
         self.indexes
             .iter()
             .map(|index| {
+                let filter = index
+                    .filter
+                    .as_ref()
+                    .map(|f| f.to_filter(properties).unwrap());
                 let properties = index
                     .properties
                     .iter()
@@ -259,9 +554,22 @@ impl CollectionSchema {
                             .iter()
                             .find(|(name, _)| name == &ips.name)
                             .unwrap();
-                        IndexProperty::new(*property, ips.index_type, ips.case_sensitive)
+                        IndexProperty::new(
+                            *property,
+                            ips.index_type,
+                            ips.case_sensitive,
+                            ips.collation,
+                        )
                     })
                     .collect_vec();
+                let analyzer = index
+                    .properties
+                    .first()
+                    .filter(|ips| ips.index_type == IndexType::FullText)
+                    .map(|ips| {
+                        let schema = ips.analyzer.clone().unwrap_or_default();
+                        Analyzer::from_schema(&schema, ips.case_sensitive.unwrap())
+                    });
 
                 Index::new(
                     index.id.unwrap(),
@@ -269,6 +577,8 @@ impl CollectionSchema {
                     properties,
                     index.unique,
                     index.replace,
+                    analyzer,
+                    filter,
                 )
             })
             .collect()
@@ -329,7 +639,10 @@ impl CollectionSchema {
         index2: &IndexSchema,
         properties2: &[PropertySchema],
     ) -> bool {
-        if index1.unique != index2.unique || index1.properties.len() != index2.properties.len() {
+        if index1.unique != index2.unique
+            || index1.filter != index2.filter
+            || index1.properties.len() != index2.properties.len()
+        {
             return false;
         }
         for (ip1, ip2) in index1.properties.iter().zip(index2.properties.iter()) {
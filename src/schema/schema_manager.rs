@@ -1,14 +1,19 @@
 use crate::error::{IsarError, Result};
+use crate::index::index_stats::write_object_count;
 use crate::lmdb::cursor::Cursor;
 use crate::lmdb::{ByteKey, IntKey, MIN_ID};
 use crate::query::Sort;
 use crate::schema::collection_migrator::CollectionMigrator;
+use crate::schema::collection_schema::IndexType;
 use crate::schema::Schema;
 use crate::txn::Cursors;
 use crate::{collection::IsarCollection, lmdb::MAX_ID, query::id_where_clause::IdWhereClause};
+use itertools::Itertools;
 use std::convert::TryInto;
 
-const ISAR_VERSION: u64 = 1;
+const ISAR_VERSION: u64 = 2;
+/// Version 1 stored the keys of words indexes without the index prefix.
+const LEGACY_WORD_KEYS_VERSION: u64 = 1;
 const INFO_VERSION_KEY: ByteKey = ByteKey::new(b"version");
 const INFO_SCHEMA_KEY: ByteKey = ByteKey::new(b"schema");
 
@@ -16,6 +21,7 @@ pub(crate) struct SchemaManger<'env> {
     info_cursor: Cursor<'env>,
     cursors: Cursors<'env>,
     cursors2: Cursors<'env>,
+    legacy_word_keys: bool,
 }
 
 impl<'env> SchemaManger<'env> {
@@ -24,6 +30,7 @@ impl<'env> SchemaManger<'env> {
             info_cursor,
             cursors,
             cursors2,
+            legacy_word_keys: false,
         }
     }
 
@@ -31,7 +38,11 @@ impl<'env> SchemaManger<'env> {
         let version = self.info_cursor.move_to(INFO_VERSION_KEY)?;
         if let Some((_, version)) = version {
             let version_num = u64::from_le_bytes(version.try_into().unwrap());
-            if version_num != ISAR_VERSION {
+            if version_num == LEGACY_WORD_KEYS_VERSION {
+                self.legacy_word_keys = true;
+                let version_bytes = &ISAR_VERSION.to_le_bytes();
+                self.info_cursor.put(INFO_VERSION_KEY, version_bytes)?;
+            } else if version_num != ISAR_VERSION {
                 return Err(IsarError::VersionError {});
             }
         } else {
@@ -104,9 +115,13 @@ impl<'env> SchemaManger<'env> {
             .filter(|existing| !collections.iter().any(|c| existing.get_id() == c.get_id()));
 
         for col in removed_collections {
+            if self.legacy_word_keys {
+                self.migrate_word_keys(col)?;
+            }
             for index in col.get_indexes() {
                 index.clear(&mut self.cursors)?;
             }
+            write_object_count(&mut self.cursors.info, col.get_id(), 0)?;
             IdWhereClause::new(col, MIN_ID, MAX_ID, Sort::Ascending).iter(
                 &mut self.cursors.data,
                 None,
@@ -123,6 +138,9 @@ impl<'env> SchemaManger<'env> {
                 .find(|existing| existing.get_id() == col.get_id());
 
             if let Some(existing) = existing {
+                if self.legacy_word_keys {
+                    self.migrate_word_keys(existing)?;
+                }
                 let migrator = CollectionMigrator::create(col, existing);
                 migrator.migrate(&mut self.cursors, &mut self.cursors2)?;
             }
@@ -130,4 +148,32 @@ impl<'env> SchemaManger<'env> {
 
         Ok(())
     }
+
+    /// Rebuilds the words indexes of `collection` using prefixed keys.
+    fn migrate_word_keys(&mut self, collection: &IsarCollection) -> Result<()> {
+        let word_indexes = collection
+            .get_indexes()
+            .iter()
+            .filter(|index| index.properties[0].index_type == IndexType::Words)
+            .collect_vec();
+        if word_indexes.is_empty() {
+            return Ok(());
+        }
+        let cursors2 = &mut self.cursors2;
+        collection.new_query_builder().build().find_all_internal(
+            &mut self.cursors,
+            false,
+            |object| {
+                let oid = object.read_long(collection.get_oid_property());
+                for index in &word_indexes {
+                    index.delete_legacy_word_keys(cursors2, oid, object)?;
+                    index.create_for_object(cursors2, oid, object, |cursors, id| {
+                        collection.delete_internal(cursors, true, None, id)?;
+                        Ok(())
+                    })?;
+                }
+                Ok(true)
+            },
+        )
+    }
 }
@@ -18,6 +18,7 @@ pub(crate) struct Cursors<'a> {
     pub(crate) data2: Cursor<'a>,
     pub(crate) index: Cursor<'a>,
     pub(crate) links: Cursor<'a>,
+    pub(crate) info: Cursor<'a>,
 }
 
 impl<'a> IsarTxn<'a> {